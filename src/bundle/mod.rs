@@ -0,0 +1,71 @@
+use crate::layout::sanitize_segment;
+use crate::models::Repository;
+use crate::Result;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// File name of the manifest written next to the bundles
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Index of the bundles in an export directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub created_at: DateTime<Utc>,
+    pub entries: Vec<BundleEntry>,
+}
+
+/// One repository's bundle and the metadata needed to register it on import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub repository: Repository,
+    /// Bundle file name, relative to the manifest
+    pub bundle: String,
+    /// Whether the bundle only contains history since a previous export
+    pub incremental: bool,
+    /// Refs and commits contained in the bundle
+    pub refs: BTreeMap<String, String>,
+    /// Commits the importing side must already have (incremental bundles only)
+    pub prerequisites: Vec<String>,
+}
+
+impl Default for BundleManifest {
+    fn default() -> Self {
+        Self {
+            created_at: Utc::now(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl BundleManifest {
+    /// Read the manifest from an export directory
+    pub fn read(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid manifest {}", path.display()))
+    }
+
+    /// Write the manifest into an export directory
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(MANIFEST_FILE);
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Bundle file name for a repository, unique per export run
+pub fn bundle_file_name(repo: &Repository, at: DateTime<Utc>) -> String {
+    format!(
+        "{}-{}.bundle",
+        sanitize_segment(&repo.full_name.replace('/', "__")),
+        at.format("%Y%m%d%H%M%S")
+    )
+}
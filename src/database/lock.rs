@@ -0,0 +1,123 @@
+use crate::Result;
+use anyhow::Context;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::PathBuf;
+
+/// Advisory lock every super-clone process holds on its database while it runs.
+///
+/// Rows left `cloning` or `updating` can only be rolled back safely when the
+/// run that set them is gone. A process that gets this lock exclusively knows
+/// no other run uses the database; the operating system drops the lock of a
+/// process that dies, so a crashed run never keeps it.
+#[derive(Debug)]
+pub struct RunLock {
+    /// `None` for in-memory databases, which no other process can open
+    file: Option<File>,
+    exclusive: bool,
+}
+
+impl RunLock {
+    /// Take the lock for a database, exclusively when no other run holds it
+    pub fn acquire(database_url: &str) -> Result<Self> {
+        let Some(path) = lock_path(database_url) else {
+            return Ok(Self {
+                file: None,
+                exclusive: true,
+            });
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+
+        let exclusive = match file.try_lock() {
+            Ok(()) => true,
+            Err(TryLockError::WouldBlock) => {
+                // Waits only while another run cleans up at its start
+                file.lock_shared()?;
+                false
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock {}", path.display()))
+            }
+        };
+        Ok(Self {
+            file: Some(file),
+            exclusive,
+        })
+    }
+
+    /// Whether this is the only run using the database
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    /// Let other runs start once the start-up clean-up is done
+    pub fn share(&mut self) -> Result<()> {
+        if let (Some(file), true) = (&self.file, self.exclusive) {
+            file.unlock()?;
+            file.lock_shared()?;
+            self.exclusive = false;
+        }
+        Ok(())
+    }
+}
+
+/// `<database>.lock` next to a database file, `None` for in-memory databases
+fn lock_path(database_url: &str) -> Option<PathBuf> {
+    let path = database_url
+        .strip_prefix("sqlite://")
+        .unwrap_or(database_url);
+    let path = path.split('?').next().unwrap_or(path);
+    if path.is_empty() || path == ":memory:" || database_url.starts_with("sqlite::memory:") {
+        return None;
+    }
+    let mut lock = PathBuf::from(path).into_os_string();
+    lock.push(".lock");
+    Some(PathBuf::from(lock))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_path() {
+        assert_eq!(
+            lock_path("/home/me/.super-clone/repositories.db"),
+            Some(PathBuf::from("/home/me/.super-clone/repositories.db.lock"))
+        );
+        assert_eq!(
+            lock_path("sqlite:///tmp/r.db?mode=rwc"),
+            Some(PathBuf::from("/tmp/r.db.lock"))
+        );
+        assert_eq!(lock_path(":memory:"), None);
+    }
+
+    #[test]
+    fn test_only_a_lone_run_is_exclusive() {
+        let db = std::env::temp_dir().join(format!("super-clone-lock-{}.db", uuid::Uuid::new_v4()));
+        let db = db.to_string_lossy().to_string();
+
+        let mut first = RunLock::acquire(&db).unwrap();
+        assert!(first.is_exclusive());
+        first.share().unwrap();
+        assert!(!first.is_exclusive());
+
+        // Another run starts while the first one is still going
+        let second = RunLock::acquire(&db).unwrap();
+        assert!(!second.is_exclusive());
+        drop(first);
+        assert!(!RunLock::acquire(&db).unwrap().is_exclusive());
+
+        // Once every other run is gone the next one may clean up after them
+        drop(second);
+        assert!(RunLock::acquire(&db).unwrap().is_exclusive());
+        std::fs::remove_file(format!("{}.lock", db)).unwrap();
+    }
+}
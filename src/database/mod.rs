@@ -1,6 +1,43 @@
-use crate::models::{CloneStatus, Repository};
+use crate::models::{
+    BundleExport, CloneStatus, Job, JobStatus, JobTask, Mirror, Operation, OperationOutcome,
+    Repository, RepositorySource, TagRule, TaskStatus,
+};
 use crate::Result;
-use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+use sqlx::{sqlite::SqlitePoolOptions, Connection, SqliteConnection, SqlitePool};
+use std::collections::{HashMap, HashSet};
+
+mod lock;
+pub use lock::RunLock;
+
+/// Columns of the repositories table. `seq` is the stable key the full-text
+/// index refers to; everything else uses the TEXT `id`.
+const REPOSITORIES_COLUMNS: &str = "
+    seq INTEGER PRIMARY KEY,
+    id TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    full_name TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    clone_url_https TEXT NOT NULL,
+    clone_url_ssh TEXT NOT NULL,
+    description TEXT,
+    is_private BOOLEAN NOT NULL DEFAULT FALSE,
+    local_path TEXT,
+    status TEXT NOT NULL DEFAULT 'not_cloned',
+    source TEXT NOT NULL DEFAULT 'discovered',
+    kind TEXT NOT NULL DEFAULT 'repository',
+    has_wiki BOOLEAN NOT NULL DEFAULT FALSE,
+    parent_id TEXT,
+    clone_depth INTEGER,
+    clone_filter TEXT,
+    single_branch BOOLEAN NOT NULL DEFAULT FALSE,
+    sparse_paths TEXT,
+    topics TEXT,
+    last_pulled_at TEXT,
+    metadata_updated_at TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+";
 
 /// Database service for repository operations
 #[derive(Debug, Clone)]
@@ -36,21 +73,21 @@ impl RepositoryDatabase {
 
     /// Run database migrations
     async fn migrate(&self) -> Result<()> {
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS repositories ({})",
+            REPOSITORIES_COLUMNS
+        ))
+        .execute(&self.pool)
+        .await?;
+
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS repositories (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                full_name TEXT NOT NULL,
-                owner TEXT NOT NULL,
-                provider TEXT NOT NULL,
-                clone_url_https TEXT NOT NULL,
-                clone_url_ssh TEXT NOT NULL,
-                description TEXT,
-                is_private BOOLEAN NOT NULL DEFAULT FALSE,
-                local_path TEXT,
+            CREATE TABLE IF NOT EXISTS mirrors (
+                repository_id TEXT PRIMARY KEY REFERENCES repositories(id) ON DELETE CASCADE,
+                local_path TEXT NOT NULL,
+                include_pull_refs BOOLEAN NOT NULL DEFAULT FALSE,
                 status TEXT NOT NULL DEFAULT 'not_cloned',
-                last_pulled_at TEXT,
+                last_updated_at TEXT,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL
             )
@@ -58,6 +95,266 @@ impl RepositoryDatabase {
         )
         .execute(&self.pool)
         .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS bundle_exports (
+                id TEXT PRIMARY KEY,
+                repository_id TEXT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                bundle_path TEXT NOT NULL,
+                incremental BOOLEAN NOT NULL DEFAULT FALSE,
+                refs TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS jobs (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                description TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'running',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                finished_at TEXT
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS job_tasks (
+                job_id TEXT NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+                repository_id TEXT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                position INTEGER NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (job_id, repository_id)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS operations (
+                id TEXT PRIMARY KEY,
+                repository_id TEXT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                kind TEXT NOT NULL,
+                outcome TEXT NOT NULL,
+                exit_code INTEGER,
+                error TEXT,
+                started_at TEXT NOT NULL,
+                finished_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS operations_by_repository ON operations (repository_id, started_at)",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tags (
+                name TEXT PRIMARY KEY,
+                created_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS repository_tags (
+                repository_id TEXT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                tag TEXT NOT NULL REFERENCES tags(name) ON DELETE CASCADE,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (repository_id, tag)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tag_rules (
+                id TEXT PRIMARY KEY,
+                tag TEXT NOT NULL REFERENCES tags(name) ON DELETE CASCADE,
+                kind TEXT NOT NULL,
+                pattern TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Columns added after the initial schema
+        self.add_column_if_missing(
+            "repositories",
+            "source",
+            "TEXT NOT NULL DEFAULT 'discovered'",
+        )
+        .await?;
+        self.add_column_if_missing("repositories", "kind", "TEXT NOT NULL DEFAULT 'repository'")
+            .await?;
+        self.add_column_if_missing("repositories", "has_wiki", "BOOLEAN NOT NULL DEFAULT FALSE")
+            .await?;
+        self.add_column_if_missing("repositories", "parent_id", "TEXT")
+            .await?;
+        self.add_column_if_missing("repositories", "clone_depth", "INTEGER")
+            .await?;
+        self.add_column_if_missing("repositories", "clone_filter", "TEXT")
+            .await?;
+        self.add_column_if_missing(
+            "repositories",
+            "single_branch",
+            "BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .await?;
+        self.add_column_if_missing("repositories", "sparse_paths", "TEXT")
+            .await?;
+        self.add_column_if_missing("repositories", "topics", "TEXT")
+            .await?;
+        self.add_column_if_missing("repositories", "metadata_updated_at", "TEXT")
+            .await?;
+        self.add_search_key().await?;
+
+        self.create_search_index().await?;
+        Ok(())
+    }
+
+    /// Give tables of older versions the `seq` key of the full-text index.
+    ///
+    /// Their index referred to the implicit rowid, which VACUUM may renumber in a
+    /// table with a TEXT primary key. The table is rebuilt once, keeping every
+    /// row's current rowid as its `seq`, and the index is created anew.
+    async fn add_search_key(&self) -> Result<()> {
+        let columns: Vec<(String,)> =
+            sqlx::query_as("SELECT name FROM pragma_table_info('repositories')")
+                .fetch_all(&self.pool)
+                .await?;
+        if columns.iter().any(|(name,)| name == "seq") {
+            return Ok(());
+        }
+        let columns = columns
+            .iter()
+            .map(|(name,)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut conn = self.pool.acquire().await?;
+        // Dropping the old table must not cascade to operations, tags and mirrors
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await?;
+        let rebuilt = rebuild_repositories(&mut conn, &columns).await;
+        sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&mut *conn)
+            .await?;
+        rebuilt
+    }
+
+    /// Create the FTS5 index over repository metadata, kept in sync by triggers.
+    /// The index is rebuilt when it is first created so existing rows are searchable.
+    async fn create_search_index(&self) -> Result<()> {
+        let existing: Option<(String,)> = sqlx::query_as(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'repositories_fts'",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS repositories_fts USING fts5(
+                name, full_name, description, topics, owner,
+                content = 'repositories', content_rowid = 'seq'
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS repositories_fts_insert AFTER INSERT ON repositories BEGIN
+                INSERT INTO repositories_fts (rowid, name, full_name, description, topics, owner)
+                VALUES (new.seq, new.name, new.full_name, new.description, new.topics, new.owner);
+            END
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS repositories_fts_delete AFTER DELETE ON repositories BEGIN
+                INSERT INTO repositories_fts (repositories_fts, rowid, name, full_name, description, topics, owner)
+                VALUES ('delete', old.seq, old.name, old.full_name, old.description, old.topics, old.owner);
+            END
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS repositories_fts_update AFTER UPDATE ON repositories BEGIN
+                INSERT INTO repositories_fts (repositories_fts, rowid, name, full_name, description, topics, owner)
+                VALUES ('delete', old.seq, old.name, old.full_name, old.description, old.topics, old.owner);
+                INSERT INTO repositories_fts (rowid, name, full_name, description, topics, owner)
+                VALUES (new.seq, new.name, new.full_name, new.description, new.topics, new.owner);
+            END
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if existing.is_none() {
+            sqlx::query("INSERT INTO repositories_fts (repositories_fts) VALUES ('rebuild')")
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Add a column to an existing table created by an older version
+    async fn add_column_if_missing(
+        &self,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<()> {
+        let columns: Vec<(String,)> =
+            sqlx::query_as(&format!("SELECT name FROM pragma_table_info('{}')", table))
+                .fetch_all(&self.pool)
+                .await?;
+
+        if !columns.iter().any(|(name,)| name == column) {
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))
+            .execute(&self.pool)
+            .await?;
+        }
         Ok(())
     }
 
@@ -103,6 +400,53 @@ impl RepositoryDatabase {
         Ok(repos)
     }
 
+    /// Get repositories found through a source, among others
+    pub async fn get_repositories_by_source(
+        &self,
+        source: RepositorySource,
+    ) -> Result<Vec<Repository>> {
+        let repos = sqlx::query_as::<_, Repository>(
+            "SELECT * FROM repositories WHERE ',' || source || ',' LIKE '%,' || ? || ',%' ORDER BY full_name ASC",
+        )
+        .bind(source.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(repos)
+    }
+
+    /// Get repositories linked to a parent repository (e.g. its wiki)
+    pub async fn get_linked_repositories(&self, parent_id: &str) -> Result<Vec<Repository>> {
+        let repos = sqlx::query_as::<_, Repository>(
+            "SELECT * FROM repositories WHERE parent_id = ? ORDER BY full_name ASC",
+        )
+        .bind(parent_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(repos)
+    }
+
+    /// Full-text search over name, full name, description, topics and owner.
+    ///
+    /// Every word in the query must match the start of a word in one of those
+    /// fields. Results are ranked best first, with name matches weighted highest.
+    pub async fn search(&self, query: &str, limit: u32) -> Result<Vec<Repository>> {
+        let Some(query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let repos = sqlx::query_as::<_, Repository>(
+            "SELECT r.* FROM repositories_fts
+             JOIN repositories r ON r.seq = repositories_fts.rowid
+             WHERE repositories_fts MATCH ?
+             ORDER BY bm25(repositories_fts, 10.0, 5.0, 1.0, 3.0, 2.0), r.full_name ASC
+             LIMIT ?",
+        )
+        .bind(query)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(repos)
+    }
+
     /// Get a repository by ID
     pub async fn get_repository(&self, id: &str) -> Result<Option<Repository>> {
         let repo = sqlx::query_as::<_, Repository>("SELECT * FROM repositories WHERE id = ?")
@@ -125,8 +469,8 @@ impl RepositoryDatabase {
     /// Create a new repository
     pub async fn create_repository(&self, repo: &Repository) -> Result<()> {
         sqlx::query(
-            "INSERT INTO repositories (id, name, full_name, owner, provider, clone_url_https, clone_url_ssh, description, is_private, local_path, status, last_pulled_at, created_at, updated_at) 
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO repositories (id, name, full_name, owner, provider, clone_url_https, clone_url_ssh, description, is_private, local_path, status, source, kind, has_wiki, parent_id, clone_depth, clone_filter, single_branch, sparse_paths, topics, last_pulled_at, metadata_updated_at, created_at, updated_at) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&repo.id)
         .bind(&repo.name)
@@ -139,7 +483,17 @@ impl RepositoryDatabase {
         .bind(repo.is_private)
         .bind(&repo.local_path)
         .bind(&repo.status)
+        .bind(&repo.source)
+        .bind(&repo.kind)
+        .bind(repo.has_wiki)
+        .bind(&repo.parent_id)
+        .bind(repo.clone_depth)
+        .bind(&repo.clone_filter)
+        .bind(repo.single_branch)
+        .bind(&repo.sparse_paths)
+        .bind(&repo.topics)
         .bind(repo.last_pulled_at.map(|dt| dt.to_rfc3339()))
+        .bind(repo.metadata_updated_at.map(|dt| dt.to_rfc3339()))
         .bind(repo.created_at.to_rfc3339())
         .bind(repo.updated_at.to_rfc3339())
         .execute(&self.pool)
@@ -150,7 +504,7 @@ impl RepositoryDatabase {
     /// Update a repository
     pub async fn update_repository(&self, repo: &Repository) -> Result<()> {
         sqlx::query(
-            "UPDATE repositories SET name = ?, full_name = ?, owner = ?, provider = ?, clone_url_https = ?, clone_url_ssh = ?, description = ?, is_private = ?, local_path = ?, status = ?, last_pulled_at = ?, updated_at = ? WHERE id = ?"
+            "UPDATE repositories SET name = ?, full_name = ?, owner = ?, provider = ?, clone_url_https = ?, clone_url_ssh = ?, description = ?, is_private = ?, local_path = ?, status = ?, source = ?, kind = ?, has_wiki = ?, parent_id = ?, clone_depth = ?, clone_filter = ?, single_branch = ?, sparse_paths = ?, topics = ?, last_pulled_at = ?, metadata_updated_at = ?, updated_at = ? WHERE id = ?"
         )
         .bind(&repo.name)
         .bind(&repo.full_name)
@@ -162,7 +516,17 @@ impl RepositoryDatabase {
         .bind(repo.is_private)
         .bind(&repo.local_path)
         .bind(&repo.status)
+        .bind(&repo.source)
+        .bind(&repo.kind)
+        .bind(repo.has_wiki)
+        .bind(&repo.parent_id)
+        .bind(repo.clone_depth)
+        .bind(&repo.clone_filter)
+        .bind(repo.single_branch)
+        .bind(&repo.sparse_paths)
+        .bind(&repo.topics)
         .bind(repo.last_pulled_at.map(|dt| dt.to_rfc3339()))
+        .bind(repo.metadata_updated_at.map(|dt| dt.to_rfc3339()))
         .bind(repo.updated_at.to_rfc3339())
         .bind(&repo.id)
         .execute(&self.pool)
@@ -186,4 +550,564 @@ impl RepositoryDatabase {
             .await?;
         Ok(())
     }
+
+    /// Roll back `cloning`/`updating` statuses left behind by an interrupted run.
+    ///
+    /// Only safe while no other run uses the database, see [`RunLock`].
+    pub async fn reset_interrupted_statuses(&self) -> Result<u64> {
+        let cloning = sqlx::query("UPDATE repositories SET status = ? WHERE status = ?")
+            .bind(CloneStatus::NotCloned.to_string())
+            .bind(CloneStatus::Cloning.to_string())
+            .execute(&self.pool)
+            .await?;
+        let updating = sqlx::query("UPDATE repositories SET status = ? WHERE status = ?")
+            .bind(CloneStatus::Cloned.to_string())
+            .bind(CloneStatus::Updating.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(cloning.rows_affected() + updating.rows_affected())
+    }
+
+    /// Store a new job together with its tasks
+    pub async fn create_job(&self, job: &Job, tasks: &[JobTask]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO jobs (id, kind, description, status, created_at, updated_at, finished_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&job.id)
+        .bind(&job.kind)
+        .bind(&job.description)
+        .bind(&job.status)
+        .bind(job.created_at.to_rfc3339())
+        .bind(job.updated_at.to_rfc3339())
+        .bind(job.finished_at.map(|dt| dt.to_rfc3339()))
+        .execute(&mut *tx)
+        .await?;
+        for task in tasks {
+            sqlx::query(
+                "INSERT INTO job_tasks (job_id, repository_id, position, status, attempts, last_error, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&task.job_id)
+            .bind(&task.repository_id)
+            .bind(task.position)
+            .bind(&task.status)
+            .bind(task.attempts)
+            .bind(&task.last_error)
+            .bind(task.updated_at.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Update the status of a job
+    pub async fn update_job(&self, job: &Job) -> Result<()> {
+        sqlx::query("UPDATE jobs SET status = ?, updated_at = ?, finished_at = ? WHERE id = ?")
+            .bind(&job.status)
+            .bind(job.updated_at.to_rfc3339())
+            .bind(job.finished_at.map(|dt| dt.to_rfc3339()))
+            .bind(&job.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Update the state of a job task
+    pub async fn update_job_task(&self, task: &JobTask) -> Result<()> {
+        sqlx::query(
+            "UPDATE job_tasks SET status = ?, attempts = ?, last_error = ?, updated_at = ? WHERE job_id = ? AND repository_id = ?",
+        )
+        .bind(&task.status)
+        .bind(task.attempts)
+        .bind(&task.last_error)
+        .bind(task.updated_at.to_rfc3339())
+        .bind(&task.job_id)
+        .bind(&task.repository_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Get the most recent jobs, newest first
+    pub async fn get_jobs(&self, limit: u32) -> Result<Vec<Job>> {
+        let jobs = sqlx::query_as::<_, Job>("SELECT * FROM jobs ORDER BY created_at DESC LIMIT ?")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(jobs)
+    }
+
+    /// Find a job by its ID or a unique prefix of it
+    pub async fn find_job(&self, id: &str) -> Result<Option<Job>> {
+        let mut jobs = sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id LIKE ? LIMIT 2")
+            .bind(format!("{}%", id.replace(['%', '_'], "")))
+            .fetch_all(&self.pool)
+            .await?;
+        if jobs.len() > 1 {
+            return Err(anyhow::anyhow!("Job ID prefix is ambiguous: {}", id));
+        }
+        Ok(jobs.pop())
+    }
+
+    /// Get the most recent job that has failed tasks
+    pub async fn get_latest_job_with_failures(&self) -> Result<Option<Job>> {
+        let job = sqlx::query_as::<_, Job>(
+            "SELECT * FROM jobs WHERE id IN (SELECT job_id FROM job_tasks WHERE status = ?) ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(TaskStatus::Failed.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(job)
+    }
+
+    /// Get the tasks of a job in the order they run
+    pub async fn get_job_tasks(&self, job_id: &str) -> Result<Vec<JobTask>> {
+        let tasks = sqlx::query_as::<_, JobTask>(
+            "SELECT * FROM job_tasks WHERE job_id = ? ORDER BY position ASC",
+        )
+        .bind(job_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(tasks)
+    }
+
+    /// Mark jobs left running by a killed process as interrupted and return them.
+    ///
+    /// Only safe while no other run uses the database, see [`RunLock`].
+    pub async fn reset_interrupted_jobs(&self) -> Result<Vec<Job>> {
+        let mut jobs =
+            sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE status = ? ORDER BY created_at ASC")
+                .bind(JobStatus::Running.to_string())
+                .fetch_all(&self.pool)
+                .await?;
+        for job in &mut jobs {
+            job.update_status(JobStatus::Interrupted);
+            self.update_job(job).await?;
+        }
+        sqlx::query("UPDATE job_tasks SET status = ? WHERE status = ?")
+            .bind(TaskStatus::Pending.to_string())
+            .bind(TaskStatus::Running.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(jobs)
+    }
+
+    /// Add an operation to a repository's history
+    pub async fn create_operation(&self, operation: &Operation) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO operations (id, repository_id, kind, outcome, exit_code, error, started_at, finished_at, duration_ms)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&operation.id)
+        .bind(&operation.repository_id)
+        .bind(&operation.kind)
+        .bind(&operation.outcome)
+        .bind(operation.exit_code)
+        .bind(&operation.error)
+        .bind(operation.started_at.to_rfc3339())
+        .bind(operation.finished_at.to_rfc3339())
+        .bind(operation.duration_ms)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Get the operation history of a repository, newest first
+    pub async fn get_operations(&self, repository_id: &str, limit: u32) -> Result<Vec<Operation>> {
+        let operations = sqlx::query_as::<_, Operation>(
+            "SELECT * FROM operations WHERE repository_id = ? ORDER BY started_at DESC LIMIT ?",
+        )
+        .bind(repository_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(operations)
+    }
+
+    /// Get the latest operation of every repository whose latest operation failed,
+    /// most recent first
+    pub async fn get_current_failures(&self) -> Result<Vec<Operation>> {
+        let operations = sqlx::query_as::<_, Operation>(
+            "SELECT * FROM operations o
+             WHERE outcome = ?
+               AND started_at = (SELECT MAX(started_at) FROM operations WHERE repository_id = o.repository_id)
+             ORDER BY finished_at DESC",
+        )
+        .bind(OperationOutcome::Failed.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(operations)
+    }
+
+    /// Create a tag unless it already exists
+    async fn ensure_tag(&self, tag: &str) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO tags (name, created_at) VALUES (?, ?)")
+            .bind(tag)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Tag a repository, creating the tag if needed; returns whether the tag was new to it
+    pub async fn add_tag(&self, repository_id: &str, tag: &str) -> Result<bool> {
+        self.ensure_tag(tag).await?;
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO repository_tags (repository_id, tag, created_at) VALUES (?, ?, ?)",
+        )
+        .bind(repository_id)
+        .bind(tag)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Untag a repository; returns whether it had the tag
+    pub async fn remove_tag(&self, repository_id: &str, tag: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM repository_tags WHERE repository_id = ? AND tag = ?")
+            .bind(repository_id)
+            .bind(tag)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete a tag together with its repository links and rules
+    pub async fn delete_tag(&self, tag: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM tags WHERE name = ?")
+            .bind(tag)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// All tags with the number of repositories carrying each
+    pub async fn get_tags(&self) -> Result<Vec<(String, i64)>> {
+        let tags = sqlx::query_as(
+            "SELECT t.name, COUNT(rt.repository_id) FROM tags t
+             LEFT JOIN repository_tags rt ON rt.tag = t.name
+             GROUP BY t.name ORDER BY t.name ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(tags)
+    }
+
+    /// Tags of a repository
+    pub async fn get_repository_tags(&self, repository_id: &str) -> Result<Vec<String>> {
+        let tags: Vec<(String,)> = sqlx::query_as(
+            "SELECT tag FROM repository_tags WHERE repository_id = ? ORDER BY tag ASC",
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(tags.into_iter().map(|(tag,)| tag).collect())
+    }
+
+    /// Tags of every tagged repository, keyed by repository ID
+    pub async fn get_all_repository_tags(&self) -> Result<HashMap<String, Vec<String>>> {
+        let links: Vec<(String, String)> =
+            sqlx::query_as("SELECT repository_id, tag FROM repository_tags ORDER BY tag ASC")
+                .fetch_all(&self.pool)
+                .await?;
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        for (repository_id, tag) in links {
+            tags.entry(repository_id).or_default().push(tag);
+        }
+        Ok(tags)
+    }
+
+    /// IDs of the repositories carrying any of the tags
+    pub async fn get_tagged_repository_ids(&self, tags: &[String]) -> Result<HashSet<String>> {
+        let mut ids = HashSet::new();
+        for tag in tags {
+            let tagged: Vec<(String,)> =
+                sqlx::query_as("SELECT repository_id FROM repository_tags WHERE tag = ?")
+                    .bind(tag)
+                    .fetch_all(&self.pool)
+                    .await?;
+            ids.extend(tagged.into_iter().map(|(id,)| id));
+        }
+        Ok(ids)
+    }
+
+    /// Save a tag rule, creating its tag if needed
+    pub async fn create_tag_rule(&self, rule: &TagRule) -> Result<()> {
+        self.ensure_tag(&rule.tag).await?;
+        sqlx::query(
+            "INSERT INTO tag_rules (id, tag, kind, pattern, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&rule.id)
+        .bind(&rule.tag)
+        .bind(&rule.kind)
+        .bind(&rule.pattern)
+        .bind(rule.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// All tag rules, grouped by tag
+    pub async fn get_tag_rules(&self) -> Result<Vec<TagRule>> {
+        let rules = sqlx::query_as::<_, TagRule>(
+            "SELECT * FROM tag_rules ORDER BY tag ASC, created_at ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rules)
+    }
+
+    /// Find a tag rule by ID or a unique prefix of it
+    pub async fn find_tag_rule(&self, id: &str) -> Result<Option<TagRule>> {
+        let mut rules =
+            sqlx::query_as::<_, TagRule>("SELECT * FROM tag_rules WHERE id LIKE ? LIMIT 2")
+                .bind(format!("{}%", id.replace(['%', '_'], "")))
+                .fetch_all(&self.pool)
+                .await?;
+        if rules.len() > 1 {
+            return Err(anyhow::anyhow!("Tag rule ID prefix is ambiguous: {}", id));
+        }
+        Ok(rules.pop())
+    }
+
+    /// Delete a tag rule; tags it already assigned stay
+    pub async fn delete_tag_rule(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM tag_rules WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Tag the repositories matched by the tag rules; returns the number of new tags
+    pub async fn apply_tag_rules(&self, repos: &[Repository]) -> Result<u64> {
+        let rules = self.get_tag_rules().await?;
+        let mut added = 0;
+        for repo in repos {
+            for rule in rules.iter().filter(|rule| rule.matches(repo)) {
+                if self.add_tag(&repo.id, &rule.tag).await? {
+                    added += 1;
+                }
+            }
+        }
+        Ok(added)
+    }
+
+    /// Get the mirror of a repository
+    pub async fn get_mirror(&self, repository_id: &str) -> Result<Option<Mirror>> {
+        let mirror = sqlx::query_as::<_, Mirror>("SELECT * FROM mirrors WHERE repository_id = ?")
+            .bind(repository_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(mirror)
+    }
+
+    /// Get all mirrors
+    pub async fn get_all_mirrors(&self) -> Result<Vec<Mirror>> {
+        let mirrors = sqlx::query_as::<_, Mirror>("SELECT * FROM mirrors ORDER BY local_path ASC")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(mirrors)
+    }
+
+    /// Create or update the mirror of a repository
+    pub async fn save_mirror(&self, mirror: &Mirror) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO mirrors (repository_id, local_path, include_pull_refs, status, last_updated_at, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(repository_id) DO UPDATE SET local_path = excluded.local_path, include_pull_refs = excluded.include_pull_refs, status = excluded.status, last_updated_at = excluded.last_updated_at, updated_at = excluded.updated_at"
+        )
+        .bind(&mirror.repository_id)
+        .bind(&mirror.local_path)
+        .bind(mirror.include_pull_refs)
+        .bind(&mirror.status)
+        .bind(mirror.last_updated_at.map(|dt| dt.to_rfc3339()))
+        .bind(mirror.created_at.to_rfc3339())
+        .bind(mirror.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete the mirror record of a repository
+    pub async fn delete_mirror(&self, repository_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM mirrors WHERE repository_id = ?")
+            .bind(repository_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Record a bundle written for a repository
+    pub async fn create_bundle_export(&self, export: &BundleExport) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO bundle_exports (id, repository_id, bundle_path, incremental, refs, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&export.id)
+        .bind(&export.repository_id)
+        .bind(&export.bundle_path)
+        .bind(export.incremental)
+        .bind(&export.refs)
+        .bind(export.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Get the most recent bundle written for a repository
+    pub async fn get_latest_bundle_export(
+        &self,
+        repository_id: &str,
+    ) -> Result<Option<BundleExport>> {
+        let export = sqlx::query_as::<_, BundleExport>(
+            "SELECT * FROM bundle_exports WHERE repository_id = ? ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(repository_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(export)
+    }
+}
+
+/// Turn free text into an FTS5 query: every word becomes a quoted prefix
+/// term so punctuation in the input cannot be read as query syntax
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Copy the repositories table into one with the `seq` key, in one transaction
+async fn rebuild_repositories(conn: &mut SqliteConnection, columns: &str) -> Result<()> {
+    let mut tx = conn.begin().await?;
+    sqlx::query(&format!(
+        "CREATE TABLE repositories_rebuilt ({})",
+        REPOSITORIES_COLUMNS
+    ))
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(&format!(
+        "INSERT INTO repositories_rebuilt (seq, {0}) SELECT rowid, {0} FROM repositories",
+        columns
+    ))
+    .execute(&mut *tx)
+    .await?;
+    // Its triggers go with the old table; the index is recreated and rebuilt
+    sqlx::query("DROP TABLE IF EXISTS repositories_fts")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DROP TABLE repositories")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("ALTER TABLE repositories_rebuilt RENAME TO repositories")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(
+            fts_query("payments ledger").as_deref(),
+            Some("\"payments\"* \"ledger\"*")
+        );
+        assert_eq!(
+            fts_query("acme/billing-api").as_deref(),
+            Some("\"acme\"* \"billing\"* \"api\"*")
+        );
+        assert_eq!(fts_query(" \"*( ").as_deref(), None);
+    }
+
+    #[tokio::test]
+    async fn test_search_key_migration_survives_vacuum() {
+        let path = std::env::temp_dir().join(format!("super-clone-{}.db", uuid::Uuid::new_v4()));
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+        const CREATED: &str = "2024-01-01T00:00:00+00:00";
+
+        // A database of a version whose index used the implicit rowid
+        let old = SqlitePoolOptions::new().connect(&url).await.unwrap();
+        for statement in [
+            "CREATE TABLE repositories (id TEXT PRIMARY KEY, name TEXT NOT NULL,
+                full_name TEXT NOT NULL, owner TEXT NOT NULL, provider TEXT NOT NULL,
+                clone_url_https TEXT NOT NULL, clone_url_ssh TEXT NOT NULL, description TEXT,
+                is_private BOOLEAN NOT NULL DEFAULT FALSE, local_path TEXT,
+                status TEXT NOT NULL DEFAULT 'not_cloned', last_pulled_at TEXT,
+                created_at TEXT NOT NULL, updated_at TEXT NOT NULL)",
+            "CREATE TABLE operations (id TEXT PRIMARY KEY,
+                repository_id TEXT NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+                kind TEXT NOT NULL, outcome TEXT NOT NULL, exit_code INTEGER, error TEXT,
+                started_at TEXT NOT NULL, finished_at TEXT NOT NULL, duration_ms INTEGER NOT NULL)",
+        ] {
+            sqlx::query(statement).execute(&old).await.unwrap();
+        }
+        for (id, name) in [("a", "gone"), ("b", "ledger"), ("c", "search")] {
+            sqlx::query(
+                "INSERT INTO repositories (id, name, full_name, owner, provider, clone_url_https,
+                 clone_url_ssh, created_at, updated_at)
+                 VALUES (?, ?, 'acme/' || ?, 'acme', 'github', '', '', ?, ?)",
+            )
+            .bind(id)
+            .bind(name)
+            .bind(name)
+            .bind(CREATED)
+            .bind(CREATED)
+            .execute(&old)
+            .await
+            .unwrap();
+        }
+        sqlx::query("DELETE FROM repositories WHERE id = 'a'")
+            .execute(&old)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO operations VALUES ('op', 'b', 'clone', 'succeeded', 0, NULL, ?, ?, 0)",
+        )
+        .bind(CREATED)
+        .bind(CREATED)
+        .execute(&old)
+        .await
+        .unwrap();
+        old.close().await;
+
+        let db = RepositoryDatabase::new(path.to_str().unwrap())
+            .await
+            .unwrap();
+        let names = |repos: Vec<Repository>| -> Vec<String> {
+            repos.into_iter().map(|repo| repo.full_name).collect()
+        };
+        assert_eq!(
+            names(db.search("ledger", 10).await.unwrap()),
+            ["acme/ledger"]
+        );
+        // Rebuilding the table kept the rows that refer to it
+        assert_eq!(db.get_operations("b", 10).await.unwrap().len(), 1);
+
+        sqlx::query("VACUUM").execute(&db.pool).await.unwrap();
+        assert_eq!(
+            names(db.search("ledger", 10).await.unwrap()),
+            ["acme/ledger"]
+        );
+        assert_eq!(
+            names(db.search("search", 10).await.unwrap()),
+            ["acme/search"]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
 }
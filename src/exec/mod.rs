@@ -0,0 +1,338 @@
+use crate::git::CancelFlag;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// How the output of commands run across repositories is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Stream every line as it arrives, prefixed with the repository name
+    Prefix,
+    /// Print each repository's output in one block once its command is done
+    Group,
+    /// Keep the output in the results only
+    Capture,
+}
+
+impl std::str::FromStr for OutputMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "prefix" => Ok(OutputMode::Prefix),
+            "group" => Ok(OutputMode::Group),
+            _ => Err(anyhow::anyhow!(
+                "Invalid output mode: {} (expected prefix or group)",
+                s
+            )),
+        }
+    }
+}
+
+/// A repository to run a command in
+#[derive(Debug, Clone)]
+pub struct ExecTarget {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExecOptions {
+    /// Commands running at the same time
+    pub parallel: usize,
+    pub output: OutputMode,
+    /// Keep starting commands after one failed
+    pub continue_on_error: bool,
+    /// Stop starting commands once set
+    pub cancel: CancelFlag,
+}
+
+impl Default for ExecOptions {
+    fn default() -> Self {
+        Self {
+            parallel: 1,
+            output: OutputMode::Prefix,
+            continue_on_error: false,
+            cancel: CancelFlag::new(),
+        }
+    }
+}
+
+/// Outcome of the command in one repository
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecResult {
+    pub repository: String,
+    pub path: String,
+    /// `None` when the command could not start or was killed by a signal
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    /// The command never ran because an earlier one failed or the run was cancelled
+    pub skipped: bool,
+    pub duration_ms: u64,
+    /// Why the command could not run at all
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+}
+
+impl ExecResult {
+    fn new(target: &ExecTarget) -> Self {
+        Self {
+            repository: target.name.clone(),
+            path: target.path.to_string_lossy().to_string(),
+            exit_code: None,
+            success: false,
+            skipped: false,
+            duration_ms: 0,
+            error: None,
+            stdout: None,
+            stderr: None,
+        }
+    }
+}
+
+/// Build the process for a command line: a single argument is handed to the
+/// shell (so `&&`, pipes and globs work), several are run as program and arguments
+fn command_for(command: &[String]) -> Command {
+    let mut process = if command.len() == 1 {
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        shell.arg(&command[0]);
+        shell
+    } else {
+        let mut program = Command::new(&command[0]);
+        program.args(&command[1..]);
+        program
+    };
+    process.stdin(Stdio::null()).kill_on_drop(true);
+    process
+}
+
+/// Run a command in every target directory, at most `options.parallel` at a time.
+///
+/// Results come back in the order of `targets`.
+pub async fn run_in_repositories(
+    targets: &[ExecTarget],
+    command: &[String],
+    options: &ExecOptions,
+) -> anyhow::Result<Vec<ExecResult>> {
+    if command.is_empty() {
+        return Err(anyhow::anyhow!("No command given"));
+    }
+
+    let slots = Arc::new(Semaphore::new(options.parallel.max(1)));
+    let failed = Arc::new(AtomicBool::new(false));
+    let mut running = JoinSet::new();
+    for (index, target) in targets.iter().enumerate() {
+        let permit = slots.clone().acquire_owned().await?;
+        let stop = options.cancel.is_cancelled()
+            || (!options.continue_on_error && failed.load(Ordering::SeqCst));
+        let target = target.clone();
+        if stop {
+            let mut result = ExecResult::new(&target);
+            result.skipped = true;
+            running.spawn(async move { (index, result) });
+            continue;
+        }
+
+        let command = command.to_vec();
+        let output = options.output;
+        let failed = failed.clone();
+        running.spawn(async move {
+            let result = run_one(&target, &command, output).await;
+            if !result.success {
+                failed.store(true, Ordering::SeqCst);
+            }
+            drop(permit);
+            (index, result)
+        });
+    }
+
+    let mut results = Vec::with_capacity(targets.len());
+    while let Some(finished) = running.join_next().await {
+        results.push(finished?);
+    }
+    results.sort_by_key(|(index, _)| *index);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+async fn run_one(target: &ExecTarget, command: &[String], output: OutputMode) -> ExecResult {
+    let mut result = ExecResult::new(target);
+    let started = Instant::now();
+    if !target.path.is_dir() {
+        result.error = Some(format!("{} does not exist", target.path.display()));
+        return result;
+    }
+
+    let mut process = command_for(command);
+    process
+        .current_dir(&target.path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = match process.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            result.error = Some(format!("Failed to run {}: {}", command[0], e));
+            return result;
+        }
+    };
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let prefix = match output {
+        OutputMode::Prefix => Some(target.name.clone()),
+        _ => None,
+    };
+    let (stdout, stderr, status) = tokio::join!(
+        read_lines(stdout, prefix.clone(), false),
+        read_lines(stderr, prefix, true),
+        child.wait()
+    );
+
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    match status {
+        Ok(status) => {
+            result.exit_code = status.code();
+            result.success = status.success();
+        }
+        Err(e) => result.error = Some(format!("Failed to wait for {}: {}", command[0], e)),
+    }
+
+    match output {
+        OutputMode::Prefix => {}
+        OutputMode::Group => {
+            let status = match result.exit_code {
+                Some(code) => format!("exit code {}", code),
+                None => "no exit code".to_string(),
+            };
+            println!("── {} ({})", target.name, status);
+            print!("{}", stdout);
+            eprint!("{}", stderr);
+        }
+        OutputMode::Capture => {
+            result.stdout = Some(stdout);
+            result.stderr = Some(stderr);
+        }
+    }
+    result
+}
+
+/// Read a child's output line by line; with a prefix each line is printed
+/// right away, otherwise the output is collected and returned
+async fn read_lines(
+    reader: impl AsyncRead + Unpin,
+    prefix: Option<String>,
+    is_stderr: bool,
+) -> String {
+    let mut lines = BufReader::new(reader).lines();
+    let mut collected = String::new();
+    while let Ok(Some(line)) = lines.next_line().await {
+        match &prefix {
+            Some(name) if is_stderr => eprintln!("[{}] {}", name, line),
+            Some(name) => println!("[{}] {}", name, line),
+            None => {
+                collected.push_str(&line);
+                collected.push('\n');
+            }
+        }
+    }
+    collected
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn targets(dirs: &[&std::path::Path]) -> Vec<ExecTarget> {
+        dirs.iter()
+            .map(|dir| ExecTarget {
+                name: dir.file_name().unwrap().to_string_lossy().to_string(),
+                path: dir.to_path_buf(),
+            })
+            .collect()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("exec-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_runs_in_each_directory_through_the_shell() {
+        let (a, b) = (temp_dir("a"), temp_dir("b"));
+        let options = ExecOptions {
+            parallel: 2,
+            output: OutputMode::Capture,
+            ..Default::default()
+        };
+        let results = run_in_repositories(
+            &targets(&[&a, &b]),
+            &["pwd && echo done".to_string()],
+            &options,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(results.len(), 2);
+        for (result, dir) in results.iter().zip([&a, &b]) {
+            assert!(result.success);
+            assert_eq!(result.exit_code, Some(0));
+            let stdout = result.stdout.as_deref().unwrap();
+            assert!(stdout.contains(dir.file_name().unwrap().to_str().unwrap()));
+            assert!(stdout.ends_with("done\n"));
+        }
+        let _ = std::fs::remove_dir_all(a);
+        let _ = std::fs::remove_dir_all(b);
+    }
+
+    #[tokio::test]
+    async fn test_stops_after_failure_unless_continuing() {
+        let (a, b) = (temp_dir("a"), temp_dir("b"));
+        let command = vec!["sh".to_string(), "-c".to_string(), "exit 3".to_string()];
+        let mut options = ExecOptions {
+            output: OutputMode::Capture,
+            ..Default::default()
+        };
+
+        let results = run_in_repositories(&targets(&[&a, &b]), &command, &options)
+            .await
+            .unwrap();
+        assert_eq!(results[0].exit_code, Some(3));
+        assert!(results[1].skipped);
+
+        options.continue_on_error = true;
+        let results = run_in_repositories(&targets(&[&a, &b]), &command, &options)
+            .await
+            .unwrap();
+        assert!(results.iter().all(|r| !r.skipped && r.exit_code == Some(3)));
+
+        let missing = ExecTarget {
+            name: "missing".to_string(),
+            path: a.join("missing"),
+        };
+        let results = run_in_repositories(&[missing], &command, &options)
+            .await
+            .unwrap();
+        assert!(results[0].error.is_some());
+        let _ = std::fs::remove_dir_all(a);
+        let _ = std::fs::remove_dir_all(b);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::models::{CloneStatus, Provider, Repository, RepositorySource};
+use std::collections::HashSet;
+
+/// Selects the repositories a command acts on
+#[derive(Debug, Clone, Default)]
+pub struct RepositoryFilter {
+    pub provider: Option<Provider>,
+    pub source: Option<RepositorySource>,
+    /// Only repositories that are cloned
+    pub cloned: bool,
+    /// Full name patterns to keep (`*` and `?` wildcards); empty keeps everything
+    pub include: Vec<String>,
+    /// Full name patterns to drop, applied after `include`
+    pub exclude: Vec<String>,
+    /// IDs of the repositories carrying one of the selected tags; `None` ignores tags
+    pub tagged: Option<HashSet<String>>,
+}
+
+impl RepositoryFilter {
+    /// Whether a repository passes every configured filter
+    pub fn matches(&self, repo: &Repository) -> bool {
+        if let Some(provider) = &self.provider {
+            if repo.provider != provider.to_string() {
+                return false;
+            }
+        }
+        if let Some(source) = &self.source {
+            if !repo.has_source(source) {
+                return false;
+            }
+        }
+        if self.cloned && repo.status != CloneStatus::Cloned.to_string() {
+            return false;
+        }
+        if let Some(tagged) = &self.tagged {
+            if !tagged.contains(&repo.id) {
+                return false;
+            }
+        }
+        if !self.include.is_empty()
+            && !self
+                .include
+                .iter()
+                .any(|pattern| glob_match(pattern, &repo.full_name))
+        {
+            return false;
+        }
+        !self
+            .exclude
+            .iter()
+            .any(|pattern| glob_match(pattern, &repo.full_name))
+    }
+
+    /// Keep the repositories that pass the filters
+    pub fn apply(&self, repos: Vec<Repository>) -> Vec<Repository> {
+        repos
+            .into_iter()
+            .filter(|repo| self.matches(repo))
+            .collect()
+    }
+}
+
+/// Match text against a pattern where `*` matches any run of characters
+/// (including `/`) and `?` matches exactly one
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it currently covers up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, covered)) => {
+                    p = star + 1;
+                    t = covered + 1;
+                    backtrack = Some((star, covered + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(full_name: &str, provider: Provider) -> Repository {
+        let (owner, name) = full_name.split_once('/').unwrap();
+        Repository::new(
+            name.to_string(),
+            full_name.to_string(),
+            owner.to_string(),
+            provider,
+            format!("https://example.com/{}.git", full_name),
+            format!("git@example.com:{}.git", full_name),
+            None,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("acme/*", "acme/api"));
+        assert!(glob_match("*-service", "acme/billing-service"));
+        assert!(glob_match("acme/???", "acme/api"));
+        assert!(glob_match("*", "group/sub/project"));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("acme/*", "other/api"));
+        assert!(!glob_match("acme/??", "acme/api"));
+        assert!(!glob_match("a*b*c", "axxbyy"));
+    }
+
+    #[test]
+    fn test_filter_combines_conditions() {
+        let mut cloned = repo("acme/api", Provider::GitHub);
+        cloned.update_status(CloneStatus::Cloned);
+        let repos = vec![
+            cloned,
+            repo("acme/web", Provider::GitHub),
+            repo("acme/legacy-api", Provider::GitLab),
+        ];
+
+        let filter = RepositoryFilter {
+            include: vec!["acme/*api".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(filter.apply(repos.clone()).len(), 2);
+
+        let filter = RepositoryFilter {
+            provider: Some(Provider::GitHub),
+            exclude: vec!["*/web".to_string()],
+            ..Default::default()
+        };
+        let names: Vec<String> = filter
+            .apply(repos.clone())
+            .into_iter()
+            .map(|r| r.full_name)
+            .collect();
+        assert_eq!(names, vec!["acme/api"]);
+
+        let filter = RepositoryFilter {
+            cloned: true,
+            ..Default::default()
+        };
+        assert_eq!(filter.apply(repos.clone()).len(), 1);
+
+        let filter = RepositoryFilter {
+            tagged: Some(HashSet::from([repos[2].id.clone()])),
+            ..Default::default()
+        };
+        let names: Vec<String> = filter
+            .apply(repos)
+            .into_iter()
+            .map(|r| r.full_name)
+            .collect();
+        assert_eq!(names, vec!["acme/legacy-api"]);
+    }
+}
@@ -0,0 +1,632 @@
+use super::process::{self, git_command, RunLimits};
+use crate::models::CloneOptions;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+/// Structured error returned by git backends.
+///
+/// Failures of a git run carry the process' exit code; it is `None` for
+/// libgit2 errors and for git processes killed by a signal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitError {
+    /// Credentials were missing or rejected
+    Authentication {
+        message: String,
+        exit_code: Option<i32>,
+    },
+    /// The remote host could not be reached
+    Network {
+        message: String,
+        exit_code: Option<i32>,
+    },
+    /// The remote repository does not exist or is not accessible
+    RemoteNotFound {
+        message: String,
+        exit_code: Option<i32>,
+    },
+    /// The local path is not a git repository
+    NotARepository {
+        message: String,
+        exit_code: Option<i32>,
+    },
+    /// The local branch has diverged from its upstream
+    NonFastForward {
+        message: String,
+        exit_code: Option<i32>,
+    },
+    /// The backend does not support the requested operation or option
+    Unsupported(String),
+    /// The operation was cancelled (e.g. Ctrl-C)
+    Cancelled,
+    /// The operation ran longer than the configured timeout
+    TimedOut(std::time::Duration),
+    /// Any other failure, with the git or libgit2 message
+    Other {
+        message: String,
+        exit_code: Option<i32>,
+    },
+}
+
+impl std::fmt::Display for GitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitError::Authentication { message, .. } => {
+                write!(f, "Authentication failed: {}", message)
+            }
+            GitError::Network { message, .. } => write!(f, "Network error: {}", message),
+            GitError::RemoteNotFound { message, .. } => {
+                write!(f, "Remote repository not found: {}", message)
+            }
+            GitError::NotARepository { message, .. } => {
+                write!(f, "Not a git repository: {}", message)
+            }
+            GitError::NonFastForward { message, .. } => {
+                write!(f, "Cannot fast-forward: {}", message)
+            }
+            GitError::Unsupported(msg) => write!(f, "Unsupported: {}", msg),
+            GitError::Cancelled => write!(f, "Cancelled"),
+            GitError::TimedOut(timeout) => {
+                write!(f, "Timed out after {}s", timeout.as_secs_f32())
+            }
+            GitError::Other { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for GitError {}
+
+impl GitError {
+    /// Classify the stderr of a failed git command
+    pub fn from_stderr(action: &str, exit_code: Option<i32>, stderr: &str) -> Self {
+        let message = match exit_code {
+            Some(code) => format!("git {} (exit code {}): {}", action, code, stderr.trim()),
+            None => format!("git {}: {}", action, stderr.trim()),
+        };
+        let lower = stderr.to_lowercase();
+        if lower.contains("authentication failed")
+            || lower.contains("could not read username")
+            || lower.contains("permission denied (publickey)")
+            || lower.contains("terminal prompts disabled")
+        {
+            GitError::Authentication { message, exit_code }
+        } else if lower.contains("could not resolve host")
+            || lower.contains("operation too slow")
+            || lower.contains("connection refused")
+            || lower.contains("connection timed out")
+            || lower.contains("network is unreachable")
+        {
+            GitError::Network { message, exit_code }
+        } else if lower.contains("repository not found")
+            || lower.contains("does not appear to be a git repository")
+            || (lower.contains("repository") && lower.contains("does not exist"))
+        {
+            GitError::RemoteNotFound { message, exit_code }
+        } else if lower.contains("not a git repository") {
+            GitError::NotARepository { message, exit_code }
+        } else if lower.contains("not possible to fast-forward")
+            || lower.contains("diverging branches")
+            || lower.contains("non-fast-forward")
+        {
+            GitError::NonFastForward { message, exit_code }
+        } else {
+            GitError::Other { message, exit_code }
+        }
+    }
+
+    /// A failure without a git exit code, e.g. from libgit2
+    pub fn other(message: impl Into<String>) -> Self {
+        GitError::Other {
+            message: message.into(),
+            exit_code: None,
+        }
+    }
+
+    /// Exit code of the git process behind this error, when one ran and exited
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            GitError::Authentication { exit_code, .. }
+            | GitError::Network { exit_code, .. }
+            | GitError::RemoteNotFound { exit_code, .. }
+            | GitError::NotARepository { exit_code, .. }
+            | GitError::NonFastForward { exit_code, .. }
+            | GitError::Other { exit_code, .. } => *exit_code,
+            GitError::Unsupported(_) | GitError::Cancelled | GitError::TimedOut(_) => None,
+        }
+    }
+}
+
+pub type GitResult<T> = std::result::Result<T, GitError>;
+
+/// Stage of a clone, fetch or pull
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressPhase {
+    /// The remote is counting or compressing objects
+    Preparing,
+    /// Objects are being downloaded
+    Receiving,
+    /// Deltas are being resolved
+    Resolving,
+    /// Files are being written to the working tree
+    CheckingOut,
+}
+
+impl std::fmt::Display for ProgressPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgressPhase::Preparing => write!(f, "preparing"),
+            ProgressPhase::Receiving => write!(f, "receiving objects"),
+            ProgressPhase::Resolving => write!(f, "resolving deltas"),
+            ProgressPhase::CheckingOut => write!(f, "checking out"),
+        }
+    }
+}
+
+/// Transfer counters reported while cloning, fetching or pulling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferProgress {
+    pub phase: ProgressPhase,
+    /// Items done in this phase
+    pub current: usize,
+    /// Items in this phase, 0 when not known yet
+    pub total: usize,
+    /// Bytes received so far (0 when not reported)
+    pub received_bytes: u64,
+}
+
+/// Callback receiving transfer progress from a backend
+pub type ProgressCallback = Arc<dyn Fn(&TransferProgress) + Send + Sync>;
+
+/// Working tree and branch state of a clone
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoStatus {
+    /// Checked out branch, `None` when HEAD is detached
+    pub branch: Option<String>,
+    /// Commit HEAD points at, `None` in an empty repository
+    pub head: Option<String>,
+    /// Upstream branch, e.g. `origin/main`
+    pub upstream: Option<String>,
+    /// Commits on the branch that are not on the upstream
+    pub ahead: usize,
+    /// Commits on the upstream that are not on the branch
+    pub behind: usize,
+    /// Staged, modified, deleted or conflicted paths
+    pub changed: usize,
+    /// Untracked paths
+    pub untracked: usize,
+}
+
+impl RepoStatus {
+    /// No local changes and in sync with the upstream
+    pub fn is_clean(&self) -> bool {
+        self.changed == 0 && self.untracked == 0 && self.ahead == 0 && self.behind == 0
+    }
+}
+
+/// A ref advertised by a remote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteRef {
+    pub name: String,
+    pub oid: String,
+}
+
+/// Git operations that can run through the `git` binary or in-process
+#[async_trait::async_trait]
+pub trait GitBackend: Send + Sync {
+    /// Short backend name, as accepted by `--git-backend`
+    fn name(&self) -> &'static str;
+
+    /// Clone `url` into `target`; `sparse` starts with only top-level files checked out
+    async fn clone_repo(
+        &self,
+        url: &str,
+        target: &Path,
+        options: &CloneOptions,
+        sparse: bool,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()>;
+
+    /// Fetch from a configured remote
+    async fn fetch(
+        &self,
+        local_path: &Path,
+        remote: &str,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()>;
+
+    /// Update the checked out branch from its upstream
+    async fn pull(
+        &self,
+        local_path: &Path,
+        options: &CloneOptions,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()>;
+
+    /// Branch and working tree state
+    async fn status(&self, local_path: &Path) -> GitResult<RepoStatus>;
+
+    /// List the refs a remote advertises
+    async fn ls_remote(&self, url: &str) -> GitResult<Vec<RemoteRef>>;
+}
+
+/// Names accepted by [`backend_by_name`]
+pub fn available_backends() -> Vec<&'static str> {
+    let mut names = vec!["cli"];
+    if cfg!(feature = "libgit2") {
+        names.push("libgit2");
+    }
+    names
+}
+
+/// Create a backend from its name (`cli`, or `libgit2` with the `libgit2` feature)
+pub fn backend_by_name(name: &str, limits: &RunLimits) -> crate::Result<Arc<dyn GitBackend>> {
+    match name {
+        "cli" => Ok(Arc::new(CliBackend::with_limits(limits.clone()))),
+        #[cfg(feature = "libgit2")]
+        "libgit2" => Ok(Arc::new(super::libgit2::Libgit2Backend::with_limits(
+            limits.clone(),
+        ))),
+        _ => Err(anyhow::anyhow!(
+            "Unknown git backend: {} (available: {})",
+            name,
+            available_backends().join(", ")
+        )),
+    }
+}
+
+/// Backend running the `git` binary
+#[derive(Debug, Clone, Default)]
+pub struct CliBackend {
+    limits: RunLimits,
+}
+
+impl CliBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply timeouts, the low-speed abort and cancellation to every git run
+    pub fn with_limits(limits: RunLimits) -> Self {
+        Self { limits }
+    }
+
+    fn run(&self, command: &mut Command, action: &str) -> GitResult<String> {
+        // LFS objects are fetched afterwards so a smudge failure can't abort the clone or pull
+        command.env("GIT_LFS_SKIP_SMUDGE", "1");
+        process::run(command, action, &self.limits, |_| false)
+    }
+
+    /// Run a transfer command, streaming its `--progress` output into the callback
+    fn run_transfer(
+        &self,
+        command: &mut Command,
+        action: &str,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()> {
+        let Some(progress) = progress else {
+            return self.run(command, action).map(|_| ());
+        };
+
+        command.arg("--progress").env("GIT_LFS_SKIP_SMUDGE", "1");
+        process::run(
+            command,
+            action,
+            &self.limits,
+            |line| match parse_progress_line(line) {
+                Some(update) => {
+                    progress(&update);
+                    true
+                }
+                None => false,
+            },
+        )
+        .map(|_| ())
+    }
+
+    fn in_repo(local_path: &Path) -> Command {
+        let mut command = git_command();
+        command.arg("-C").arg(local_path);
+        command
+    }
+}
+
+#[async_trait::async_trait]
+impl GitBackend for CliBackend {
+    fn name(&self) -> &'static str {
+        "cli"
+    }
+
+    async fn clone_repo(
+        &self,
+        url: &str,
+        target: &Path,
+        options: &CloneOptions,
+        sparse: bool,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()> {
+        let mut command = git_command();
+        command.arg("clone").args(super::clone_args(options));
+        if sparse {
+            command.arg("--sparse");
+        }
+        command.arg(url).arg(target);
+        let backend = self.clone();
+        blocking(move || backend.run_transfer(&mut command, "clone", progress)).await
+    }
+
+    async fn fetch(
+        &self,
+        local_path: &Path,
+        remote: &str,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()> {
+        let mut command = Self::in_repo(local_path);
+        command.args(["fetch", "--prune", remote]);
+        let backend = self.clone();
+        blocking(move || backend.run_transfer(&mut command, "fetch", progress)).await
+    }
+
+    async fn pull(
+        &self,
+        local_path: &Path,
+        options: &CloneOptions,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()> {
+        let backend = self.clone();
+        if options.depth.is_none() {
+            let mut command = Self::in_repo(local_path);
+            command.arg("pull");
+            return blocking(move || backend.run_transfer(&mut command, "pull", progress)).await;
+        }
+
+        // `pull --depth` cuts the new commits off from the old shallow history,
+        // which git then refuses to merge; a plain fetch extends the history
+        // from the existing shallow boundary, so the branch can fast-forward
+        let mut fetch = Self::in_repo(local_path);
+        fetch.arg("fetch");
+        let mut merge = Self::in_repo(local_path);
+        merge.args(["merge", "--ff-only", "@{upstream}"]);
+        blocking(move || {
+            backend.run_transfer(&mut fetch, "pull", progress)?;
+            backend.run(&mut merge, "pull").map(|_| ())
+        })
+        .await
+    }
+
+    async fn status(&self, local_path: &Path) -> GitResult<RepoStatus> {
+        let mut command = Self::in_repo(local_path);
+        command.args(["status", "--porcelain=v2", "--branch"]);
+        let backend = self.clone();
+        let output = blocking(move || backend.run(&mut command, "status")).await?;
+        Ok(parse_porcelain_status(&output))
+    }
+
+    async fn ls_remote(&self, url: &str) -> GitResult<Vec<RemoteRef>> {
+        let mut command = git_command();
+        command.args(["ls-remote", url]);
+        let backend = self.clone();
+        let output = blocking(move || backend.run(&mut command, "ls-remote")).await?;
+        Ok(output
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(oid, name)| RemoteRef {
+                name: name.to_string(),
+                oid: oid.to_string(),
+            })
+            .collect())
+    }
+}
+
+/// Run blocking git work on tokio's blocking thread pool, so that a long clone
+/// or a libgit2 transfer doesn't stall the runtime's worker threads
+pub(crate) async fn blocking<T, F>(work: F) -> GitResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> GitResult<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(work)
+        .await
+        .unwrap_or_else(|e| Err(GitError::other(format!("git task failed: {}", e))))
+}
+
+/// Parse a git progress line such as `Receiving objects:  45% (450/1000), 1.20 MiB | 2.00 MiB/s`
+fn parse_progress_line(line: &str) -> Option<TransferProgress> {
+    let line = line.trim().trim_start_matches("remote:").trim();
+    let (label, rest) = line.split_once(':')?;
+    let phase = match label {
+        "Enumerating objects" | "Counting objects" | "Compressing objects" => {
+            ProgressPhase::Preparing
+        }
+        "Receiving objects" => ProgressPhase::Receiving,
+        "Resolving deltas" => ProgressPhase::Resolving,
+        "Updating files" | "Checking out files" => ProgressPhase::CheckingOut,
+        _ => return None,
+    };
+
+    let (current, total) = match rest.find('(') {
+        Some(start) => {
+            let counts = &rest[start + 1..start + rest[start..].find(')')?];
+            let (current, total) = counts.split_once('/')?;
+            (current.trim().parse().ok()?, total.trim().parse().ok()?)
+        }
+        // "Enumerating objects: 42, done." has a count but no total
+        None => (
+            rest.trim()
+                .split(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse()
+                .ok()?,
+            0,
+        ),
+    };
+
+    let received_bytes = rest
+        .split_once("), ")
+        .and_then(|(_, size)| parse_size(size.split('|').next()?.trim()))
+        .unwrap_or(0);
+
+    Some(TransferProgress {
+        phase,
+        current,
+        total,
+        received_bytes,
+    })
+}
+
+/// Parse a size like `1.20 MiB` as printed by git
+fn parse_size(size: &str) -> Option<u64> {
+    let (value, unit) = size.split_once(' ')?;
+    let value: f64 = value.parse().ok()?;
+    let multiplier = match unit.trim_end_matches(',') {
+        "bytes" | "byte" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((value * multiplier) as u64)
+}
+
+/// Parse `git status --porcelain=v2 --branch` output
+fn parse_porcelain_status(output: &str) -> RepoStatus {
+    let mut status = RepoStatus::default();
+    for line in output.lines() {
+        if let Some(oid) = line.strip_prefix("# branch.oid ") {
+            if oid != "(initial)" {
+                status.head = Some(oid.to_string());
+            }
+        } else if let Some(head) = line.strip_prefix("# branch.head ") {
+            if head != "(detached)" {
+                status.branch = Some(head.to_string());
+            }
+        } else if let Some(upstream) = line.strip_prefix("# branch.upstream ") {
+            status.upstream = Some(upstream.to_string());
+        } else if let Some(counts) = line.strip_prefix("# branch.ab ") {
+            for count in counts.split_whitespace() {
+                if let Some(ahead) = count.strip_prefix('+') {
+                    status.ahead = ahead.parse().unwrap_or(0);
+                } else if let Some(behind) = count.strip_prefix('-') {
+                    status.behind = behind.parse().unwrap_or(0);
+                }
+            }
+        } else if line.starts_with("? ") {
+            status.untracked += 1;
+        } else if line.starts_with("1 ") || line.starts_with("2 ") || line.starts_with("u ") {
+            status.changed += 1;
+        }
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_stderr() {
+        assert!(matches!(
+            GitError::from_stderr(
+                "clone",
+                Some(128),
+                "fatal: Authentication failed for 'https://github.com/o/r.git/'"
+            ),
+            GitError::Authentication { .. }
+        ));
+        assert!(matches!(
+            GitError::from_stderr(
+                "fetch",
+                Some(128),
+                "fatal: unable to access '...': Could not resolve host: github.com"
+            ),
+            GitError::Network { .. }
+        ));
+        assert!(matches!(
+            GitError::from_stderr(
+                "clone",
+                Some(128),
+                "fatal: repository '/tmp/missing' does not exist"
+            ),
+            GitError::RemoteNotFound { .. }
+        ));
+        assert!(matches!(
+            GitError::from_stderr(
+                "pull",
+                Some(128),
+                "fatal: Not possible to fast-forward, aborting."
+            ),
+            GitError::NonFastForward { .. }
+        ));
+        assert!(matches!(
+            GitError::from_stderr("pull", None, "error: something else"),
+            GitError::Other { .. }
+        ));
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let error = GitError::from_stderr("clone", Some(128), "fatal: repository not found");
+        assert_eq!(error.exit_code(), Some(128));
+        // Doesn't depend on the wording of the action or message
+        let error = GitError::from_stderr("fetch: origin", Some(1), "fatal: (exit code 2)");
+        assert_eq!(error.exit_code(), Some(1));
+        assert_eq!(
+            GitError::from_stderr("pull", None, "error: x (exit code 3): y").exit_code(),
+            None
+        );
+        assert_eq!(GitError::Cancelled.exit_code(), None);
+        assert_eq!(GitError::other("failed").exit_code(), None);
+    }
+
+    #[test]
+    fn test_parse_porcelain_status() {
+        let output = "# branch.oid 1234abcd\n\
+                      # branch.head main\n\
+                      # branch.upstream origin/main\n\
+                      # branch.ab +2 -1\n\
+                      1 .M N... 100644 100644 100644 aaa bbb src/lib.rs\n\
+                      2 R. N... 100644 100644 100644 aaa bbb R100 new.rs\told.rs\n\
+                      ? notes.txt\n";
+        let status = parse_porcelain_status(output);
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.head.as_deref(), Some("1234abcd"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        assert_eq!((status.changed, status.untracked), (2, 1));
+        assert!(!status.is_clean());
+    }
+
+    #[test]
+    fn test_parse_porcelain_status_detached() {
+        let status = parse_porcelain_status("# branch.oid (initial)\n# branch.head (detached)\n");
+        assert_eq!(status.branch, None);
+        assert_eq!(status.head, None);
+        assert!(status.is_clean());
+    }
+
+    #[test]
+    fn test_parse_progress_line() {
+        let update =
+            parse_progress_line("Receiving objects:  45% (450/1000), 1.50 MiB | 2.00 MiB/s")
+                .unwrap();
+        assert_eq!(update.phase, ProgressPhase::Receiving);
+        assert_eq!((update.current, update.total), (450, 1000));
+        assert_eq!(update.received_bytes, 1024 * 1024 * 3 / 2);
+
+        let update = parse_progress_line("remote: Compressing objects: 100% (3/3), done.").unwrap();
+        assert_eq!(update.phase, ProgressPhase::Preparing);
+        assert_eq!((update.current, update.total), (3, 3));
+
+        let update = parse_progress_line("remote: Enumerating objects: 42, done.").unwrap();
+        assert_eq!((update.current, update.total), (42, 0));
+
+        assert!(parse_progress_line("Cloning into 'repo'...").is_none());
+        assert!(parse_progress_line("fatal: repository not found").is_none());
+    }
+
+    #[test]
+    fn test_backend_by_name() {
+        let limits = RunLimits::default();
+        assert_eq!(backend_by_name("cli", &limits).unwrap().name(), "cli");
+        assert!(backend_by_name("svn", &limits).is_err());
+    }
+}
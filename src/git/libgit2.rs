@@ -0,0 +1,449 @@
+use super::backend::{
+    blocking, GitBackend, GitError, GitResult, ProgressCallback, ProgressPhase, RemoteRef,
+    RepoStatus, TransferProgress,
+};
+use super::process::RunLimits;
+use crate::models::CloneOptions;
+use git2::{
+    build::{CheckoutBuilder, RepoBuilder},
+    AutotagOption, Cred, CredentialType, Direction, ErrorClass, ErrorCode, FetchOptions,
+    RemoteCallbacks, Repository, StatusOptions,
+};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often libgit2 may ask for credentials before giving up
+const MAX_CREDENTIAL_ATTEMPTS: usize = 3;
+
+/// In-process backend built on libgit2, with transfer progress reporting
+#[derive(Debug, Default, Clone)]
+pub struct Libgit2Backend {
+    limits: RunLimits,
+}
+
+/// Why a transfer was aborted from inside a libgit2 callback
+type StopReason = Arc<Mutex<Option<GitError>>>;
+
+impl Libgit2Backend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply timeouts, the low-speed abort and cancellation to transfers.
+    ///
+    /// libgit2 can only be interrupted from its transfer callbacks, so a
+    /// connection that hangs before any data arrives is not covered.
+    pub fn with_limits(limits: RunLimits) -> Self {
+        Self { limits }
+    }
+
+    fn callbacks(
+        &self,
+        progress: Option<ProgressCallback>,
+        stop: &StopReason,
+    ) -> RemoteCallbacks<'static> {
+        let mut callbacks = RemoteCallbacks::new();
+        let limits = self.limits.clone();
+        let deadline = limits.deadline();
+        let stop = stop.clone();
+        let mut speed_window = (Instant::now(), 0usize);
+        callbacks.transfer_progress(move |stats| {
+            let mut reason = limits.check(deadline).err();
+            let (window_start, window_bytes) = speed_window;
+            let elapsed = window_start.elapsed();
+            if reason.is_none()
+                && limits.low_speed_time > Duration::ZERO
+                && elapsed >= limits.low_speed_time
+            {
+                let bytes = stats.received_bytes().saturating_sub(window_bytes) as f64;
+                if stats.received_objects() < stats.total_objects()
+                    && bytes / elapsed.as_secs_f64() < f64::from(limits.low_speed_limit)
+                {
+                    reason = Some(GitError::Network {
+                        message: format!(
+                            "transfer slower than {} bytes/s for {}s",
+                            limits.low_speed_limit,
+                            limits.low_speed_time.as_secs()
+                        ),
+                        exit_code: None,
+                    });
+                }
+                speed_window = (Instant::now(), stats.received_bytes());
+            }
+            if let Some(reason) = reason {
+                *stop.lock().unwrap_or_else(|e| e.into_inner()) = Some(reason);
+                return false;
+            }
+
+            if let Some(progress) = &progress {
+                let update = if stats.received_objects() < stats.total_objects() {
+                    TransferProgress {
+                        phase: ProgressPhase::Receiving,
+                        current: stats.received_objects(),
+                        total: stats.total_objects(),
+                        received_bytes: stats.received_bytes() as u64,
+                    }
+                } else {
+                    TransferProgress {
+                        phase: ProgressPhase::Resolving,
+                        current: stats.indexed_deltas(),
+                        total: stats.total_deltas(),
+                        received_bytes: stats.received_bytes() as u64,
+                    }
+                };
+                progress(&update);
+            }
+            true
+        });
+
+        // HTTPS tokens travel in the URL; SSH uses the agent and HTTPS falls back
+        // to the configured credential helper
+        let mut attempts = 0;
+        callbacks.credentials(move |url, username, allowed| {
+            attempts += 1;
+            if attempts > MAX_CREDENTIAL_ATTEMPTS {
+                return Err(git2::Error::new(
+                    ErrorCode::Auth,
+                    ErrorClass::Http,
+                    "no valid credentials available",
+                ));
+            }
+            if allowed.contains(CredentialType::SSH_KEY) {
+                Cred::ssh_key_from_agent(username.unwrap_or("git"))
+            } else if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+                let config = git2::Config::open_default()?;
+                Cred::credential_helper(&config, url, username)
+            } else {
+                Cred::default()
+            }
+        });
+        callbacks
+    }
+
+    fn fetch_options(
+        &self,
+        options: &CloneOptions,
+        progress: Option<ProgressCallback>,
+        stop: &StopReason,
+    ) -> GitResult<FetchOptions<'static>> {
+        if options.filter.is_some() {
+            return Err(GitError::Unsupported(
+                "partial clone filters need the cli backend".to_string(),
+            ));
+        }
+        let mut fetch = FetchOptions::new();
+        fetch.remote_callbacks(self.callbacks(progress, stop));
+        fetch.download_tags(AutotagOption::All);
+        if let Some(depth) = options.depth {
+            fetch.depth(depth as i32);
+        }
+        Ok(fetch)
+    }
+
+    fn checkout(progress: Option<ProgressCallback>) -> CheckoutBuilder<'static> {
+        let mut checkout = CheckoutBuilder::new();
+        if let Some(progress) = progress {
+            checkout.progress(move |_, current, total| {
+                progress(&TransferProgress {
+                    phase: ProgressPhase::CheckingOut,
+                    current,
+                    total,
+                    received_bytes: 0,
+                });
+            });
+        }
+        checkout
+    }
+}
+
+fn open(local_path: &Path) -> GitResult<Repository> {
+    Repository::open(local_path).map_err(|e| GitError::NotARepository {
+        message: format!("{}: {}", local_path.display(), e.message()),
+        exit_code: None,
+    })
+}
+
+/// The reason a callback stopped the transfer, or the mapped libgit2 error
+fn stopped_or(stop: &StopReason, error: git2::Error) -> GitError {
+    stop.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .unwrap_or_else(|| to_git_error(error))
+}
+
+/// Map a libgit2 error onto the backend error kinds
+fn to_git_error(error: git2::Error) -> GitError {
+    let message = error.message().to_string();
+    match (error.code(), error.class()) {
+        (ErrorCode::Auth, _) | (ErrorCode::Certificate, _) => GitError::Authentication {
+            message,
+            exit_code: None,
+        },
+        (ErrorCode::NotFastForward, _) => GitError::NonFastForward {
+            message,
+            exit_code: None,
+        },
+        (_, ErrorClass::Net) | (_, ErrorClass::Ssl) | (_, ErrorClass::Ssh) => GitError::Network {
+            message,
+            exit_code: None,
+        },
+        (_, ErrorClass::Http) if message.contains("404") => GitError::RemoteNotFound {
+            message,
+            exit_code: None,
+        },
+        (_, ErrorClass::Http) => GitError::Network {
+            message,
+            exit_code: None,
+        },
+        (ErrorCode::NotFound, _) => GitError::RemoteNotFound {
+            message,
+            exit_code: None,
+        },
+        // Local remotes that don't exist
+        (_, ErrorClass::Os) if message.starts_with("failed to resolve path") => {
+            GitError::RemoteNotFound {
+                message,
+                exit_code: None,
+            }
+        }
+        _ => GitError::other(message),
+    }
+}
+
+/// The blocking libgit2 calls behind the [`GitBackend`] methods
+impl Libgit2Backend {
+    fn clone_repo_blocking(
+        &self,
+        url: &str,
+        target: &Path,
+        options: &CloneOptions,
+        sparse: bool,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()> {
+        if sparse {
+            return Err(GitError::Unsupported(
+                "sparse checkout needs the cli backend".to_string(),
+            ));
+        }
+        if options.single_branch {
+            return Err(GitError::Unsupported(
+                "single-branch clones need the cli backend".to_string(),
+            ));
+        }
+        self.limits.check(None)?;
+        let stop = StopReason::default();
+        let mut builder = RepoBuilder::new();
+        if let Some(branch) = &options.branch {
+            builder.branch(branch);
+        }
+        builder
+            .fetch_options(self.fetch_options(options, progress.clone(), &stop)?)
+            .with_checkout(Self::checkout(progress))
+            .clone(url, target)
+            .map(|_| ())
+            .map_err(|e| stopped_or(&stop, e))
+    }
+
+    fn fetch_blocking(
+        &self,
+        local_path: &Path,
+        remote: &str,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()> {
+        self.limits.check(None)?;
+        let repo = open(local_path)?;
+        let mut remote = repo.find_remote(remote).map_err(to_git_error)?;
+        let stop = StopReason::default();
+        let mut fetch = self.fetch_options(&CloneOptions::default(), progress, &stop)?;
+        fetch.prune(git2::FetchPrune::On);
+        remote
+            .fetch::<&str>(&[], Some(&mut fetch), None)
+            .map_err(|e| stopped_or(&stop, e))
+    }
+
+    fn pull_blocking(
+        &self,
+        local_path: &Path,
+        options: &CloneOptions,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()> {
+        self.limits.check(None)?;
+        let repo = open(local_path)?;
+        let head = repo.head().map_err(to_git_error)?;
+        let branch = head
+            .shorthand()
+            .filter(|_| head.is_branch())
+            .ok_or_else(|| GitError::other("HEAD is detached, nothing to pull"))?
+            .to_string();
+
+        let upstream_name = repo
+            .branch_upstream_remote(&format!("refs/heads/{}", branch))
+            .map_err(to_git_error)?;
+        let remote_name = upstream_name.as_str().unwrap_or("origin").to_string();
+        let mut remote = repo.find_remote(&remote_name).map_err(to_git_error)?;
+        let stop = StopReason::default();
+        // A depth would cut the new commits off from the shallow history, see
+        // `CliBackend::pull`
+        let options = CloneOptions {
+            depth: None,
+            ..options.clone()
+        };
+        let mut fetch = self.fetch_options(&options, progress.clone(), &stop)?;
+        remote
+            .fetch::<&str>(&[], Some(&mut fetch), None)
+            .map_err(|e| stopped_or(&stop, e))?;
+
+        let upstream = repo
+            .find_branch(&branch, git2::BranchType::Local)
+            .and_then(|local| local.upstream())
+            .map_err(to_git_error)?;
+        let upstream_oid = upstream
+            .get()
+            .target()
+            .ok_or_else(|| GitError::other(format!("Upstream of {} has no target", branch)))?;
+        let annotated = repo
+            .find_annotated_commit(upstream_oid)
+            .map_err(to_git_error)?;
+        let (analysis, _) = repo.merge_analysis(&[&annotated]).map_err(to_git_error)?;
+
+        if analysis.is_up_to_date() {
+            return Ok(());
+        }
+        if !analysis.is_fast_forward() {
+            return Err(GitError::NonFastForward {
+                message: format!("{} has diverged from its upstream", branch),
+                exit_code: None,
+            });
+        }
+
+        let target = repo.find_object(upstream_oid, None).map_err(to_git_error)?;
+        repo.checkout_tree(&target, Some(Self::checkout(progress).safe()))
+            .map_err(to_git_error)?;
+        repo.find_reference(&format!("refs/heads/{}", branch))
+            .and_then(|mut reference| reference.set_target(upstream_oid, "pull: fast-forward"))
+            .map_err(to_git_error)?;
+        Ok(())
+    }
+
+    fn status_blocking(&self, local_path: &Path) -> GitResult<RepoStatus> {
+        let repo = open(local_path)?;
+        let mut status = RepoStatus::default();
+
+        if let Ok(head) = repo.head() {
+            status.head = head.target().map(|oid| oid.to_string());
+            if head.is_branch() {
+                status.branch = head.shorthand().map(|name| name.to_string());
+            }
+        }
+
+        if let Some(branch) = &status.branch {
+            let local = repo
+                .find_branch(branch, git2::BranchType::Local)
+                .map_err(to_git_error)?;
+            if let Ok(upstream) = local.upstream() {
+                status.upstream = upstream.name().ok().flatten().map(|n| n.to_string());
+                if let (Some(local_oid), Some(upstream_oid)) =
+                    (local.get().target(), upstream.get().target())
+                {
+                    let (ahead, behind) = repo
+                        .graph_ahead_behind(local_oid, upstream_oid)
+                        .map_err(to_git_error)?;
+                    status.ahead = ahead;
+                    status.behind = behind;
+                }
+            }
+        }
+
+        let mut options = StatusOptions::new();
+        options.include_untracked(true).include_ignored(false);
+        let statuses = repo.statuses(Some(&mut options)).map_err(to_git_error)?;
+        for entry in statuses.iter() {
+            if entry.status() == git2::Status::WT_NEW {
+                status.untracked += 1;
+            } else if !entry.status().is_ignored() {
+                status.changed += 1;
+            }
+        }
+
+        Ok(status)
+    }
+
+    fn ls_remote_blocking(&self, url: &str) -> GitResult<Vec<RemoteRef>> {
+        let mut remote = git2::Remote::create_detached(url).map_err(to_git_error)?;
+        let connection = remote
+            .connect_auth(
+                Direction::Fetch,
+                Some(self.callbacks(None, &StopReason::default())),
+                None,
+            )
+            .map_err(to_git_error)?;
+        let refs = connection
+            .list()
+            .map_err(to_git_error)?
+            .iter()
+            .map(|head| RemoteRef {
+                name: head.name().to_string(),
+                oid: head.oid().to_string(),
+            })
+            .collect();
+        Ok(refs)
+    }
+}
+
+#[async_trait::async_trait]
+impl GitBackend for Libgit2Backend {
+    fn name(&self) -> &'static str {
+        "libgit2"
+    }
+
+    async fn clone_repo(
+        &self,
+        url: &str,
+        target: &Path,
+        options: &CloneOptions,
+        sparse: bool,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()> {
+        let (backend, url, target, options) = (
+            self.clone(),
+            url.to_string(),
+            target.to_path_buf(),
+            options.clone(),
+        );
+        blocking(move || backend.clone_repo_blocking(&url, &target, &options, sparse, progress))
+            .await
+    }
+
+    async fn fetch(
+        &self,
+        local_path: &Path,
+        remote: &str,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()> {
+        let (backend, local_path, remote) =
+            (self.clone(), local_path.to_path_buf(), remote.to_string());
+        blocking(move || backend.fetch_blocking(&local_path, &remote, progress)).await
+    }
+
+    async fn pull(
+        &self,
+        local_path: &Path,
+        options: &CloneOptions,
+        progress: Option<ProgressCallback>,
+    ) -> GitResult<()> {
+        let (backend, local_path, options) =
+            (self.clone(), local_path.to_path_buf(), options.clone());
+        blocking(move || backend.pull_blocking(&local_path, &options, progress)).await
+    }
+
+    async fn status(&self, local_path: &Path) -> GitResult<RepoStatus> {
+        let (backend, local_path) = (self.clone(), local_path.to_path_buf());
+        blocking(move || backend.status_blocking(&local_path)).await
+    }
+
+    async fn ls_remote(&self, url: &str) -> GitResult<Vec<RemoteRef>> {
+        let (backend, url) = (self.clone(), url.to_string());
+        blocking(move || backend.ls_remote_blocking(&url)).await
+    }
+}
@@ -0,0 +1,303 @@
+use super::backend::{GitError, GitResult};
+use std::io::{BufReader, Read};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+/// How often a running git process is checked for cancellation and timeouts
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often an exiting git process is checked once its output is closed
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Shared flag set when the user asks to stop (e.g. Ctrl-C)
+#[derive(Debug, Clone, Default)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask running and future git operations to stop
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Limits applied to the git processes a backend runs
+#[derive(Debug, Clone)]
+pub struct RunLimits {
+    /// Kill a single operation that runs longer than this
+    pub timeout: Option<Duration>,
+    /// Abort transfers slower than this many bytes per second...
+    pub low_speed_limit: u32,
+    /// ...for this long (0 disables the low-speed abort)
+    pub low_speed_time: Duration,
+    /// Kill running operations once set
+    pub cancel: CancelFlag,
+}
+
+impl Default for RunLimits {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            low_speed_limit: 1000,
+            low_speed_time: Duration::from_secs(60),
+            cancel: CancelFlag::new(),
+        }
+    }
+}
+
+impl RunLimits {
+    /// Point in time after which an operation started now times out
+    pub fn deadline(&self) -> Option<Instant> {
+        self.timeout.map(|timeout| Instant::now() + timeout)
+    }
+
+    /// Error for an operation stopped by these limits, if any applies
+    pub fn check(&self, deadline: Option<Instant>) -> GitResult<()> {
+        if self.cancel.is_cancelled() {
+            return Err(GitError::Cancelled);
+        }
+        match (deadline, self.timeout) {
+            (Some(deadline), Some(timeout)) if Instant::now() >= deadline => {
+                Err(GitError::TimedOut(timeout))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A `git` command that never waits for input on the terminal
+pub fn git_command() -> Command {
+    let mut command = Command::new("git");
+    command
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GCM_INTERACTIVE", "never")
+        .stdin(Stdio::null());
+    // Fail instead of asking about unknown host keys or passphrases, and notice
+    // dead SSH connections; a user-provided ssh command is left alone
+    if std::env::var_os("GIT_SSH_COMMAND").is_none() && std::env::var_os("GIT_SSH").is_none() {
+        command.env(
+            "GIT_SSH_COMMAND",
+            "ssh -o BatchMode=yes -o ServerAliveInterval=15 -o ServerAliveCountMax=4",
+        );
+    }
+    command
+}
+
+/// Run a git command under the limits and return its standard output.
+///
+/// Every stderr line (split on `\r` and `\n`) is offered to `on_stderr_line`;
+/// lines it doesn't consume end up in the error message if the command fails.
+/// On cancellation or timeout the process is killed.
+pub fn run(
+    command: &mut Command,
+    action: &str,
+    limits: &RunLimits,
+    mut on_stderr_line: impl FnMut(&str) -> bool,
+) -> GitResult<String> {
+    limits.check(None)?;
+    let deadline = limits.deadline();
+
+    if limits.low_speed_time > Duration::ZERO {
+        command
+            .env(
+                "GIT_HTTP_LOW_SPEED_LIMIT",
+                limits.low_speed_limit.to_string(),
+            )
+            .env(
+                "GIT_HTTP_LOW_SPEED_TIME",
+                limits.low_speed_time.as_secs().max(1).to_string(),
+            );
+    }
+    // Its own process group lets a kill reach the ssh, remote helper and
+    // index-pack processes git starts, not only git itself
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| GitError::other(format!("Failed to execute git {}: {}", action, e)))?;
+
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let stdout_reader = std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        output
+    });
+
+    let (lines, received) = mpsc::channel();
+    let stderr = child.stderr.take().expect("stderr is piped");
+    std::thread::spawn(move || {
+        let mut line = Vec::new();
+        for byte in BufReader::new(stderr).bytes() {
+            let Ok(byte) = byte else { break };
+            if byte != b'\r' && byte != b'\n' {
+                line.push(byte);
+                continue;
+            }
+            if !line.is_empty()
+                && lines
+                    .send(String::from_utf8_lossy(&line).to_string())
+                    .is_err()
+            {
+                return;
+            }
+            line.clear();
+        }
+        if !line.is_empty() {
+            let _ = lines.send(String::from_utf8_lossy(&line).to_string());
+        }
+    });
+
+    let mut messages = String::new();
+    let status = loop {
+        if let Err(stopped) = limits.check(deadline) {
+            kill(&mut child);
+            return Err(stopped);
+        }
+        match received.recv_timeout(POLL_INTERVAL) {
+            Ok(line) => {
+                if !on_stderr_line(&line) {
+                    messages.push_str(&line);
+                    messages.push('\n');
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            // stderr is closed, so the process is exiting
+            Err(mpsc::RecvTimeoutError::Disconnected) => match wait(&mut child, limits, deadline) {
+                Ok(status) => break status,
+                Err(stopped) => return Err(stopped),
+            },
+        }
+    };
+    let stdout = stdout_reader.join().unwrap_or_default();
+
+    // Cancelled while git was exiting on its own
+    limits.check(None)?;
+    if !status.success() {
+        return Err(GitError::from_stderr(action, status.code(), &messages));
+    }
+    Ok(String::from_utf8_lossy(&stdout).to_string())
+}
+
+fn wait(child: &mut Child, limits: &RunLimits, deadline: Option<Instant>) -> GitResult<ExitStatus> {
+    loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|e| GitError::other(format!("Failed to wait for git: {}", e)))?
+        {
+            return Ok(status);
+        }
+        if let Err(stopped) = limits.check(deadline) {
+            kill(child);
+            return Err(stopped);
+        }
+        std::thread::sleep(EXIT_POLL_INTERVAL);
+    }
+}
+
+/// Kill git together with every process it started
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: signals the process group `run` created for this child,
+        // which is not reaped (and so not reused) before the `wait` below
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_command_is_non_interactive() {
+        let command = git_command();
+        let envs: Vec<_> = command.get_envs().collect();
+        assert!(envs.contains(&(
+            std::ffi::OsStr::new("GIT_TERMINAL_PROMPT"),
+            Some(std::ffi::OsStr::new("0"))
+        )));
+    }
+
+    #[test]
+    fn test_cancelled_limits_refuse_to_start() {
+        let limits = RunLimits::default();
+        limits.cancel.cancel();
+        let mut command = git_command();
+        command.arg("--version");
+        assert_eq!(
+            run(&mut command, "version", &limits, |_| false),
+            Err(GitError::Cancelled)
+        );
+    }
+
+    #[test]
+    fn test_timeout_kills_process() {
+        let limits = RunLimits {
+            timeout: Some(Duration::from_millis(500)),
+            ..Default::default()
+        };
+        // A shell alias that sleeps stands in for a hung network operation; the
+        // sleep is a grandchild of git, like ssh or git-remote-https
+        let pid_file =
+            std::env::temp_dir().join(format!("super-clone-hang-{}", uuid::Uuid::new_v4()));
+        let alias = format!(
+            "alias.hang=!sleep 30 & echo $! > '{}'; wait",
+            pid_file.display()
+        );
+        let mut command = git_command();
+        command.args(["-c", &alias, "hang"]);
+        let started = Instant::now();
+        let result = run(&mut command, "config", &limits, |_| false);
+        assert_eq!(result, Err(GitError::TimedOut(Duration::from_millis(500))));
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        #[cfg(unix)]
+        {
+            let pid = std::fs::read_to_string(&pid_file).unwrap();
+            let sleep_alive = || {
+                Command::new("kill")
+                    .args(["-0", pid.trim()])
+                    .stderr(Stdio::null())
+                    .status()
+                    .unwrap()
+                    .success()
+            };
+            // The killed sleep may take a moment to be reaped
+            let killed = Instant::now();
+            while sleep_alive() && killed.elapsed() < Duration::from_secs(5) {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            assert!(!sleep_alive());
+        }
+        let _ = std::fs::remove_file(&pid_file);
+    }
+
+    #[test]
+    fn test_stderr_lines_are_offered() {
+        let mut command = git_command();
+        command.args(["rev-parse", "--verify", "refs/heads/does-not-exist"]);
+        command.current_dir(std::env::temp_dir());
+        let mut seen = Vec::new();
+        let result = run(&mut command, "rev-parse", &RunLimits::default(), |line| {
+            seen.push(line.to_string());
+            false
+        });
+        assert!(result.is_err());
+        assert!(!seen.is_empty());
+    }
+}
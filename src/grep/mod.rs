@@ -0,0 +1,165 @@
+use crate::exec::ExecTarget;
+use crate::git::process::git_command;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// What to look for and where
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    /// Extended regular expression, or a literal string with `fixed_strings`
+    pub pattern: String,
+    pub fixed_strings: bool,
+    pub ignore_case: bool,
+    /// Pathspecs limiting the search, e.g. `*.toml` or `src/`
+    pub paths: Vec<String>,
+    /// Repositories searched at the same time
+    pub parallel: usize,
+}
+
+/// A matching line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GrepMatch {
+    /// File path relative to the repository root
+    pub path: String,
+    pub line: u64,
+    pub text: String,
+}
+
+/// Matches found in one repository
+#[derive(Debug, Clone, Serialize)]
+pub struct RepositoryMatches {
+    pub repository: String,
+    pub path: String,
+    pub matches: Vec<GrepMatch>,
+    /// Why the repository could not be searched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn grep_command(target: &ExecTarget, options: &GrepOptions) -> Command {
+    let mut command = git_command();
+    command
+        .arg("-C")
+        .arg(&target.path)
+        .args(["grep", "-z", "-n", "-I", "--no-color"]);
+    if options.fixed_strings {
+        command.arg("-F");
+    } else {
+        command.arg("-E");
+    }
+    if options.ignore_case {
+        command.arg("-i");
+    }
+    command.arg("-e").arg(&options.pattern).arg("--");
+    command.args(&options.paths);
+    command.into()
+}
+
+/// Parse `git grep -z -n` output: `path NUL line NUL text` per line
+pub fn parse_grep_output(output: &str) -> Vec<GrepMatch> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\0');
+            let path = fields.next()?;
+            let line = fields.next()?.parse().ok()?;
+            let text = fields.next()?;
+            Some(GrepMatch {
+                path: path.to_string(),
+                line,
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+async fn search_one(target: ExecTarget, options: Arc<GrepOptions>) -> RepositoryMatches {
+    let mut result = RepositoryMatches {
+        repository: target.name.clone(),
+        path: target.path.to_string_lossy().to_string(),
+        matches: Vec::new(),
+        error: None,
+    };
+    if !target.path.is_dir() {
+        result.error = Some(format!("{} does not exist", target.path.display()));
+        return result;
+    }
+
+    match grep_command(&target, &options).output().await {
+        Ok(output) => match output.status.code() {
+            Some(0) => result.matches = parse_grep_output(&String::from_utf8_lossy(&output.stdout)),
+            // git grep exits with 1 when nothing matched
+            Some(1) => {}
+            _ => {
+                result.error = Some(format!(
+                    "git grep: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))
+            }
+        },
+        Err(e) => result.error = Some(format!("Failed to execute git grep: {}", e)),
+    }
+    result
+}
+
+/// Search every target with `git grep`, several repositories at a time.
+///
+/// Results come back in the order of `targets`, including repositories
+/// without matches.
+pub async fn search(
+    targets: &[ExecTarget],
+    options: &GrepOptions,
+) -> anyhow::Result<Vec<RepositoryMatches>> {
+    if options.pattern.is_empty() {
+        return Err(anyhow::anyhow!("No search pattern given"));
+    }
+
+    let slots = Arc::new(Semaphore::new(options.parallel.max(1)));
+    let options = Arc::new(options.clone());
+    let mut running = JoinSet::new();
+    for (index, target) in targets.iter().cloned().enumerate() {
+        let slots = slots.clone();
+        let options = options.clone();
+        running.spawn(async move {
+            let _permit = slots.acquire_owned().await;
+            (index, search_one(target, options).await)
+        });
+    }
+
+    let mut results = Vec::with_capacity(targets.len());
+    while let Some(finished) = running.join_next().await {
+        results.push(finished?);
+    }
+    results.sort_by_key(|(index, _)| *index);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_grep_output() {
+        let output = "src/main.rs\x0012\x00use old_lib::Client;\n\
+                      odd:name.txt\x003\x00a:b:c\n\
+                      Binary file x matches\n";
+        assert_eq!(
+            parse_grep_output(output),
+            vec![
+                GrepMatch {
+                    path: "src/main.rs".to_string(),
+                    line: 12,
+                    text: "use old_lib::Client;".to_string(),
+                },
+                GrepMatch {
+                    path: "odd:name.txt".to_string(),
+                    line: 3,
+                    text: "a:b:c".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use super::local::find_git_repositories;
+use super::ImportEntry;
+use crate::git::GitOperations;
+use crate::Result;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+/// ghq's root: `GHQ_ROOT`, then `git config ghq.root`, then `~/ghq`.
+///
+/// When several roots are configured the first (primary) one is used.
+pub fn default_root() -> PathBuf {
+    let configured = std::env::var("GHQ_ROOT")
+        .ok()
+        .and_then(|roots| roots.split(':').next().map(str::to_string))
+        .filter(|root| !root.is_empty())
+        .or_else(|| {
+            let output = Command::new("git")
+                .args(["config", "--path", "--get-all", "ghq.root"])
+                .output()
+                .ok()?;
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .map(str::to_string)
+        });
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    match configured {
+        Some(root) => match root.strip_prefix("~/") {
+            Some(rest) => home.join(rest),
+            None => PathBuf::from(root),
+        },
+        None => home.join("ghq"),
+    }
+}
+
+/// Find the clones of a ghq root (`<root>/<host>/<owner>/<name>`).
+///
+/// The URL is taken from each clone's `origin` remote, falling back to the
+/// HTTPS URL its path implies.
+pub fn scan(root: &Path, git_ops: &GitOperations) -> Result<Vec<ImportEntry>> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("Cannot read directory {}: {}", root.display(), e))?;
+
+    let mut entries = Vec::new();
+    for path in find_git_repositories(&root)? {
+        let relative = path.strip_prefix(&root).unwrap_or(&path);
+        let origin = git_ops
+            .get_remote_url(&path.to_string_lossy(), "origin")
+            .ok()
+            .flatten();
+        let Some(url) = origin.or_else(|| url_from_path(relative)) else {
+            continue;
+        };
+        entries.push(ImportEntry {
+            url,
+            path,
+            revision: None,
+        });
+    }
+    Ok(entries)
+}
+
+/// Parse `ghq list` output, with paths relative to `root` or absolute (`ghq list -p`)
+pub fn parse_list(content: &str, root: &Path) -> Result<Vec<ImportEntry>> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let path = root.join(line);
+            let relative = path.strip_prefix(root).map_err(|_| {
+                anyhow::anyhow!("{} is not below the ghq root {}", line, root.display())
+            })?;
+            let url = url_from_path(relative).ok_or_else(|| {
+                anyhow::anyhow!("Expected <host>/<owner>/<name> in ghq list: {}", line)
+            })?;
+            Ok(ImportEntry {
+                url,
+                path,
+                revision: None,
+            })
+        })
+        .collect()
+}
+
+/// HTTPS URL of a `<host>/<owner>/<name>` path below the ghq root
+fn url_from_path(relative: &Path) -> Option<String> {
+    let parts = relative
+        .components()
+        .map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (parts.len() >= 3).then(|| format!("https://{}.git", parts.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ghq_list() {
+        let root = Path::new("/home/dev/ghq");
+        let entries = parse_list(
+            "github.com/acme/api\n\n/home/dev/ghq/gitlab.com/acme/platform/web\n",
+            root,
+        )
+        .unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                ImportEntry {
+                    url: "https://github.com/acme/api.git".to_string(),
+                    path: root.join("github.com/acme/api"),
+                    revision: None,
+                },
+                ImportEntry {
+                    url: "https://gitlab.com/acme/platform/web.git".to_string(),
+                    path: root.join("gitlab.com/acme/platform/web"),
+                    revision: None,
+                },
+            ]
+        );
+
+        assert!(parse_list("/elsewhere/github.com/acme/api\n", root).is_err());
+        assert!(parse_list("github.com/acme\n", root).is_err());
+        assert!(parse_list("github.com/../acme/api\n", root).is_err());
+    }
+}
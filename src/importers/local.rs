@@ -0,0 +1,89 @@
+use crate::git::GitOperations;
+use crate::models::{CloneStatus, Provider, Repository};
+use crate::remote::RemoteUrl;
+use crate::Result;
+use std::path::{Path, PathBuf};
+
+/// A git repository found on disk
+#[derive(Debug)]
+pub struct LocalClone {
+    /// Absolute path of the working tree
+    pub path: PathBuf,
+    /// Repository record derived from the `origin` remote
+    pub repository: Result<Repository>,
+}
+
+/// Walk a directory tree and collect every git working tree below it.
+///
+/// Repositories are not descended into, so nested clones (other than submodules,
+/// which live inside their superproject) are reported only once.
+pub fn find_git_repositories(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        if dir.join(".git").exists() {
+            found.push(dir);
+            continue;
+        }
+
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            // Unreadable directories are skipped rather than aborting the walk
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+            if file_type.is_dir() && !file_type.is_symlink() {
+                pending.push(entry.path());
+            }
+        }
+    }
+
+    found.sort();
+    Ok(found)
+}
+
+/// Find existing clones under `root` and work out which repository each one is
+pub fn scan(
+    root: &Path,
+    git_ops: &GitOperations,
+    provider: Option<Provider>,
+    gitlab_host: Option<&str>,
+) -> Result<Vec<LocalClone>> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("Cannot read directory {}: {}", root.display(), e))?;
+
+    let mut clones = Vec::new();
+    for path in find_git_repositories(&root)? {
+        let repository = identify(&path, git_ops, provider.clone(), gitlab_host);
+        clones.push(LocalClone { path, repository });
+    }
+    Ok(clones)
+}
+
+/// Build a `Cloned` repository record from a working tree's `origin` remote
+fn identify(
+    path: &Path,
+    git_ops: &GitOperations,
+    provider: Option<Provider>,
+    gitlab_host: Option<&str>,
+) -> Result<Repository> {
+    let local_path = path.to_string_lossy().to_string();
+    let url = git_ops
+        .get_remote_url(&local_path, "origin")?
+        .ok_or_else(|| anyhow::anyhow!("No origin remote"))?;
+    let remote = RemoteUrl::parse(&url)?;
+    let provider = provider
+        .or_else(|| remote.provider(gitlab_host))
+        .ok_or_else(|| anyhow::anyhow!("Unknown provider for host {}", remote.host))?;
+
+    let mut repo = remote.to_repository(provider);
+    repo.set_local_path(local_path);
+    repo.update_status(CloneStatus::Cloned);
+    Ok(repo)
+}
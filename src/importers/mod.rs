@@ -0,0 +1,103 @@
+pub mod ghq;
+pub mod local;
+pub mod mrconfig;
+pub mod repo_manifest;
+
+use crate::models::{CloneStatus, Provider, Repository};
+use crate::remote::RemoteUrl;
+use crate::Result;
+use std::path::PathBuf;
+
+/// A repository listed by another tool (repo manifest, myrepos, ghq)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportEntry {
+    /// Clone URL as the tool records it
+    pub url: String,
+    /// Where the tool keeps, or would keep, the clone
+    pub path: PathBuf,
+    /// Branch, tag or commit the tool checks out
+    pub revision: Option<String>,
+}
+
+impl ImportEntry {
+    /// Build a repository record, `Cloned` when the clone is already on disk.
+    ///
+    /// `provider` overrides the provider guessed from the URL host.
+    pub fn to_repository(
+        &self,
+        provider: Option<Provider>,
+        gitlab_host: Option<&str>,
+    ) -> Result<Repository> {
+        let remote = RemoteUrl::parse(&self.url)?;
+        let provider = provider
+            .or_else(|| remote.provider(gitlab_host))
+            .ok_or_else(|| anyhow::anyhow!("Unknown provider for host {}", remote.host))?;
+
+        let mut repo = remote.to_repository(provider);
+        // Keep the URL the tool uses, it may carry a port or another scheme
+        if self.url.starts_with("http://") || self.url.starts_with("https://") {
+            repo.clone_url_https = self.url.clone();
+        } else {
+            repo.clone_url_ssh = self.url.clone();
+        }
+        if self.path.join(".git").exists() {
+            repo.set_local_path(self.path.to_string_lossy().to_string());
+            repo.update_status(CloneStatus::Cloned);
+        }
+        Ok(repo)
+    }
+
+    /// Branch or tag to check out, when the revision names one
+    pub fn branch(&self) -> Option<String> {
+        let revision = self.revision.as_deref()?;
+        if is_commit(revision) {
+            return None;
+        }
+        let name = revision
+            .strip_prefix("refs/heads/")
+            .or_else(|| revision.strip_prefix("refs/tags/"))
+            .unwrap_or(revision);
+        Some(name.to_string())
+    }
+
+    /// Commit to pin, when the revision is a full commit ID
+    pub fn commit(&self) -> Option<String> {
+        self.revision.clone().filter(|revision| is_commit(revision))
+    }
+}
+
+fn is_commit(revision: &str) -> bool {
+    revision.len() == 40 && revision.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_entry() {
+        let mut entry = ImportEntry {
+            url: "ssh://git@gitlab.example.com:2222/team/api.git".to_string(),
+            path: PathBuf::from("/nonexistent/api"),
+            revision: Some("refs/heads/stable".to_string()),
+        };
+        let repo = entry
+            .to_repository(None, Some("gitlab.example.com"))
+            .unwrap();
+        assert_eq!(repo.full_name, "team/api");
+        assert_eq!(repo.provider, "gitlab");
+        assert_eq!(repo.clone_url_ssh, entry.url);
+        assert_eq!(repo.status, "not_cloned");
+        assert_eq!(entry.branch().as_deref(), Some("stable"));
+        assert_eq!(entry.commit(), None);
+
+        entry.revision = Some("0123456789abcdef0123456789abcdef01234567".to_string());
+        assert_eq!(entry.branch(), None);
+        assert!(entry.commit().is_some());
+
+        entry.url = "https://android.googlesource.com/platform/build".to_string();
+        assert!(entry.to_repository(None, None).is_err());
+        let repo = entry.to_repository(Some(Provider::GitLab), None).unwrap();
+        assert_eq!(repo.clone_url_https, entry.url);
+    }
+}
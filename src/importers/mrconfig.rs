@@ -0,0 +1,229 @@
+use super::ImportEntry;
+use crate::Result;
+use anyhow::Context;
+use std::path::Path;
+
+/// `git clone` options that take their value as the next argument
+const OPTIONS_WITH_VALUE: &[&str] = &[
+    "-b",
+    "--branch",
+    "-o",
+    "--origin",
+    "-c",
+    "--config",
+    "-u",
+    "--upload-pack",
+    "-j",
+    "--jobs",
+    "--depth",
+    "--filter",
+    "--reference",
+    "--reference-if-able",
+    "--separate-git-dir",
+    "--shallow-since",
+    "--shallow-exclude",
+    "--template",
+];
+
+/// Read a myrepos `.mrconfig`; section paths are relative to its directory
+pub fn read(path: &Path) -> Result<Vec<ImportEntry>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    parse(&content, base).with_context(|| format!("Invalid mrconfig {}", path.display()))
+}
+
+/// Parse `.mrconfig` content, keeping the sections checked out with `git clone`
+pub fn parse(content: &str, base: &Path) -> Result<Vec<ImportEntry>> {
+    let mut entries = Vec::new();
+    let mut section: Option<String> = None;
+    let mut checkout: Option<String> = None;
+
+    let mut finish = |section: &Option<String>, checkout: &mut Option<String>| -> Result<()> {
+        if let (Some(section), Some(command)) = (section, checkout.take()) {
+            if let Some((url, branch)) = parse_git_clone(&command)? {
+                entries.push(ImportEntry {
+                    url,
+                    path: base.join(section),
+                    revision: branch,
+                });
+            }
+        }
+        Ok(())
+    };
+
+    let mut in_checkout = false;
+    for line in content.lines() {
+        // Indented lines continue the previous value
+        if line.starts_with([' ', '\t']) && !line.trim().is_empty() {
+            if in_checkout {
+                if let Some(command) = checkout.as_mut() {
+                    command.push(' ');
+                    command.push_str(line.trim());
+                }
+            }
+            continue;
+        }
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            finish(&section, &mut checkout)?;
+            let name = name.trim();
+            section = (name != "DEFAULT").then(|| name.to_string());
+            in_checkout = false;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(anyhow::anyhow!("Expected key = value: {}", line));
+        };
+        in_checkout = key.trim() == "checkout";
+        if in_checkout {
+            checkout = Some(value.trim().to_string());
+        }
+    }
+    finish(&section, &mut checkout)?;
+    Ok(entries)
+}
+
+/// URL and branch of the `git clone` in a checkout command, `None` for other VCSs
+fn parse_git_clone(command: &str) -> Result<Option<(String, Option<String>)>> {
+    let words = split_words(command)?;
+    let Some(start) = words
+        .windows(2)
+        .position(|pair| pair[0] == "git" && pair[1] == "clone")
+    else {
+        return Ok(None);
+    };
+
+    let mut url = None;
+    let mut branch = None;
+    let mut args = words[start + 2..]
+        .iter()
+        .take_while(|word| !matches!(word.as_str(), "&&" | "||" | ";" | "|"));
+    while let Some(arg) = args.next() {
+        if let Some(value) = arg.strip_prefix("--branch=") {
+            branch = Some(value.to_string());
+        } else if arg == "-b" || arg == "--branch" {
+            branch = args.next().cloned();
+        } else if OPTIONS_WITH_VALUE.contains(&arg.as_str()) {
+            args.next();
+        } else if !arg.starts_with('-') && url.is_none() {
+            url = Some(arg.clone());
+        }
+    }
+    let url = url.ok_or_else(|| anyhow::anyhow!("git clone without a URL: {}", command))?;
+    Ok(Some((url, branch)))
+}
+
+/// Split a shell command into words, honoring quotes and backslash escapes
+fn split_words(command: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(anyhow::anyhow!("Unterminated quote: {}", command)),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => word.extend(chars.next()),
+                        Some(c) => word.push(c),
+                        None => return Err(anyhow::anyhow!("Unterminated quote: {}", command)),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                word.extend(chars.next());
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const MRCONFIG: &str = r#"
+[DEFAULT]
+git_gc = git gc "$@"
+
+# Work projects
+[src/api]
+checkout = git clone 'git@github.com:acme/api.git' 'api'
+
+[src/web]
+checkout =
+    git clone --depth 1 -b develop
+        "https://gitlab.com/acme/web.git" web &&
+    cd web && git config user.email dev@acme.test
+update = git pull --rebase
+
+[/opt/notes]
+checkout = svn co svn://example.com/notes notes
+
+[src/no-checkout]
+update = git pull
+"#;
+
+    #[test]
+    fn test_parse_mrconfig() {
+        let entries = parse(MRCONFIG, Path::new("/home/dev")).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                ImportEntry {
+                    url: "git@github.com:acme/api.git".to_string(),
+                    path: PathBuf::from("/home/dev/src/api"),
+                    revision: None,
+                },
+                ImportEntry {
+                    url: "https://gitlab.com/acme/web.git".to_string(),
+                    path: PathBuf::from("/home/dev/src/web"),
+                    revision: Some("develop".to_string()),
+                },
+            ]
+        );
+
+        assert!(parse("[a]\ncheckout = git clone 'oops\n", Path::new("/")).is_err());
+        assert!(parse("[a]\ncheckout = git clone --depth 1\n", Path::new("/")).is_err());
+        assert!(parse("[a]\nnot a key\n", Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            split_words(r#"git clone "a b" 'c "d"' e\ f"#).unwrap(),
+            vec!["git", "clone", "a b", "c \"d\"", "e f"]
+        );
+    }
+}
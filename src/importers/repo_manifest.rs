@@ -0,0 +1,274 @@
+use super::ImportEntry;
+use crate::Result;
+use anyhow::Context;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Includes nested deeper than this are treated as a loop
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Debug, Default)]
+struct Remote {
+    fetch: String,
+    revision: Option<String>,
+}
+
+#[derive(Debug)]
+struct Project {
+    name: String,
+    path: Option<String>,
+    remote: Option<String>,
+    revision: Option<String>,
+}
+
+/// Elements collected from a manifest and the manifests it includes
+#[derive(Debug, Default)]
+struct Collected {
+    remotes: HashMap<String, Remote>,
+    default_remote: Option<String>,
+    default_revision: Option<String>,
+    projects: Vec<Project>,
+}
+
+/// Read an Android `repo` manifest (`.repo/manifest.xml` or `default.xml`),
+/// following `<include>` elements relative to the manifest's directory.
+///
+/// Project paths are relative to `root`, the directory `repo sync` checks out
+/// into. Relative remote fetch URLs (e.g. `..`) are resolved against
+/// `manifest_url`, the URL of the manifest repository.
+pub fn read(path: &Path, root: &Path, manifest_url: Option<&str>) -> Result<Vec<ImportEntry>> {
+    let mut collected = Collected::default();
+    load(path, &mut collected, 0)?;
+    entries(collected, root, manifest_url)
+}
+
+/// Parse a single manifest without includes
+pub fn parse(xml: &str, root: &Path, manifest_url: Option<&str>) -> Result<Vec<ImportEntry>> {
+    let mut collected = Collected::default();
+    collect(xml, None, &mut collected, 0)?;
+    entries(collected, root, manifest_url)
+}
+
+fn load(path: &Path, collected: &mut Collected, depth: usize) -> Result<()> {
+    if depth > MAX_INCLUDE_DEPTH {
+        return Err(anyhow::anyhow!(
+            "Manifest includes nest too deep at {}",
+            path.display()
+        ));
+    }
+    let xml = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    collect(&xml, path.parent(), collected, depth)
+        .with_context(|| format!("Invalid manifest {}", path.display()))
+}
+
+fn collect(
+    xml: &str,
+    include_dir: Option<&Path>,
+    collected: &mut Collected,
+    depth: usize,
+) -> Result<()> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+    loop {
+        let element = match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) => element,
+            Event::Eof => break,
+            _ => continue,
+        };
+        let attributes = attributes(&element)?;
+        let attribute = |name: &str| attributes.get(name).cloned();
+        let required = |name: &str| {
+            attribute(name).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "<{}> without {} attribute",
+                    String::from_utf8_lossy(element.name().as_ref()),
+                    name
+                )
+            })
+        };
+
+        match element.name().as_ref() {
+            b"remote" => {
+                let remote = Remote {
+                    fetch: required("fetch")?,
+                    revision: attribute("revision"),
+                };
+                collected.remotes.insert(required("name")?, remote);
+            }
+            b"default" => {
+                collected.default_remote = attribute("remote").or(collected.default_remote.take());
+                collected.default_revision =
+                    attribute("revision").or(collected.default_revision.take());
+            }
+            b"project" => collected.projects.push(Project {
+                name: required("name")?,
+                path: attribute("path"),
+                remote: attribute("remote"),
+                revision: attribute("revision"),
+            }),
+            b"remove-project" => {
+                let name = required("name")?;
+                collected.projects.retain(|project| project.name != name);
+            }
+            b"include" => {
+                let name = required("name")?;
+                let dir = include_dir.ok_or_else(|| {
+                    anyhow::anyhow!("<include name=\"{}\"> needs the manifest file", name)
+                })?;
+                load(&dir.join(name), collected, depth + 1)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn attributes(element: &BytesStart) -> Result<HashMap<String, String>> {
+    let mut attributes = HashMap::new();
+    for attribute in element.attributes() {
+        let attribute = attribute?;
+        attributes.insert(
+            String::from_utf8_lossy(attribute.key.as_ref()).to_string(),
+            attribute.unescape_value()?.to_string(),
+        );
+    }
+    Ok(attributes)
+}
+
+fn entries(
+    collected: Collected,
+    root: &Path,
+    manifest_url: Option<&str>,
+) -> Result<Vec<ImportEntry>> {
+    collected
+        .projects
+        .iter()
+        .map(|project| {
+            let remote_name = project
+                .remote
+                .as_ref()
+                .or(collected.default_remote.as_ref())
+                .ok_or_else(|| anyhow::anyhow!("Project {} has no remote", project.name))?;
+            let remote = collected.remotes.get(remote_name).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Project {} uses unknown remote {}",
+                    project.name,
+                    remote_name
+                )
+            })?;
+            let fetch = resolve_fetch(&remote.fetch, manifest_url)?;
+            Ok(ImportEntry {
+                url: format!("{}/{}", fetch.trim_end_matches('/'), project.name),
+                path: root.join(project.path.as_ref().unwrap_or(&project.name)),
+                revision: project
+                    .revision
+                    .clone()
+                    .or_else(|| remote.revision.clone())
+                    .or_else(|| collected.default_revision.clone()),
+            })
+        })
+        .collect()
+}
+
+/// Resolve a remote's fetch URL, which may be relative to the manifest repository
+fn resolve_fetch(fetch: &str, manifest_url: Option<&str>) -> Result<String> {
+    if fetch.contains("://") || !fetch.starts_with('.') {
+        return Ok(fetch.to_string());
+    }
+    let base = manifest_url.ok_or_else(|| {
+        anyhow::anyhow!(
+            "Remote fetch URL {} is relative to the manifest repository; pass its URL",
+            fetch
+        )
+    })?;
+    let base = url::Url::parse(base)
+        .with_context(|| format!("Invalid manifest repository URL {}", base))?;
+    Ok(base.join(fetch)?.to_string())
+}
+
+/// Directory `repo sync` checks out into when the manifest lives in its
+/// `.repo` directory
+pub fn checkout_root(manifest: &Path) -> Option<PathBuf> {
+    let manifest = manifest.canonicalize().ok()?;
+    manifest
+        .ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == ".repo"))
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest>
+  <remote name="aosp" fetch=".." review="https://android-review.googlesource.com/" />
+  <remote name="github" fetch="https://github.com/" revision="refs/heads/master" />
+  <default revision="refs/heads/main" remote="aosp" sync-j="4" />
+
+  <project path="build/make" name="platform/build" groups="pdk">
+    <linkfile src="CleanSpec.mk" dest="build/CleanSpec.mk" />
+  </project>
+  <project name="acme/tool" remote="github" />
+  <project name="platform/pinned" revision="0123456789abcdef0123456789abcdef01234567" />
+  <project name="platform/gone" />
+  <remove-project name="platform/gone" />
+</manifest>
+"#;
+
+    #[test]
+    fn test_parse_repo_manifest() {
+        let root = Path::new("/src/android");
+        let entries = parse(
+            MANIFEST,
+            root,
+            Some("https://android.googlesource.com/platform/manifest"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                ImportEntry {
+                    url: "https://android.googlesource.com/platform/build".to_string(),
+                    path: root.join("build/make"),
+                    revision: Some("refs/heads/main".to_string()),
+                },
+                ImportEntry {
+                    url: "https://github.com/acme/tool".to_string(),
+                    path: root.join("acme/tool"),
+                    revision: Some("refs/heads/master".to_string()),
+                },
+                ImportEntry {
+                    url: "https://android.googlesource.com/platform/pinned".to_string(),
+                    path: root.join("platform/pinned"),
+                    revision: Some("0123456789abcdef0123456789abcdef01234567".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_repo_manifests() {
+        let root = Path::new("/src");
+        // Relative fetch URL without the manifest repository URL
+        assert!(parse(MANIFEST, root, None).is_err());
+        assert!(parse(r#"<manifest><project name="a" /></manifest>"#, root, None).is_err());
+        assert!(parse(
+            r#"<manifest><remote name="r" fetch="https://x/" /><project name="a" remote="s" /></manifest>"#,
+            root,
+            None
+        )
+        .is_err());
+        assert!(parse(
+            r#"<manifest><include name="other.xml" /></manifest>"#,
+            root,
+            None
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,396 @@
+use crate::database::RepositoryDatabase;
+use crate::models::{normalize_tag, CloneStatus, Repository};
+use crate::Result;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Version of the JSON inventory format
+pub const INVENTORY_VERSION: u32 = 1;
+
+/// File format of an inventory dump
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryFormat {
+    Json,
+    Csv,
+}
+
+impl InventoryFormat {
+    /// Format implied by a file extension, if any
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+impl std::fmt::Display for InventoryFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InventoryFormat::Json => write!(f, "json"),
+            InventoryFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+impl std::str::FromStr for InventoryFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(InventoryFormat::Json),
+            "csv" => Ok(InventoryFormat::Csv),
+            _ => Err(anyhow::anyhow!("Invalid inventory format: {}", s)),
+        }
+    }
+}
+
+/// All repository records of a database with their tags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Inventory {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub repositories: Vec<InventoryRecord>,
+}
+
+/// One repository of an inventory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryRecord {
+    pub repository: Repository,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Trailing CSV column after the repository columns; tags can't contain commas
+#[derive(Serialize, Deserialize)]
+struct TagsColumn {
+    tags: String,
+}
+
+/// What merging an inventory into a database did, or would do
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// Repositories the database did not know
+    pub added: usize,
+    /// Known repositories whose metadata was older than the inventory's
+    pub updated: usize,
+    /// Known repositories left as they were
+    pub unchanged: usize,
+    /// Tags newly given to repositories
+    pub tags_added: usize,
+}
+
+impl Inventory {
+    /// Collect every repository record and its tags
+    pub async fn export(db: &RepositoryDatabase) -> Result<Self> {
+        let mut tags = db.get_all_repository_tags().await?;
+        let repositories = db
+            .get_all_repositories()
+            .await?
+            .into_iter()
+            .map(|repository| InventoryRecord {
+                tags: tags.remove(&repository.id).unwrap_or_default(),
+                repository,
+            })
+            .collect();
+        Ok(Self {
+            version: INVENTORY_VERSION,
+            exported_at: Utc::now(),
+            repositories,
+        })
+    }
+
+    /// Write the inventory as pretty JSON or as CSV with a header row
+    pub fn write(&self, format: InventoryFormat, mut writer: impl Write) -> Result<()> {
+        match format {
+            InventoryFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, self)?;
+                writeln!(writer)?;
+            }
+            InventoryFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(writer);
+                for record in &self.repositories {
+                    let tags = TagsColumn {
+                        tags: record.tags.join(","),
+                    };
+                    writer.serialize((&record.repository, tags))?;
+                }
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read an inventory written by [`Inventory::write`]
+    pub fn read(format: InventoryFormat, reader: impl Read) -> Result<Self> {
+        let inventory = match format {
+            InventoryFormat::Json => {
+                let inventory: Inventory = serde_json::from_reader(reader)?;
+                if inventory.version > INVENTORY_VERSION {
+                    return Err(anyhow::anyhow!(
+                        "Inventory version {} is newer than this super-clone supports ({})",
+                        inventory.version,
+                        INVENTORY_VERSION
+                    ));
+                }
+                inventory
+            }
+            InventoryFormat::Csv => {
+                let mut reader = csv::Reader::from_reader(reader);
+                // Rows are read by position, so the columns must be the exported ones
+                if reader.headers()? != &csv_header()? {
+                    return Err(anyhow::anyhow!(
+                        "Unexpected CSV columns; expected {}",
+                        csv_header()?.iter().collect::<Vec<_>>().join(",")
+                    ));
+                }
+                let mut repositories = Vec::new();
+                for (line, row) in reader.records().enumerate() {
+                    let (repository, tags): (Repository, TagsColumn) = row?
+                        .deserialize(None)
+                        .with_context(|| format!("Invalid CSV row {}", line + 1))?;
+                    repositories.push(InventoryRecord {
+                        repository,
+                        tags: tags
+                            .tags
+                            .split(',')
+                            .filter(|tag| !tag.is_empty())
+                            .map(str::to_string)
+                            .collect(),
+                    });
+                }
+                Inventory {
+                    version: INVENTORY_VERSION,
+                    exported_at: Utc::now(),
+                    repositories,
+                }
+            }
+        };
+        inventory.validate()?;
+        Ok(inventory)
+    }
+
+    /// Read an inventory file, in the format its extension names unless given
+    pub fn read_file(path: &Path, format: Option<InventoryFormat>) -> Result<Self> {
+        let format = format
+            .or_else(|| InventoryFormat::from_path(path))
+            .unwrap_or(InventoryFormat::Json);
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::read(format, std::io::BufReader::new(file))
+            .with_context(|| format!("Invalid inventory {}", path.display()))
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for record in &self.repositories {
+            if !seen.insert(record.repository.full_name.as_str()) {
+                return Err(anyhow::anyhow!(
+                    "Repository listed twice: {}",
+                    record.repository.full_name
+                ));
+            }
+            for tag in &record.tags {
+                normalize_tag(tag)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge the inventory into a database.
+    ///
+    /// Repositories are matched by full name. Unknown ones are added, keeping
+    /// their clone state only when the clone exists at the same path here.
+    /// Known ones keep their local path, status and clone mode; their metadata
+    /// is replaced when discovery updated the inventory's record more recently.
+    /// Tags are added, never removed.
+    pub async fn merge(&self, db: &RepositoryDatabase, dry_run: bool) -> Result<MergeSummary> {
+        let mut summary = MergeSummary::default();
+
+        // Record IDs differ between databases; links between records follow
+        // the local IDs
+        let mut known = HashMap::new();
+        let mut ids = HashMap::new();
+        for record in &self.repositories {
+            let repo = &record.repository;
+            let local = db.get_repository_by_full_name(&repo.full_name).await?;
+            let id = match &local {
+                Some(local) => local.id.clone(),
+                None if db.get_repository(&repo.id).await?.is_some() => {
+                    uuid::Uuid::new_v4().to_string()
+                }
+                None => repo.id.clone(),
+            };
+            ids.insert(repo.id.clone(), id);
+            known.insert(repo.full_name.clone(), local);
+        }
+
+        let mut added = Vec::new();
+        for record in &self.repositories {
+            let mut incoming = record.repository.clone();
+            incoming.id = ids[&incoming.id].clone();
+            incoming.parent_id = incoming
+                .parent_id
+                .map(|parent| ids.get(&parent).cloned().unwrap_or(parent));
+
+            let id = incoming.id.clone();
+            match known.remove(&incoming.full_name).flatten() {
+                None => {
+                    let on_disk = incoming
+                        .local_path
+                        .as_deref()
+                        .is_some_and(|path| Path::new(path).join(".git").exists());
+                    if !on_disk {
+                        incoming.local_path = None;
+                        incoming.status = CloneStatus::NotCloned.to_string();
+                        incoming.last_pulled_at = None;
+                    }
+                    if !dry_run {
+                        db.create_repository(&incoming).await?;
+                    }
+                    summary.added += 1;
+                    added.push(incoming);
+                }
+                Some(local) => {
+                    let merged = merge_metadata(&local, &incoming);
+                    match merged {
+                        Some(merged) => {
+                            if !dry_run {
+                                db.update_repository(&merged).await?;
+                            }
+                            summary.updated += 1;
+                        }
+                        None => summary.unchanged += 1,
+                    }
+                }
+            }
+
+            let current: HashSet<String> = if dry_run {
+                db.get_repository_tags(&id).await?.into_iter().collect()
+            } else {
+                HashSet::new()
+            };
+            for tag in &record.tags {
+                let tag = normalize_tag(tag)?;
+                let new = if dry_run {
+                    !current.contains(&tag)
+                } else {
+                    db.add_tag(&id, &tag).await?
+                };
+                if new {
+                    summary.tags_added += 1;
+                }
+            }
+        }
+        if !dry_run {
+            db.apply_tag_rules(&added).await?;
+        }
+        Ok(summary)
+    }
+}
+
+/// Local record with the incoming record's metadata, when that is newer and differs.
+///
+/// Only discovery counts as newer metadata: a local clone or pull after the
+/// export bumps `updated_at` but must not keep the exported metadata out.
+fn merge_metadata(local: &Repository, incoming: &Repository) -> Option<Repository> {
+    if incoming.metadata_updated() <= local.metadata_updated() {
+        return None;
+    }
+    let mut merged = local.clone();
+    merged.name = incoming.name.clone();
+    merged.owner = incoming.owner.clone();
+    merged.provider = incoming.provider.clone();
+    merged.clone_url_https = incoming.clone_url_https.clone();
+    merged.clone_url_ssh = incoming.clone_url_ssh.clone();
+    merged.description = incoming.description.clone();
+    merged.is_private = incoming.is_private;
+    for source in incoming.sources() {
+        merged.add_source(source);
+    }
+    merged.kind = incoming.kind.clone();
+    merged.has_wiki = incoming.has_wiki;
+    merged.parent_id = incoming.parent_id.clone();
+    merged.topics = incoming.topics.clone();
+
+    let changed = merged.name != local.name
+        || merged.owner != local.owner
+        || merged.provider != local.provider
+        || merged.clone_url_https != local.clone_url_https
+        || merged.clone_url_ssh != local.clone_url_ssh
+        || merged.description != local.description
+        || merged.is_private != local.is_private
+        || merged.source != local.source
+        || merged.kind != local.kind
+        || merged.has_wiki != local.has_wiki
+        || merged.parent_id != local.parent_id
+        || merged.topics != local.topics;
+    if !changed {
+        return None;
+    }
+    merged.metadata_updated_at = Some(incoming.metadata_updated());
+    merged.updated_at = Utc::now();
+    Some(merged)
+}
+
+/// Header row of a CSV inventory: the repository columns, then `tags`
+fn csv_header() -> Result<csv::StringRecord> {
+    let sample = Repository::new(
+        String::new(),
+        String::new(),
+        String::new(),
+        crate::models::Provider::GitHub,
+        String::new(),
+        String::new(),
+        None,
+        false,
+    );
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.serialize((
+        &sample,
+        TagsColumn {
+            tags: String::new(),
+        },
+    ))?;
+    let bytes = writer.into_inner().map_err(|e| anyhow::anyhow!("{}", e))?;
+    let mut reader = csv::Reader::from_reader(bytes.as_slice());
+    Ok(reader.headers()?.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inventory_format() {
+        assert_eq!(
+            "CSV".parse::<InventoryFormat>().unwrap(),
+            InventoryFormat::Csv
+        );
+        assert!("yaml".parse::<InventoryFormat>().is_err());
+        assert_eq!(
+            InventoryFormat::from_path(Path::new("repos.json")),
+            Some(InventoryFormat::Json)
+        );
+        assert_eq!(InventoryFormat::from_path(Path::new("repos")), None);
+    }
+
+    #[test]
+    fn test_read_invalid_inventories() {
+        let header = csv_header().unwrap();
+        assert_eq!(header.get(0), Some("id"));
+        assert_eq!(header.get(header.len() - 1), Some("tags"));
+
+        // Reordered columns
+        let mut columns: Vec<&str> = header.iter().collect();
+        columns.swap(1, 2);
+        let csv = format!("{}\n", columns.join(","));
+        assert!(Inventory::read(InventoryFormat::Csv, csv.as_bytes()).is_err());
+
+        let newer = r#"{"version": 99, "exported_at": "2024-01-01T00:00:00Z", "repositories": []}"#;
+        assert!(Inventory::read(InventoryFormat::Json, newer.as_bytes()).is_err());
+        let empty = r#"{"version": 1, "exported_at": "2024-01-01T00:00:00Z", "repositories": []}"#;
+        assert!(Inventory::read(InventoryFormat::Json, empty.as_bytes()).is_ok());
+    }
+}
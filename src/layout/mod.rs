@@ -0,0 +1,211 @@
+use crate::models::{Repository, RepositoryKind};
+use crate::remote::RemoteUrl;
+use std::path::PathBuf;
+
+/// Default layout: `<base>/<owner>/<name>`
+pub const DEFAULT_PATH_TEMPLATE: &str = "{owner}/{name}";
+
+const PLACEHOLDERS: &[&str] = &["host", "provider", "owner", "name", "full_name", "kind"];
+
+/// Template describing where a repository is cloned relative to the base path.
+///
+/// Supported placeholders are `{host}`, `{provider}`, `{owner}`, `{name}`,
+/// `{full_name}` and `{kind}`, e.g. `{host}/{owner}/{name}` for a ghq-style tree.
+/// Gists are always placed under a top-level `gists/` directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    template: String,
+}
+
+impl Default for PathTemplate {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_PATH_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.template)
+    }
+}
+
+impl std::str::FromStr for PathTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut rest = s;
+        let mut has_name = false;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| anyhow::anyhow!("Unclosed placeholder in path template: {}", s))?;
+            let placeholder = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&placeholder) {
+                return Err(anyhow::anyhow!(
+                    "Unknown placeholder {{{}}} in path template (expected one of: {})",
+                    placeholder,
+                    PLACEHOLDERS.join(", ")
+                ));
+            }
+            has_name |= placeholder == "name" || placeholder == "full_name";
+            rest = &rest[start + end + 1..];
+        }
+
+        // Without the name every repository of an owner would share one directory
+        if !has_name {
+            return Err(anyhow::anyhow!(
+                "Path template must contain {{name}} or {{full_name}}: {}",
+                s
+            ));
+        }
+
+        Ok(Self {
+            template: s.to_string(),
+        })
+    }
+}
+
+impl PathTemplate {
+    /// Expand the template for a repository into a path relative to the base path
+    pub fn relative_path(&self, repo: &Repository) -> PathBuf {
+        let host = RemoteUrl::parse(&repo.clone_url_https)
+            .map(|remote| remote.host)
+            .unwrap_or_else(|_| repo.provider.clone());
+
+        let expanded = self
+            .template
+            .replace("{host}", &sanitize_segment(&host))
+            .replace("{provider}", &sanitize_segment(&repo.provider))
+            .replace("{owner}", &sanitize_segment(&repo.owner))
+            .replace("{name}", &sanitize_segment(&repo.name))
+            .replace("{full_name}", &sanitize_path(&repo.full_name))
+            .replace("{kind}", &sanitize_segment(&repo.kind));
+
+        let mut path = PathBuf::new();
+        if repo.kind == RepositoryKind::Gist.to_string() {
+            path.push("gists");
+        }
+        for segment in expanded.split('/') {
+            let segment = sanitize_segment(segment);
+            if !segment.is_empty() {
+                path.push(segment);
+            }
+        }
+        path
+    }
+}
+
+/// Make a single path component safe on all platforms
+pub fn sanitize_segment(segment: &str) -> String {
+    let cleaned: String = segment
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_end_matches('.').to_string();
+
+    if cleaned.is_empty() && !segment.is_empty() || cleaned == "." || cleaned == ".." {
+        "_".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// Sanitize each component of a `/` separated path, dropping empty ones
+fn sanitize_path(path: &str) -> String {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(sanitize_segment)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Provider;
+
+    fn repo(owner: &str, name: &str, full_name: &str, provider: Provider) -> Repository {
+        let host = match provider {
+            Provider::GitHub => "github.com",
+            Provider::GitLab => "gitlab.example.com",
+        };
+        Repository::new(
+            name.to_string(),
+            full_name.to_string(),
+            owner.to_string(),
+            provider,
+            format!("https://{}/{}.git", host, full_name),
+            format!("git@{}:{}.git", host, full_name),
+            None,
+            false,
+        )
+    }
+
+    #[test]
+    fn test_default_template() {
+        let template = PathTemplate::default();
+        let repo = repo("owner", "repo", "owner/repo", Provider::GitHub);
+        assert_eq!(template.relative_path(&repo), PathBuf::from("owner/repo"));
+    }
+
+    #[test]
+    fn test_host_template() {
+        let template: PathTemplate = "{host}/{owner}/{name}".parse().unwrap();
+        let repo = repo("sub", "app", "group/sub/app", Provider::GitLab);
+        assert_eq!(
+            template.relative_path(&repo),
+            PathBuf::from("gitlab.example.com/sub/app")
+        );
+    }
+
+    #[test]
+    fn test_full_name_template_keeps_nesting() {
+        let template: PathTemplate = "{provider}/{full_name}".parse().unwrap();
+        let repo = repo("sub", "app", "group/sub/app", Provider::GitLab);
+        assert_eq!(
+            template.relative_path(&repo),
+            PathBuf::from("gitlab/group/sub/app")
+        );
+    }
+
+    #[test]
+    fn test_flat_template() {
+        let template: PathTemplate = "{owner}-{name}".parse().unwrap();
+        let repo = repo("owner", "repo", "owner/repo", Provider::GitHub);
+        assert_eq!(template.relative_path(&repo), PathBuf::from("owner-repo"));
+    }
+
+    #[test]
+    fn test_gists_stay_under_gists_directory() {
+        let template: PathTemplate = "{host}/{owner}/{name}".parse().unwrap();
+        let mut gist = repo("alice", "notes", "gists/alice/abc", Provider::GitHub);
+        gist.set_kind(RepositoryKind::Gist);
+        assert_eq!(
+            template.relative_path(&gist),
+            PathBuf::from("gists/github.com/alice/notes")
+        );
+    }
+
+    #[test]
+    fn test_unsafe_values_are_sanitized() {
+        let template = PathTemplate::default();
+        let repo = repo("..", "we:ird*name?", "../we:ird*name?", Provider::GitHub);
+        assert_eq!(
+            template.relative_path(&repo),
+            PathBuf::from("_/we_ird_name_")
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!("{owner}".parse::<PathTemplate>().is_err());
+        assert!("{owner}/{nmae}".parse::<PathTemplate>().is_err());
+        assert!("{owner}/{name".parse::<PathTemplate>().is_err());
+    }
+}
@@ -2,11 +2,23 @@
 //!
 //! A CLI and TUI tool to clone and manage repositories from GitHub and GitLab.
 
+pub mod bundle;
 pub mod database;
+pub mod exec;
+pub mod filter;
 pub mod git;
+pub mod grep;
+pub mod importers;
+pub mod inventory;
+pub mod layout;
+pub mod manifest;
 pub mod models;
+pub mod progress;
 pub mod providers;
+pub mod prune;
+pub mod remote;
 pub mod tui;
+pub mod verify;
 
 pub use models::*;
 
@@ -26,8 +38,24 @@ pub struct Config {
     pub gitlab_base_url: Option<String>,
     /// Base path for cloning repositories
     pub clone_base_path: String,
+    /// Base path for bare backup mirrors
+    pub mirror_base_path: String,
+    /// Directory layout below the base path, e.g. `{host}/{owner}/{name}`
+    pub path_template: String,
     /// Use SSH for cloning (default: HTTPS)
     pub use_ssh: bool,
+    /// Also clone project wikis next to their repositories
+    pub with_wikis: bool,
+    /// Shallow/partial/single-branch mode for new clones
+    pub clone_options: CloneOptions,
+    /// Submodule and Git LFS handling after clone and pull
+    pub checkout_options: CheckoutOptions,
+    /// Git backend for clone and pull: `cli`, or `libgit2` with that cargo feature
+    pub git_backend: String,
+    /// Timeouts, low-speed abort and cancellation for git runs
+    pub run_limits: git::RunLimits,
+    /// Clones or pulls of a batch that run at the same time
+    pub parallel: usize,
 }
 
 impl Default for Config {
@@ -47,7 +75,21 @@ impl Default for Config {
                 .join("repositories")
                 .to_string_lossy()
                 .to_string(),
+            mirror_base_path: dirs::home_dir()
+                .unwrap_or_else(|| std::path::PathBuf::from("."))
+                .join("repository-mirrors")
+                .to_string_lossy()
+                .to_string(),
+            path_template: std::env::var("SUPER_CLONE_PATH_TEMPLATE")
+                .unwrap_or_else(|_| layout::DEFAULT_PATH_TEMPLATE.to_string()),
             use_ssh: false,
+            with_wikis: false,
+            clone_options: CloneOptions::default(),
+            checkout_options: CheckoutOptions::default(),
+            git_backend: std::env::var("SUPER_CLONE_GIT_BACKEND")
+                .unwrap_or_else(|_| "cli".to_string()),
+            run_limits: git::RunLimits::default(),
+            parallel: 4,
         }
     }
 }
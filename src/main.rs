@@ -1,13 +1,37 @@
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use super_clone::{
-    database::RepositoryDatabase,
-    git::GitOperations,
-    models::{CloneStatus, Provider},
-    providers::{github::GitHubClient, gitlab::GitLabClient, RepositoryProvider},
+    bundle::{bundle_file_name, BundleEntry, BundleManifest, MANIFEST_FILE},
+    database::{RepositoryDatabase, RunLock},
+    exec::{run_in_repositories, ExecOptions, ExecTarget, OutputMode},
+    filter::RepositoryFilter,
+    git::{backend::backend_by_name, CancelFlag, GitError, GitOperations},
+    grep::GrepOptions,
+    importers::{self, ImportEntry},
+    inventory::{Inventory, InventoryFormat},
+    layout::PathTemplate,
+    manifest::{self, manifest_path, Manifest, ManifestEntry},
+    models::{
+        normalize_tag, BundleExport, CheckoutOptions, CloneOptions, CloneStatus, Job, JobKind,
+        JobStatus, JobTask, Mirror, Operation, OperationKind, OperationOutcome, Provider,
+        Repository, RepositoryKind, RepositorySource, TagRule, TagRuleKind, TaskStatus,
+    },
+    progress::{ProgressReporter, TaskProgress},
+    providers::{
+        github::GitHubClient, gitlab::GitLabClient, Affiliation, AuthenticatedDiscovery,
+        RepositoryProvider, UpstreamState,
+    },
+    prune::{self, Disposal, PruneReason},
+    remote::{host_of, redact_credentials},
     tui::App,
+    verify::{self, Problem},
     Config,
 };
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
 /// A CLI and TUI tool to clone and manage repositories from GitHub and GitLab
 #[derive(Parser)]
@@ -38,9 +62,63 @@ struct Cli {
     #[arg(short = 'p', long)]
     clone_path: Option<String>,
 
+    /// Base path for bare backup mirrors
+    #[arg(long)]
+    mirror_path: Option<String>,
+
+    /// Directory layout below the clone path, e.g. "{host}/{owner}/{name}"
+    /// (or set SUPER_CLONE_PATH_TEMPLATE env var)
+    #[arg(long)]
+    path_template: Option<String>,
+
     /// Use SSH for cloning (default: HTTPS)
     #[arg(long)]
     ssh: bool,
+
+    /// Shallow clone with this many commits of history
+    #[arg(long)]
+    depth: Option<u32>,
+
+    /// Partial clone filter: blob:none, tree:0 or blob:limit=<size>
+    #[arg(long)]
+    filter: Option<String>,
+
+    /// Only clone the default branch
+    #[arg(long)]
+    single_branch: bool,
+
+    /// Also clone project wikis next to their repositories
+    #[arg(long)]
+    with_wikis: bool,
+
+    /// Recursively init and update submodules after clone and pull
+    #[arg(long)]
+    submodules: bool,
+
+    /// Git LFS handling after clone and pull: auto, pull or skip
+    #[arg(long, default_value = "auto")]
+    lfs: String,
+
+    /// Git backend: cli, or libgit2 when built with the libgit2 feature
+    /// (or set SUPER_CLONE_GIT_BACKEND env var)
+    #[arg(long)]
+    git_backend: Option<String>,
+
+    /// Kill a single clone, pull or fetch after this many seconds
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Abort transfers slower than this many bytes per second...
+    #[arg(long, default_value_t = 1000)]
+    low_speed_limit: u32,
+
+    /// ...for this many seconds (0 disables the low-speed abort)
+    #[arg(long, default_value_t = 60)]
+    low_speed_time: u64,
+
+    /// Clones or pulls of a batch to run at the same time
+    #[arg(long, default_value_t = 4)]
+    parallel: usize,
 }
 
 #[derive(Subcommand)]
@@ -71,6 +149,20 @@ enum Commands {
         /// Provider (github or gitlab)
         #[arg(short, long, default_value = "github")]
         provider: String,
+        /// Affiliations to include: owner, collaborator, organization_member (comma-separated)
+        #[arg(
+            short,
+            long,
+            value_delimiter = ',',
+            default_value = "owner,collaborator,organization_member"
+        )]
+        affiliation: Vec<String>,
+        /// Minimum GitLab access level (10=guest, 20=reporter, 30=developer, 40=maintainer, 50=owner)
+        #[arg(long)]
+        min_access_level: Option<u32>,
+        /// Only discover public repositories owned by the user
+        #[arg(long)]
+        public_only: bool,
     },
 
     /// Clone all repositories from all organizations/groups the authenticated user has access to
@@ -80,24 +172,489 @@ enum Commands {
         provider: String,
     },
 
+    /// Clone repositories starred (or watched) by a user or the authenticated user
+    CloneStarred {
+        /// Provider (github or gitlab)
+        #[arg(short, long, default_value = "github")]
+        provider: String,
+        /// Username (defaults to the authenticated user, requires token)
+        username: Option<String>,
+        /// Clone watched repositories instead of starred ones (GitHub only)
+        #[arg(short, long)]
+        watched: bool,
+    },
+
+    /// Clone GitHub gists of a user or the authenticated user
+    CloneGists {
+        /// Username (defaults to the authenticated user, requires token)
+        username: Option<String>,
+    },
+
+    /// Register existing clones found under a directory
+    ImportLocal {
+        /// Directory to scan for git repositories
+        dir: String,
+        /// Provider to use instead of guessing it from the origin host (github or gitlab)
+        #[arg(short, long)]
+        provider: Option<String>,
+        /// Move the clones into the clone path layout
+        #[arg(long = "move")]
+        move_clones: bool,
+        /// Only show what would be imported
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Register the projects of an Android `repo` manifest
+    ImportRepoManifest {
+        /// Manifest file, e.g. .repo/manifest.xml or default.xml
+        manifest: PathBuf,
+        /// Directory the projects are checked out into (defaults to the
+        /// directory holding .repo, otherwise the clone path)
+        #[arg(long)]
+        root: Option<PathBuf>,
+        /// URL of the manifest repository, for remotes with a relative fetch URL
+        /// (defaults to the origin of the manifest's repository)
+        #[arg(long)]
+        manifest_url: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+
+    /// Register the repositories of a myrepos configuration
+    ImportMrconfig {
+        /// myrepos configuration file (defaults to ~/.mrconfig)
+        file: Option<PathBuf>,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+
+    /// Register the repositories of a ghq root
+    ImportGhq {
+        /// ghq root (defaults to GHQ_ROOT, git config ghq.root or ~/ghq)
+        #[arg(long)]
+        root: Option<PathBuf>,
+        /// Read `ghq list` output from this file (`-` for standard input)
+        /// instead of scanning the root
+        #[arg(long)]
+        list: Option<PathBuf>,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+
+    /// Move existing clones into the current path template layout
+    Relayout {
+        /// Only show what would be moved
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// List discovered repositories
     List {
-        /// Filter by provider (github or gitlab)
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+
+    /// Search repository names, descriptions, topics and owners
+    Search {
+        /// Words to look for; each matches the start of a word
+        query: String,
+        /// Maximum number of results
+        #[arg(short, long, default_value_t = 20)]
+        limit: u32,
+    },
+
+    /// Pull updates for all cloned repositories
+    PullAll {
+        /// Only pull repositories from this source (discovered, starred or watched)
+        #[arg(short, long)]
+        source: Option<String>,
+        /// Only pull repositories with this tag; repeatable
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+
+    /// Clone a specific repository by full name, or every one matching the filters
+    Clone {
+        /// Repository full name (e.g., owner/repo)
+        #[arg(
+            required_unless_present_any = ["provider", "cloned", "source", "include", "exclude", "tags"],
+            conflicts_with_all = ["provider", "cloned", "source", "include", "exclude", "tags"]
+        )]
+        repo: Option<String>,
+        #[command(flatten)]
+        filters: FilterArgs,
+        /// Shallow clone the selected repositories with this many commits of history
+        #[arg(long)]
+        depth: Option<u32>,
+        /// Partial clone filter for the selected repositories: blob:none, tree:0 or blob:limit=<size>
+        #[arg(long)]
+        filter: Option<String>,
+        /// Only clone the default branch of the selected repositories
+        #[arg(long)]
+        single_branch: bool,
+        /// Only check out these directories (sparse checkout, cone mode); repeatable
+        #[arg(long = "sparse")]
+        sparse_paths: Vec<String>,
+    },
+
+    /// Change the sparse checkout directories of a repository
+    Sparse {
+        /// Repository full name (e.g., owner/repo)
+        repo: String,
+        /// Directories to check out (cone mode)
+        paths: Vec<String>,
+        /// Go back to a full checkout
+        #[arg(long, conflicts_with = "paths")]
+        disable: bool,
+    },
+
+    /// Create or update bare backup mirrors of discovered repositories
+    Mirror {
+        /// Repository full names to mirror (default: all repositories)
+        repos: Vec<String>,
+        /// Only mirror repositories from this provider (github or gitlab)
         #[arg(short, long)]
         provider: Option<String>,
-        /// Show only cloned repositories
+        /// Also mirror pull request (GitHub) and merge request (GitLab) refs; the choice
+        /// is remembered per mirror
+        #[arg(long, conflicts_with = "no_pull_refs")]
+        pull_refs: bool,
+        /// Stop mirroring pull/merge request refs in the selected mirrors
+        #[arg(long)]
+        no_pull_refs: bool,
+    },
+
+    /// Write all repository records with their tags as JSON or CSV
+    Export {
+        /// json or csv (defaults to the output file's extension, otherwise json)
         #[arg(short, long)]
-        cloned: bool,
+        format: Option<String>,
+        /// Write to this file instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 
-    /// Pull updates for all cloned repositories
-    PullAll,
+    /// Merge repository records written by export into the database
+    Import {
+        /// File written by export
+        file: PathBuf,
+        /// json or csv (defaults to the file's extension, otherwise json)
+        #[arg(short, long)]
+        format: Option<String>,
+        /// Only show what would be merged
+        #[arg(long)]
+        dry_run: bool,
+    },
 
-    /// Clone a specific repository by full name
-    Clone {
+    /// Write a git bundle per cloned repository plus a JSON manifest
+    ExportBundles {
+        /// Directory to write bundles and manifest.json into
+        dir: String,
+        /// Repository full names to export (default: all cloned repositories)
+        repos: Vec<String>,
+        /// Write full bundles even when an earlier export exists
+        #[arg(long)]
+        full: bool,
+    },
+
+    /// Clone or update repositories from bundles written by export-bundles
+    ImportBundles {
+        /// Directory containing manifest.json and the bundles
+        dir: String,
+    },
+
+    /// Fetch more history into a shallow clone
+    Deepen {
+        /// Repository full name (e.g., owner/repo)
+        repo: String,
+        /// Number of additional commits to fetch
+        #[arg(long, default_value_t = 50)]
+        by: u32,
+    },
+
+    /// Convert a shallow, partial or single-branch clone into a full clone
+    Unshallow {
         /// Repository full name (e.g., owner/repo)
         repo: String,
     },
+
+    /// Run a command in every cloned repository, e.g. `exec -- 'git checkout main && make lint'`
+    Exec {
+        #[command(flatten)]
+        filters: FilterArgs,
+        /// Commands to run at the same time
+        #[arg(short = 'j', long, default_value_t = 1)]
+        parallel: usize,
+        /// Output: prefix (stream lines as "[owner/repo] ...") or group (one block per repository)
+        #[arg(long, default_value = "prefix")]
+        output: String,
+        /// Keep going after a command fails
+        #[arg(short = 'k', long)]
+        continue_on_error: bool,
+        /// Print exit codes and captured output as JSON instead
+        #[arg(long)]
+        json: bool,
+        /// Command to run; a single argument is run through the shell
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+
+    /// Search the files of every cloned repository with git grep
+    Grep {
+        /// Extended regular expression to search for
+        pattern: String,
+        #[command(flatten)]
+        filters: FilterArgs,
+        /// Treat the pattern as a literal string
+        #[arg(short = 'F', long)]
+        fixed_strings: bool,
+        /// Ignore case
+        #[arg(short, long)]
+        ignore_case: bool,
+        /// Only search files matching these paths or globs, e.g. '*.toml' or 'src/'
+        #[arg(last = true)]
+        paths: Vec<String>,
+        /// Repositories searched at the same time
+        #[arg(short = 'j', long, default_value_t = 8)]
+        parallel: usize,
+        /// Print the matches as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show why repositories failed to clone or pull
+    Errors {
+        /// Show the clone and pull history of this repository instead (e.g., owner/repo)
+        repo: Option<String>,
+        /// Number of history entries to show
+        #[arg(short, long, default_value_t = 20)]
+        limit: u32,
+    },
+
+    /// Inspect and continue batch clone and pull runs
+    Jobs {
+        #[command(subcommand)]
+        command: JobsCommand,
+    },
+
+    /// Clone and update the repositories listed in a manifest
+    Apply {
+        /// Manifest file
+        #[arg(default_value = manifest::MANIFEST_FILE)]
+        manifest: PathBuf,
+        /// Only show what would be done
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Write a manifest describing the cloned repositories
+    Freeze {
+        #[command(flatten)]
+        filters: FilterArgs,
+        /// Record the checked out commit of every clone
+        #[arg(long)]
+        pin: bool,
+        /// Write the manifest to this file instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Remove clones of archived, deleted or filtered out repositories, records
+    /// without clones and clones without records
+    Prune {
+        /// Keep only clones matching these filters; the others are pruned
+        #[command(flatten)]
+        filters: FilterArgs,
+        /// Ask the providers which repositories were archived or deleted
+        #[arg(long)]
+        upstream: bool,
+        /// Carry out the plan; without this it is only printed
+        #[arg(long)]
+        apply: bool,
+        /// Directory pruned clones are moved to (default: trash next to the database)
+        #[arg(long)]
+        trash: Option<PathBuf>,
+        /// Delete pruned clones instead of moving them to the trash
+        #[arg(long, conflicts_with = "trash")]
+        delete: bool,
+    },
+
+    /// Check recorded clones against the disk: missing paths, non-git
+    /// directories, wrong origins and clones the database doesn't know
+    Verify {
+        /// Update the records to match what is on disk
+        #[arg(long)]
+        fix: bool,
+        /// Fix the records, then clone again the repositories whose clone is missing
+        #[arg(long)]
+        reclone: bool,
+    },
+
+    /// Group repositories with your own tags
+    Tag {
+        #[command(subcommand)]
+        command: TagCommand,
+    },
+}
+
+/// Repository selection shared by commands that act on many repositories
+#[derive(Args)]
+struct FilterArgs {
+    /// Filter by provider (github or gitlab)
+    #[arg(short, long)]
+    provider: Option<String>,
+    /// Only cloned repositories
+    #[arg(short, long)]
+    cloned: bool,
+    /// Filter by source (discovered, starred or watched)
+    #[arg(short, long)]
+    source: Option<String>,
+    /// Only repositories whose full name matches this pattern (`*` and `?` wildcards); repeatable
+    #[arg(long)]
+    include: Vec<String>,
+    /// Skip repositories whose full name matches this pattern; repeatable
+    #[arg(long)]
+    exclude: Vec<String>,
+    /// Only repositories with this tag; repeatable, any of the tags matches
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+}
+
+impl FilterArgs {
+    /// Whether any filter narrows the selection
+    fn is_set(&self) -> bool {
+        self.provider.is_some()
+            || self.source.is_some()
+            || self.cloned
+            || !self.include.is_empty()
+            || !self.exclude.is_empty()
+            || !self.tags.is_empty()
+    }
+
+    async fn to_filter(&self, db: &RepositoryDatabase) -> anyhow::Result<RepositoryFilter> {
+        Ok(RepositoryFilter {
+            provider: self.provider.as_deref().map(str::parse).transpose()?,
+            source: self.source.as_deref().map(str::parse).transpose()?,
+            cloned: self.cloned,
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            tagged: tagged_repository_ids(db, &self.tags).await?,
+        })
+    }
+}
+
+/// Options shared by the commands importing repository lists of other tools
+#[derive(Args)]
+struct ImportArgs {
+    /// Provider to use instead of guessing it from the URL host (github or gitlab)
+    #[arg(short, long)]
+    provider: Option<String>,
+    /// Clone the repositories that are not on disk yet, where the tool keeps them
+    #[arg(long)]
+    clone: bool,
+    /// Only show what would be imported
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// IDs of the repositories carrying any of the tags, or `None` when no tag is given
+async fn tagged_repository_ids(
+    db: &RepositoryDatabase,
+    tags: &[String],
+) -> anyhow::Result<Option<HashSet<String>>> {
+    if tags.is_empty() {
+        return Ok(None);
+    }
+    let tags = tags
+        .iter()
+        .map(|tag| normalize_tag(tag))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Some(db.get_tagged_repository_ids(&tags).await?))
+}
+
+#[derive(Subcommand)]
+enum TagCommand {
+    /// Tag repositories
+    Add {
+        tag: String,
+        /// Repository full names (e.g., owner/repo)
+        #[arg(required = true)]
+        repos: Vec<String>,
+    },
+
+    /// Remove a tag from repositories
+    Remove {
+        tag: String,
+        /// Repository full names (e.g., owner/repo)
+        #[arg(required = true)]
+        repos: Vec<String>,
+    },
+
+    /// Delete a tag from every repository, together with its rules
+    Delete { tag: String },
+
+    /// List tags with their repository counts, or the tags of one repository
+    List {
+        /// Repository full name (e.g., owner/repo)
+        repo: Option<String>,
+    },
+
+    /// Assign tags automatically by topic or name pattern
+    Rule {
+        #[command(subcommand)]
+        command: TagRuleCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagRuleCommand {
+    /// Tag every repository matching a pattern, now and when discovered later
+    Add {
+        tag: String,
+        /// What the pattern is matched against (topic or name)
+        kind: String,
+        /// Pattern with `*` and `?` wildcards, e.g. 'payments*' or 'acme/billing-*'
+        pattern: String,
+    },
+
+    /// List tag rules
+    List,
+
+    /// Delete a tag rule; tags it already assigned stay
+    Remove {
+        /// Rule ID or a unique prefix of it
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum JobsCommand {
+    /// List recent jobs with their progress
+    List {
+        /// Number of jobs to show
+        #[arg(short, long, default_value_t = 20)]
+        limit: u32,
+    },
+
+    /// Continue the unfinished and failed repositories of a job
+    Resume {
+        /// Job ID or a unique prefix of it
+        id: String,
+    },
+
+    /// Re-attempt failed repositories, waiting longer after each failure
+    RetryFailed {
+        /// Job ID or prefix (default: the latest job with failures)
+        #[arg(long)]
+        job: Option<String>,
+        /// Attempts per repository in this run
+        #[arg(long, default_value_t = 3)]
+        attempts: u32,
+        /// Seconds to wait before the second attempt, doubled after each failure
+        #[arg(long, default_value_t = 5)]
+        backoff: u64,
+    },
 }
 
 #[tokio::main]
@@ -119,12 +676,65 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     if let Some(path) = cli.clone_path {
         config.clone_base_path = path;
     }
+    if let Some(path) = cli.mirror_path {
+        config.mirror_base_path = path;
+    }
+    if let Some(template) = cli.path_template {
+        config.path_template = template;
+    }
     config.use_ssh = cli.ssh;
+    config.with_wikis = cli.with_wikis;
+    config.clone_options = CloneOptions::new(cli.depth, cli.filter, cli.single_branch)?;
+    if let Some(backend) = cli.git_backend {
+        config.git_backend = backend;
+    }
+    config.run_limits.timeout = cli.timeout.map(Duration::from_secs);
+    config.run_limits.low_speed_limit = cli.low_speed_limit;
+    config.run_limits.low_speed_time = Duration::from_secs(cli.low_speed_time);
+    config.parallel = cli.parallel.max(1);
+    config.checkout_options = CheckoutOptions {
+        submodules: cli.submodules,
+        lfs: cli.lfs.parse()?,
+    };
 
     // Check if git is installed
     GitOperations::check_git_installed()?;
 
     let db = RepositoryDatabase::new(&config.database_url).await?;
+    // Held until exit; while another run holds it too, the rows it is working
+    // on are not ours to roll back
+    let mut run_lock = RunLock::acquire(&config.database_url)?;
+    if run_lock.is_exclusive() {
+        let interrupted = db.reset_interrupted_statuses().await?;
+        if interrupted > 0 {
+            eprintln!(
+                "⚠️  Reset {} repositories left cloning/updating by an interrupted run",
+                interrupted
+            );
+        }
+        for job in db.reset_interrupted_jobs().await? {
+            eprintln!(
+                "⚠️  Job {} ({}) was interrupted, continue it with: super-clone jobs resume {}",
+                job.short_id(),
+                job.description,
+                job.short_id()
+            );
+        }
+    }
+    run_lock.share()?;
+
+    // The first Ctrl-C stops running git processes and rolls back their state,
+    // the second one quits immediately
+    let cancel = config.run_limits.cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("\n⏹️  Cancelling, press Ctrl-C again to quit immediately");
+            cancel.cancel();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
+            }
+        }
+    });
 
     match cli.command {
         Some(Commands::Tui) | None => {
@@ -152,41 +762,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             println!("📦 Found {} repositories", repos.len());
 
-            // Save to database
-            for repo in &repos {
-                if db
-                    .get_repository_by_full_name(&repo.full_name)
-                    .await?
-                    .is_none()
-                {
-                    db.create_repository(repo).await?;
-                }
-            }
-
-            // Clone repositories
-            let git_ops = GitOperations::with_tokens(
-                PathBuf::from(&config.clone_base_path),
-                config.github_token.clone(),
-                config.gitlab_token.clone(),
-            );
-            for repo in &repos {
-                println!("⬇️  Cloning: {}", repo.full_name);
-                match git_ops.clone_repository(repo, config.use_ssh).await {
-                    Ok(path) => {
-                        let mut updated_repo = repo.clone();
-                        updated_repo.set_local_path(path.clone());
-                        updated_repo.update_status(CloneStatus::Cloned);
-                        db.update_repository(&updated_repo).await?;
-                        println!("   ✅ Cloned to: {}", path);
-                    }
-                    Err(e) => {
-                        let mut updated_repo = repo.clone();
-                        updated_repo.update_status(CloneStatus::Error);
-                        db.update_repository(&updated_repo).await?;
-                        eprintln!("   ❌ Failed: {}", e);
-                    }
-                }
-            }
+            let description = format!("clone-user {} {}", provider, username);
+            save_and_clone(&db, &config, &description, &repos).await?;
 
             println!("✨ Done!");
         }
@@ -213,115 +790,68 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             println!("📦 Found {} repositories", repos.len());
 
-            // Save to database
-            for repo in &repos {
-                if db
-                    .get_repository_by_full_name(&repo.full_name)
-                    .await?
-                    .is_none()
-                {
-                    db.create_repository(repo).await?;
-                }
-            }
-
-            // Clone repositories
-            let git_ops = GitOperations::with_tokens(
-                PathBuf::from(&config.clone_base_path),
-                config.github_token.clone(),
-                config.gitlab_token.clone(),
-            );
-            for repo in &repos {
-                println!("⬇️  Cloning: {}", repo.full_name);
-                match git_ops.clone_repository(repo, config.use_ssh).await {
-                    Ok(path) => {
-                        let mut updated_repo = repo.clone();
-                        updated_repo.set_local_path(path.clone());
-                        updated_repo.update_status(CloneStatus::Cloned);
-                        db.update_repository(&updated_repo).await?;
-                        println!("   ✅ Cloned to: {}", path);
-                    }
-                    Err(e) => {
-                        let mut updated_repo = repo.clone();
-                        updated_repo.update_status(CloneStatus::Error);
-                        db.update_repository(&updated_repo).await?;
-                        eprintln!("   ❌ Failed: {}", e);
-                    }
-                }
-            }
+            let description = format!("clone-org {} {}", provider, org);
+            save_and_clone(&db, &config, &description, &repos).await?;
 
             println!("✨ Done!");
         }
-        Some(Commands::CloneMine { provider }) => {
+        Some(Commands::CloneMine {
+            provider,
+            affiliation,
+            min_access_level,
+            public_only,
+        }) => {
             let provider_enum: Provider = provider.parse()?;
+            let options = AuthenticatedDiscovery {
+                affiliations: affiliation
+                    .iter()
+                    .filter(|a| !a.trim().is_empty())
+                    .map(|a| a.trim().parse::<Affiliation>())
+                    .collect::<Result<Vec<_>, _>>()?,
+                min_access_level,
+            };
+            if options.affiliations.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "--affiliation needs at least one of owner, collaborator, organization_member"
+                )
+                .into());
+            }
 
             // Check for token first
-            match provider_enum {
-                Provider::GitHub => {
-                    if config.github_token.is_none() {
-                        return Err(anyhow::anyhow!(
-                            "GitHub token is required for this command. Set GITHUB_TOKEN env var or use --github-token flag."
-                        ).into());
-                    }
-                }
-                Provider::GitLab => {
-                    if config.gitlab_token.is_none() {
-                        return Err(anyhow::anyhow!(
-                            "GitLab token is required for this command. Set GITLAB_TOKEN env var or use --gitlab-token flag."
-                        ).into());
-                    }
-                }
-            }
+            require_token(&config, &provider_enum)?;
 
             // Get authenticated user and discover repos
             println!("🔍 Discovering repositories for authenticated user...");
             let repos = match provider_enum {
                 Provider::GitHub => {
-                    let client = GitHubClient::new(config.github_token)?;
+                    let client = GitHubClient::new(config.github_token.clone())?;
                     let username = client.get_authenticated_user().await?;
                     println!("   Authenticated as: {}", username);
-                    client.discover_user_repos(&username).await?
+                    if public_only {
+                        client.discover_user_repos(&username).await?
+                    } else {
+                        client.discover_authenticated_repos(&options).await?
+                    }
                 }
                 Provider::GitLab => {
-                    let client = GitLabClient::new(config.gitlab_token, config.gitlab_base_url)?;
+                    let client = GitLabClient::new(
+                        config.gitlab_token.clone(),
+                        config.gitlab_base_url.clone(),
+                    )?;
                     let username = client.get_authenticated_user().await?;
                     println!("   Authenticated as: {}", username);
-                    client.discover_user_repos(&username).await?
+                    if public_only {
+                        client.discover_user_repos(&username).await?
+                    } else {
+                        client.discover_authenticated_repos(&options).await?
+                    }
                 }
             };
 
             println!("📦 Found {} repositories", repos.len());
 
-            // Save to database
-            for repo in &repos {
-                if db
-                    .get_repository_by_full_name(&repo.full_name)
-                    .await?
-                    .is_none()
-                {
-                    db.create_repository(repo).await?;
-                }
-            }
-
-            // Clone repositories
-            let git_ops = GitOperations::new(PathBuf::from(&config.clone_base_path));
-            for repo in &repos {
-                println!("⬇️  Cloning: {}", repo.full_name);
-                match git_ops.clone_repository(repo, config.use_ssh).await {
-                    Ok(path) => {
-                        let mut updated_repo = repo.clone();
-                        updated_repo.set_local_path(path.clone());
-                        updated_repo.update_status(CloneStatus::Cloned);
-                        db.update_repository(&updated_repo).await?;
-                        println!("   ✅ Cloned to: {}", path);
-                    }
-                    Err(e) => {
-                        let mut updated_repo = repo.clone();
-                        updated_repo.update_status(CloneStatus::Error);
-                        db.update_repository(&updated_repo).await?;
-                        eprintln!("   ❌ Failed: {}", e);
-                    }
-                }
-            }
+            let description = format!("clone-mine {}", provider);
+            save_and_clone(&db, &config, &description, &repos).await?;
 
             println!("✨ Done!");
         }
@@ -329,22 +859,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let provider_enum: Provider = provider.parse()?;
 
             // Check for token first
-            match provider_enum {
-                Provider::GitHub => {
-                    if config.github_token.is_none() {
-                        return Err(anyhow::anyhow!(
-                            "GitHub token is required for this command. Set GITHUB_TOKEN env var or use --github-token flag."
-                        ).into());
-                    }
-                }
-                Provider::GitLab => {
-                    if config.gitlab_token.is_none() {
-                        return Err(anyhow::anyhow!(
-                            "GitLab token is required for this command. Set GITLAB_TOKEN env var or use --gitlab-token flag."
-                        ).into());
-                    }
-                }
-            }
+            require_token(&config, &provider_enum)?;
 
             println!("🔍 Discovering organizations/groups...");
 
@@ -384,56 +899,276 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 all_repos.len()
             );
 
-            // Save to database
-            for repo in &all_repos {
-                if db
-                    .get_repository_by_full_name(&repo.full_name)
-                    .await?
-                    .is_none()
-                {
-                    db.create_repository(repo).await?;
-                }
+            let description = format!("clone-all-orgs {}", provider);
+            save_and_clone(&db, &config, &description, &all_repos).await?;
+
+            println!("✨ Done!");
+        }
+        Some(Commands::CloneStarred {
+            provider,
+            username,
+            watched,
+        }) => {
+            let provider_enum: Provider = provider.parse()?;
+            if username.is_none() {
+                require_token(&config, &provider_enum)?;
             }
 
-            // Clone repositories
-            let git_ops = GitOperations::new(PathBuf::from(&config.clone_base_path));
-            for repo in &all_repos {
-                println!("⬇️  Cloning: {}", repo.full_name);
-                match git_ops.clone_repository(repo, config.use_ssh).await {
-                    Ok(path) => {
-                        let mut updated_repo = repo.clone();
-                        updated_repo.set_local_path(path.clone());
-                        updated_repo.update_status(CloneStatus::Cloned);
-                        db.update_repository(&updated_repo).await?;
-                        println!("   ✅ Cloned to: {}", path);
+            let kind = if watched { "watched" } else { "starred" };
+            match &username {
+                Some(username) => println!(
+                    "🔍 Discovering {} repositories for user: {}",
+                    kind, username
+                ),
+                None => println!(
+                    "🔍 Discovering {} repositories for authenticated user...",
+                    kind
+                ),
+            }
+            let repos = match provider_enum {
+                Provider::GitHub => {
+                    let client = GitHubClient::new(config.github_token.clone())?;
+                    if watched {
+                        client.discover_watched_repos(username.as_deref()).await?
+                    } else {
+                        client.discover_starred_repos(username.as_deref()).await?
                     }
-                    Err(e) => {
-                        let mut updated_repo = repo.clone();
-                        updated_repo.update_status(CloneStatus::Error);
-                        db.update_repository(&updated_repo).await?;
-                        eprintln!("   ❌ Failed: {}", e);
+                }
+                Provider::GitLab => {
+                    let client = GitLabClient::new(
+                        config.gitlab_token.clone(),
+                        config.gitlab_base_url.clone(),
+                    )?;
+                    if watched {
+                        client.discover_watched_repos(username.as_deref()).await?
+                    } else {
+                        client.discover_starred_repos(username.as_deref()).await?
                     }
                 }
+            };
+
+            println!("📦 Found {} repositories", repos.len());
+
+            let mut description = format!("clone-starred {}", provider);
+            if let Some(username) = &username {
+                description.push_str(&format!(" {}", username));
             }
+            if watched {
+                description.push_str(" --watched");
+            }
+            save_and_clone(&db, &config, &description, &repos).await?;
 
             println!("✨ Done!");
         }
-        Some(Commands::List { provider, cloned }) => {
-            let repos = if let Some(p) = provider {
-                db.get_repositories_by_provider(&p).await?
-            } else if cloned {
-                db.get_repositories_by_status(CloneStatus::Cloned).await?
-            } else {
-                db.get_all_repositories().await?
+        Some(Commands::CloneGists { username }) => {
+            if username.is_none() {
+                require_token(&config, &Provider::GitHub)?;
+            }
+
+            match &username {
+                Some(username) => println!("🔍 Discovering gists for user: {}", username),
+                None => println!("🔍 Discovering gists for authenticated user..."),
+            }
+            let client = GitHubClient::new(config.github_token.clone())?;
+            let gists = client.discover_gists(username.as_deref()).await?;
+
+            println!("📦 Found {} gists", gists.len());
+
+            let description = match &username {
+                Some(username) => format!("clone-gists {}", username),
+                None => "clone-gists".to_string(),
             };
+            save_and_clone(&db, &config, &description, &gists).await?;
 
-            if repos.is_empty() {
-                println!("No repositories found.");
-            } else {
-                println!("📦 Repositories ({})", repos.len());
-                for repo in repos {
-                    let status = match repo.status.as_str() {
-                        "cloned" => "✓",
+            println!("✨ Done!");
+        }
+        Some(Commands::ImportLocal {
+            dir,
+            provider,
+            move_clones,
+            dry_run,
+        }) => {
+            let provider = provider.map(|p| p.parse::<Provider>()).transpose()?;
+            let gitlab_host = config.gitlab_base_url.as_deref().and_then(host_of);
+            let git_ops = git_operations(&config)?;
+
+            println!("🔍 Scanning {} for git repositories", dir);
+            let clones = importers::local::scan(
+                Path::new(&dir),
+                &git_ops,
+                provider,
+                gitlab_host.as_deref(),
+            )?;
+            println!("📦 Found {} git repositories", clones.len());
+
+            let mut imported = 0;
+            for clone in clones {
+                let mut repo = match clone.repository {
+                    Ok(repo) => repo,
+                    Err(e) => {
+                        eprintln!("   ⚠️  Skipping {}: {}", clone.path.display(), e);
+                        continue;
+                    }
+                };
+
+                if move_clones {
+                    let target = git_ops.get_repo_path(&repo);
+                    if target.exists() && target != clone.path {
+                        eprintln!(
+                            "   ⚠️  Not moving {}: {} already exists",
+                            clone.path.display(),
+                            target.display()
+                        );
+                    } else if target != clone.path && !dry_run {
+                        match git_ops.move_clone(&clone.path, &target) {
+                            Ok(_) => repo.set_local_path(target.to_string_lossy().to_string()),
+                            Err(e) => eprintln!("   ⚠️  Could not move: {}", e),
+                        }
+                    } else if target != clone.path {
+                        repo.set_local_path(target.to_string_lossy().to_string());
+                    }
+                }
+
+                let path = repo.local_path.clone().unwrap_or_default();
+                if dry_run {
+                    println!("   {} -> {}", repo.full_name, path);
+                    continue;
+                }
+
+                match db.get_repository_by_full_name(&repo.full_name).await? {
+                    Some(mut existing) => {
+                        existing.set_local_path(path.clone());
+                        existing.update_status(CloneStatus::Cloned);
+                        db.update_repository(&existing).await?;
+                    }
+                    None => db.create_repository(&repo).await?,
+                }
+                println!("   ✅ {} -> {}", repo.full_name, path);
+                imported += 1;
+            }
+
+            if dry_run {
+                println!("Dry run: nothing was changed");
+            } else {
+                println!("✨ Imported {} repositories", imported);
+            }
+        }
+        Some(Commands::ImportRepoManifest {
+            manifest,
+            root,
+            manifest_url,
+            import,
+        }) => {
+            let root = root
+                .or_else(|| importers::repo_manifest::checkout_root(&manifest))
+                .unwrap_or_else(|| PathBuf::from(&config.clone_base_path));
+            let manifest_url = match manifest_url {
+                Some(url) => Some(url),
+                None => manifest_repository_url(&config, &manifest)?,
+            };
+            println!("📜 Reading {}", manifest.display());
+            let entries =
+                importers::repo_manifest::read(&manifest, &root, manifest_url.as_deref())?;
+            import_entries(&db, &config, entries, &import).await?;
+        }
+        Some(Commands::ImportMrconfig { file, import }) => {
+            let file = file.unwrap_or_else(|| {
+                dirs::home_dir()
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join(".mrconfig")
+            });
+            println!("📜 Reading {}", file.display());
+            let entries = importers::mrconfig::read(&file)?;
+            import_entries(&db, &config, entries, &import).await?;
+        }
+        Some(Commands::ImportGhq { root, list, import }) => {
+            let root = root.unwrap_or_else(importers::ghq::default_root);
+            let entries = match list {
+                Some(list) => {
+                    let content = if list.as_os_str() == "-" {
+                        std::io::read_to_string(std::io::stdin())?
+                    } else {
+                        std::fs::read_to_string(&list).map_err(|e| {
+                            anyhow::anyhow!("Failed to read {}: {}", list.display(), e)
+                        })?
+                    };
+                    importers::ghq::parse_list(&content, &root)?
+                }
+                None => {
+                    println!("🔍 Scanning ghq root {}", root.display());
+                    importers::ghq::scan(&root, &git_operations(&config)?)?
+                }
+            };
+            import_entries(&db, &config, entries, &import).await?;
+        }
+        Some(Commands::Relayout { dry_run }) => {
+            let git_ops = git_operations(&config)?;
+            let repos = db.get_all_repositories().await?;
+
+            println!("📐 Applying layout: {}", config.path_template);
+            let mut moved = 0;
+            for mut repo in repos {
+                let current = match &repo.local_path {
+                    Some(path) => PathBuf::from(path),
+                    None => continue,
+                };
+                let target = git_ops.get_repo_path(&repo);
+                if current == target {
+                    continue;
+                }
+
+                if dry_run {
+                    println!("   {} -> {}", current.display(), target.display());
+                    moved += 1;
+                    continue;
+                }
+
+                if !current.exists() {
+                    eprintln!(
+                        "   ⚠️  Skipping {}: {} does not exist",
+                        repo.full_name,
+                        current.display()
+                    );
+                    continue;
+                }
+                match git_ops.move_clone(&current, &target) {
+                    Ok(_) => {
+                        repo.set_local_path(target.to_string_lossy().to_string());
+                        db.update_repository(&repo).await?;
+                        println!("   ✅ {} -> {}", current.display(), target.display());
+                        moved += 1;
+                    }
+                    Err(e) => eprintln!("   ❌ {}: {}", repo.full_name, e),
+                }
+            }
+
+            if dry_run {
+                println!("Dry run: {} clones would be moved", moved);
+            } else {
+                println!("✨ Moved {} clones", moved);
+            }
+        }
+        Some(Commands::List { filters }) => {
+            let repos = filters
+                .to_filter(&db)
+                .await?
+                .apply(db.get_all_repositories().await?);
+
+            let mirrors: HashMap<String, Mirror> = db
+                .get_all_mirrors()
+                .await?
+                .into_iter()
+                .map(|m| (m.repository_id.clone(), m))
+                .collect();
+            let tags = db.get_all_repository_tags().await?;
+
+            if repos.is_empty() {
+                println!("No repositories found.");
+            } else {
+                println!("📦 Repositories ({})", repos.len());
+                for repo in repos {
+                    let status = match repo.status.as_str() {
+                        "cloned" => "✓",
                         "not_cloned" => "○",
                         "error" => "✗",
                         _ => "?",
@@ -446,11 +1181,47 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     if let Some(path) = &repo.local_path {
                         println!("   📁 {}", path);
                     }
+                    if let Some(mirror) = mirrors.get(&repo.id) {
+                        println!("   🪞 {}", mirror.local_path);
+                    }
+                    if let Some(tags) = tags.get(&repo.id) {
+                        println!("   🏷  {}", tags.join(", "));
+                    }
+                }
+            }
+        }
+        Some(Commands::Search { query, limit }) => {
+            let repos = db.search(&query, limit).await?;
+            if repos.is_empty() {
+                println!("No repositories match \"{}\".", query);
+            } else {
+                println!("🔍 {} results for \"{}\"", repos.len(), query);
+                for repo in repos {
+                    let status = if repo.status == CloneStatus::Cloned.to_string() {
+                        "✓"
+                    } else {
+                        "○"
+                    };
+                    println!("{} [{}] {}", status, repo.provider, repo.full_name);
+                    if let Some(description) = &repo.description {
+                        println!("   {}", description);
+                    }
+                    let topics = repo.topics();
+                    if !topics.is_empty() {
+                        println!("   🏷  {}", topics.join(", "));
+                    }
                 }
             }
         }
-        Some(Commands::PullAll) => {
-            let repos = db.get_repositories_by_status(CloneStatus::Cloned).await?;
+        Some(Commands::PullAll { source, tags }) => {
+            let mut repos = db.get_repositories_by_status(CloneStatus::Cloned).await?;
+            if let Some(source) = &source {
+                let source: RepositorySource = source.parse()?;
+                repos.retain(|r| r.has_source(&source));
+            }
+            if let Some(tagged) = tagged_repository_ids(&db, &tags).await? {
+                repos.retain(|r| tagged.contains(&r.id));
+            }
 
             if repos.is_empty() {
                 println!("No cloned repositories found.");
@@ -459,61 +1230,2046 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             println!("🔄 Pulling updates for {} repositories", repos.len());
 
-            let git_ops = GitOperations::with_tokens(
-                PathBuf::from(&config.clone_base_path),
-                config.github_token.clone(),
-                config.gitlab_token.clone(),
+            let mut description = match &source {
+                Some(source) => format!("pull-all --source {}", source),
+                None => "pull-all".to_string(),
+            };
+            for tag in &tags {
+                description.push_str(&format!(" --tag {}", tag));
+            }
+            let job = Job::new(JobKind::Pull, description);
+            let tasks: Vec<JobTask> = repos
+                .iter()
+                .filter(|repo| repo.local_path.is_some())
+                .enumerate()
+                .map(|(position, repo)| {
+                    JobTask::new(job.id.clone(), repo.id.clone(), position as u32)
+                })
+                .collect();
+            db.create_job(&job, &tasks).await?;
+            run_job(&db, &config, job, tasks, Retry::once()).await?;
+
+            println!("✨ Done!");
+        }
+        Some(Commands::Clone {
+            repo: None,
+            filters,
+            depth,
+            filter,
+            single_branch,
+            sparse_paths,
+        }) => {
+            let options = CloneOptions::new(depth, filter, single_branch)?;
+            if !options.is_full() {
+                config.clone_options = options;
+            }
+            let mut repos = filters
+                .to_filter(&db)
+                .await?
+                .apply(db.get_all_repositories().await?);
+            if repos.is_empty() {
+                println!("No repositories match the filters.");
+                return Ok(());
+            }
+            if !sparse_paths.is_empty() {
+                record_sparse_paths(&db, &config, &mut repos, &sparse_paths).await?;
+            }
+            let description = format!("clone {} repositories", repos.len());
+            save_and_clone(&db, &config, &description, &repos).await?;
+        }
+        Some(Commands::Clone {
+            repo: Some(repo),
+            depth,
+            filter,
+            single_branch,
+            sparse_paths,
+            ..
+        }) => {
+            let options = CloneOptions::new(depth, filter, single_branch)?;
+            if !options.is_full() {
+                config.clone_options = options;
+            }
+            let repository = db.get_repository_by_full_name(&repo).await?;
+
+            if let Some(repo) = repository {
+                let mut repos = vec![repo];
+                if !sparse_paths.is_empty() {
+                    record_sparse_paths(&db, &config, &mut repos, &sparse_paths).await?;
+                }
+                let description = format!("clone {}", repos[0].full_name);
+                save_and_clone(&db, &config, &description, &repos).await?;
+            } else {
+                eprintln!("Repository not found: {}", repo);
+                eprintln!("First discover it using 'clone-user' or 'clone-org' command");
+            }
+        }
+        Some(Commands::Sparse {
+            repo,
+            paths,
+            disable,
+        }) => {
+            let mut repo = db
+                .get_repository_by_full_name(&repo)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Repository not found: {}", repo))?;
+            if !disable && paths.is_empty() {
+                let current = repo.sparse_paths();
+                if current.is_empty() {
+                    println!("{} uses a full checkout", repo.full_name);
+                } else {
+                    println!("{} sparse checkout:", repo.full_name);
+                    for path in current {
+                        println!("   📁 {}", path);
+                    }
+                }
+                return Ok(());
+            }
+
+            repo.set_sparse_paths(&paths);
+            let clone = repo
+                .local_path
+                .clone()
+                .filter(|path| Path::new(path).exists());
+            if let Some(path) = clone {
+                let git_ops = git_operations(&config)?;
+                if disable {
+                    git_ops.disable_sparse_checkout(&path).await?;
+                } else {
+                    git_ops
+                        .set_sparse_checkout(&path, &repo.sparse_paths())
+                        .await?;
+                }
+            }
+            db.update_repository(&repo).await?;
+
+            if disable {
+                println!("✅ {} now uses a full checkout", repo.full_name);
+            } else {
+                println!(
+                    "✅ {} sparse checkout: {}",
+                    repo.full_name,
+                    repo.sparse_paths().join(", ")
+                );
+            }
+        }
+        Some(Commands::Mirror {
+            repos,
+            provider,
+            pull_refs,
+            no_pull_refs,
+        }) => {
+            // Without either flag every mirror keeps the setting it was created with
+            let pull_refs = match (pull_refs, no_pull_refs) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
+            let mut selected = if let Some(p) = provider {
+                db.get_repositories_by_provider(&p).await?
+            } else {
+                db.get_all_repositories().await?
+            };
+            if !repos.is_empty() {
+                selected.retain(|r| repos.contains(&r.full_name));
+            }
+
+            if selected.is_empty() {
+                println!("No repositories found.");
+                return Ok(());
+            }
+
+            let git_ops = git_operations(&config)?;
+            let mirror_root = PathBuf::from(&config.mirror_base_path);
+            println!(
+                "🪞 Mirroring {} repositories into {}",
+                selected.len(),
+                mirror_root.display()
             );
-            for repo in repos {
-                if let Some(path) = &repo.local_path {
-                    print!("⬇️  Pulling: {} ... ", repo.full_name);
-                    match git_ops.pull_repository(path).await {
-                        Ok(_) => {
-                            let mut updated_repo = repo.clone();
-                            updated_repo.update_pulled_at();
-                            db.update_repository(&updated_repo).await?;
-                            println!("✅");
+
+            for repo in selected {
+                let existing = db.get_mirror(&repo.id).await?;
+                let mut mirror = match existing {
+                    Some(mirror) if Path::new(&mirror.local_path).exists() => {
+                        print!("🔄 Updating: {} ... ", repo.full_name);
+                        let mut mirror = mirror;
+                        if let Some(pull_refs) = pull_refs {
+                            mirror.include_pull_refs = pull_refs;
                         }
-                        Err(e) => {
-                            println!("❌ Failed: {}", e);
+                        mirror.update_status(CloneStatus::Updating);
+                        db.save_mirror(&mirror).await?;
+                        let result = git_ops
+                            .sync_mirror(&mirror.local_path, mirror.include_pull_refs)
+                            .await;
+                        finish_mirror(&db, &mut mirror, result).await?;
+                        continue;
+                    }
+                    Some(mut mirror) => {
+                        if let Some(pull_refs) = pull_refs {
+                            mirror.include_pull_refs = pull_refs;
+                        }
+                        mirror
+                    }
+                    None => {
+                        let path = git_ops.get_mirror_path(&mirror_root, &repo);
+                        Mirror::new(
+                            repo.id.clone(),
+                            path.to_string_lossy().to_string(),
+                            pull_refs.unwrap_or(false),
+                        )
+                    }
+                };
+
+                print!("⬇️  Mirroring: {} ... ", repo.full_name);
+                mirror.update_status(CloneStatus::Cloning);
+                db.save_mirror(&mirror).await?;
+                let result = git_ops
+                    .mirror_repository(
+                        &repo,
+                        Path::new(&mirror.local_path),
+                        config.use_ssh,
+                        mirror.include_pull_refs,
+                    )
+                    .await;
+                finish_mirror(&db, &mut mirror, result).await?;
+            }
+
+            println!("✨ Done!");
+        }
+        Some(Commands::Export { format, output }) => {
+            let format = match format {
+                Some(format) => format.parse()?,
+                None => output
+                    .as_deref()
+                    .and_then(InventoryFormat::from_path)
+                    .unwrap_or(InventoryFormat::Json),
+            };
+            let inventory = Inventory::export(&db).await?;
+            match output {
+                Some(path) => {
+                    let file = std::fs::File::create(&path).map_err(|e| {
+                        anyhow::anyhow!("Failed to write {}: {}", path.display(), e)
+                    })?;
+                    inventory.write(format, std::io::BufWriter::new(file))?;
+                    println!(
+                        "📝 Wrote {} repositories to {}",
+                        inventory.repositories.len(),
+                        path.display()
+                    );
+                }
+                None => inventory.write(format, std::io::stdout().lock())?,
+            }
+        }
+        Some(Commands::Import {
+            file,
+            format,
+            dry_run,
+        }) => {
+            let format = format.map(|f| f.parse()).transpose()?;
+            let inventory = Inventory::read_file(&file, format)?;
+            println!(
+                "📥 Merging {} repositories from {}",
+                inventory.repositories.len(),
+                file.display()
+            );
+            let summary = inventory.merge(&db, dry_run).await?;
+            println!(
+                "   {} added, {} updated, {} unchanged, {} tags added",
+                summary.added, summary.updated, summary.unchanged, summary.tags_added
+            );
+            if dry_run {
+                println!("Dry run: nothing was changed");
+            } else {
+                println!("✨ Done!");
+            }
+        }
+        Some(Commands::ExportBundles { dir, repos, full }) => {
+            let mut selected = db.get_repositories_by_status(CloneStatus::Cloned).await?;
+            if !repos.is_empty() {
+                selected.retain(|r| repos.contains(&r.full_name));
+            }
+
+            let git_ops = git_operations(&config)?;
+            let out_dir = PathBuf::from(&dir);
+            std::fs::create_dir_all(&out_dir)?;
+            let mut manifest = BundleManifest::default();
+            println!(
+                "📦 Exporting {} repositories to {}",
+                selected.len(),
+                out_dir.display()
+            );
+
+            for repo in selected {
+                // Prefer the mirror, which carries every ref
+                let source = match db.get_mirror(&repo.id).await? {
+                    Some(mirror) if Path::new(&mirror.local_path).exists() => mirror.local_path,
+                    _ => match &repo.local_path {
+                        Some(path) if Path::new(path).exists() => path.clone(),
+                        _ => {
+                            eprintln!("   ⚠️  Skipping {}: clone is missing", repo.full_name);
+                            continue;
                         }
+                    },
+                };
+
+                let refs = match git_ops.list_refs(&source) {
+                    Ok(refs) if !refs.is_empty() => refs,
+                    Ok(_) => {
+                        eprintln!("   ⚠️  Skipping {}: no refs", repo.full_name);
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("   ❌ {}: {}", repo.full_name, e);
+                        continue;
+                    }
+                };
+
+                let previous = if full {
+                    None
+                } else {
+                    db.get_latest_bundle_export(&repo.id)
+                        .await?
+                        .map(|export| export.refs())
+                };
+                if previous.as_ref() == Some(&refs) {
+                    println!("   ⏭️  Up to date: {}", repo.full_name);
+                    continue;
+                }
+
+                let mut prerequisites: Vec<String> = previous
+                    .map(|refs| {
+                        refs.into_values()
+                            .collect::<BTreeSet<_>>()
+                            .into_iter()
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let file = bundle_file_name(&repo, manifest.created_at);
+                let bundle_path = out_dir.join(&file);
+
+                let mut result = git_ops
+                    .create_bundle(&source, &bundle_path, &prerequisites)
+                    .await;
+                if result.is_err() && !prerequisites.is_empty() {
+                    // Earlier commits may be gone after a force push; fall back to a full bundle
+                    prerequisites.clear();
+                    result = git_ops.create_bundle(&source, &bundle_path, &[]).await;
+                }
+
+                match result {
+                    Ok(_) => {
+                        let incremental = !prerequisites.is_empty();
+                        db.create_bundle_export(&BundleExport::new(
+                            repo.id.clone(),
+                            bundle_path.to_string_lossy().to_string(),
+                            incremental,
+                            &refs,
+                        ))
+                        .await?;
+                        println!(
+                            "   ✅ {} ({})",
+                            file,
+                            if incremental { "incremental" } else { "full" }
+                        );
+                        manifest.entries.push(BundleEntry {
+                            repository: repo,
+                            bundle: file,
+                            incremental,
+                            refs,
+                            prerequisites,
+                        });
+                    }
+                    Err(e) => eprintln!("   ❌ {}: {}", repo.full_name, e),
+                }
+            }
+
+            manifest.write(&out_dir)?;
+            println!(
+                "✨ Wrote {} bundles and {}",
+                manifest.entries.len(),
+                MANIFEST_FILE
+            );
+        }
+        Some(Commands::ImportBundles { dir }) => {
+            let in_dir = PathBuf::from(&dir);
+            let manifest = BundleManifest::read(&in_dir)?;
+            let git_ops = git_operations(&config)?;
+            println!("📦 Importing {} bundles", manifest.entries.len());
+
+            for entry in manifest.entries {
+                let bundle_path = in_dir.join(&entry.bundle);
+                let mut repo = match db
+                    .get_repository_by_full_name(&entry.repository.full_name)
+                    .await?
+                {
+                    Some(existing) => existing,
+                    None => {
+                        let mut repo = entry.repository.clone();
+                        repo.local_path = None;
+                        repo.update_status(CloneStatus::NotCloned);
+                        db.create_repository(&repo).await?;
+                        repo
+                    }
+                };
+
+                let existing_clone = repo
+                    .local_path
+                    .clone()
+                    .filter(|path| Path::new(path).exists());
+                let result = match existing_clone {
+                    Some(path) => git_ops
+                        .fetch_from_bundle(&path, &bundle_path)
+                        .await
+                        .map(|_| path),
+                    None if entry.incremental => Err(anyhow::anyhow!(
+                        "incremental bundle needs an existing clone; import a full bundle first"
+                    )),
+                    None => {
+                        let target = git_ops.get_repo_path(&repo);
+                        let origin = if config.use_ssh {
+                            &repo.clone_url_ssh
+                        } else {
+                            &repo.clone_url_https
+                        };
+                        git_ops
+                            .clone_from_bundle(&bundle_path, &target, origin)
+                            .await
+                            .map(|_| target.to_string_lossy().to_string())
+                    }
+                };
+
+                match result {
+                    Ok(path) => {
+                        repo.set_local_path(path.clone());
+                        repo.update_status(CloneStatus::Cloned);
+                        db.update_repository(&repo).await?;
+                        println!("   ✅ {} -> {}", repo.full_name, path);
                     }
+                    Err(e) => eprintln!("   ❌ {}: {}", repo.full_name, e),
                 }
             }
 
             println!("✨ Done!");
         }
-        Some(Commands::Clone { repo }) => {
-            let repository = db.get_repository_by_full_name(&repo).await?;
+        Some(Commands::Deepen { repo, by }) => {
+            let mut repo = find_cloned_repository(&db, &repo).await?;
+            let options = repo.clone_options();
+            let path = repo.local_path.clone().unwrap_or_default();
+            let depth = match options.depth {
+                Some(depth) => depth,
+                None => {
+                    println!("{} is not a shallow clone", repo.full_name);
+                    return Ok(());
+                }
+            };
 
-            if let Some(repo) = repository {
-                let git_ops = GitOperations::with_tokens(
-                    PathBuf::from(&config.clone_base_path),
-                    config.github_token.clone(),
-                    config.gitlab_token.clone(),
+            println!("⬇️  Deepening: {} by {} commits", repo.full_name, by);
+            git_operations(&config)?
+                .deepen_repository(&path, by)
+                .await?;
+            repo.set_clone_options(&CloneOptions {
+                depth: Some(depth.saturating_add(by)),
+                ..options
+            });
+            db.update_repository(&repo).await?;
+            println!("✅ Done");
+        }
+        Some(Commands::Unshallow { repo }) => {
+            let mut repo = find_cloned_repository(&db, &repo).await?;
+            let options = repo.clone_options();
+            let path = repo.local_path.clone().unwrap_or_default();
+            if options.is_full() {
+                println!("{} is already a full clone", repo.full_name);
+                return Ok(());
+            }
+
+            println!("⬇️  Converting to full clone: {}", repo.full_name);
+            git_operations(&config)?
+                .unshallow_repository(&path, &options)
+                .await?;
+            repo.set_clone_options(&CloneOptions::default());
+            db.update_repository(&repo).await?;
+            println!("✅ Done");
+        }
+        Some(Commands::Exec {
+            filters,
+            parallel,
+            output,
+            continue_on_error,
+            json,
+            command,
+        }) => {
+            let output: OutputMode = if json {
+                OutputMode::Capture
+            } else {
+                output.parse()?
+            };
+            let targets = clone_targets(
+                filters
+                    .to_filter(&db)
+                    .await?
+                    .apply(db.get_all_repositories().await?),
+            );
+            if targets.is_empty() {
+                if json {
+                    println!("[]");
+                } else {
+                    println!("No cloned repositories found.");
+                }
+                return Ok(());
+            }
+
+            let options = ExecOptions {
+                parallel,
+                output,
+                continue_on_error,
+                cancel: config.run_limits.cancel.clone(),
+            };
+            let results = run_in_repositories(&targets, &command, &options).await?;
+            let failed: Vec<_> = results
+                .iter()
+                .filter(|r| !r.success && !r.skipped)
+                .collect();
+            let skipped = results.iter().filter(|r| r.skipped).count();
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            } else {
+                println!();
+                println!(
+                    "✨ {} succeeded, {} failed, {} skipped",
+                    results.len() - failed.len() - skipped,
+                    failed.len(),
+                    skipped
                 );
-                println!("⬇️  Cloning: {}", repo.full_name);
-                match git_ops.clone_repository(&repo, config.use_ssh).await {
-                    Ok(path) => {
-                        let mut updated_repo = repo.clone();
-                        updated_repo.set_local_path(path.clone());
-                        updated_repo.update_status(CloneStatus::Cloned);
-                        db.update_repository(&updated_repo).await?;
-                        println!("✅ Cloned to: {}", path);
+                for result in &failed {
+                    let reason = match (&result.error, result.exit_code) {
+                        (Some(error), _) => error.clone(),
+                        (None, Some(code)) => format!("exit code {}", code),
+                        (None, None) => "killed by a signal".to_string(),
+                    };
+                    println!("   ❌ {}: {}", result.repository, reason);
+                }
+            }
+            if !failed.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Command failed in {} of {} repositories",
+                    failed.len(),
+                    results.len()
+                )
+                .into());
+            }
+        }
+        Some(Commands::Grep {
+            pattern,
+            filters,
+            fixed_strings,
+            ignore_case,
+            paths,
+            parallel,
+            json,
+        }) => {
+            let targets = clone_targets(
+                filters
+                    .to_filter(&db)
+                    .await?
+                    .apply(db.get_all_repositories().await?),
+            );
+            let options = GrepOptions {
+                pattern,
+                fixed_strings,
+                ignore_case,
+                paths,
+                parallel,
+            };
+            let mut results = super_clone::grep::search(&targets, &options).await?;
+            results.retain(|r| !r.matches.is_empty() || r.error.is_some());
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+                return Ok(());
+            }
+            let mut total = 0;
+            for result in &results {
+                if let Some(error) = &result.error {
+                    eprintln!("⚠️  {}: {}", result.repository, error);
+                    continue;
+                }
+                println!("📦 {}", result.repository);
+                for found in &result.matches {
+                    println!(
+                        "   {}:{}: {}",
+                        found.path,
+                        found.line,
+                        found.text.trim_end()
+                    );
+                }
+                total += result.matches.len();
+            }
+            let repos = results.iter().filter(|r| !r.matches.is_empty()).count();
+            println!(
+                "🔍 {} matches in {} of {} repositories",
+                total,
+                repos,
+                targets.len()
+            );
+        }
+        Some(Commands::Errors { repo, limit }) => match repo {
+            Some(full_name) => {
+                let repo = db
+                    .get_repository_by_full_name(&full_name)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("Repository not found: {}", full_name))?;
+                let operations = db.get_operations(&repo.id, limit).await?;
+                if operations.is_empty() {
+                    println!("No clone or pull recorded for {}.", repo.full_name);
+                } else {
+                    println!("📜 History of {} ({})", repo.full_name, repo.status);
+                    for operation in &operations {
+                        print_operation(operation);
+                    }
+                }
+            }
+            None => {
+                let failures = db.get_current_failures().await?;
+                if failures.is_empty() {
+                    println!("No failed repositories.");
+                } else {
+                    println!("❌ Failed repositories ({})", failures.len());
+                    for operation in &failures {
+                        let name = db
+                            .get_repository(&operation.repository_id)
+                            .await?
+                            .map(|repo| repo.full_name)
+                            .unwrap_or_else(|| operation.repository_id.clone());
+                        println!("{}", name);
+                        print_operation(operation);
                     }
+                }
+            }
+        },
+        Some(Commands::Apply { manifest, dry_run }) => {
+            let manifest = Manifest::read(&manifest)?;
+            let git_ops = git_operations(&config)?;
+            let reporter = if dry_run {
+                ProgressReporter::plain()
+            } else {
+                ProgressReporter::new(manifest.repositories.len(), "Applying")
+            };
+            let mut failed = 0;
+            for entry in &manifest.repositories {
+                if config.run_limits.cancel.is_cancelled() {
+                    break;
+                }
+                if let Err(e) = apply_entry(&db, &config, &git_ops, &reporter, entry, dry_run).await
+                {
+                    failed += 1;
+                    reporter.eprintln(format!(
+                        "   ❌ {}: {}",
+                        entry.name,
+                        redact(&config, &e.to_string())
+                    ));
+                }
+            }
+            reporter.finish();
+
+            let extras: Vec<Repository> = db
+                .get_repositories_by_status(CloneStatus::Cloned)
+                .await?
+                .into_iter()
+                .filter(|repo| !manifest.contains(&repo.full_name))
+                .collect();
+            if !extras.is_empty() {
+                println!(
+                    "📎 {} cloned repositories are not in the manifest:",
+                    extras.len()
+                );
+                for repo in &extras {
+                    println!(
+                        "   {} ({})",
+                        repo.full_name,
+                        repo.local_path.as_deref().unwrap_or("-")
+                    );
+                }
+            }
+            if failed > 0 {
+                return Err(anyhow::anyhow!(
+                    "{} of {} repositories could not be applied",
+                    failed,
+                    manifest.repositories.len()
+                )
+                .into());
+            }
+            println!("✨ Done!");
+        }
+        Some(Commands::Freeze {
+            filters,
+            pin,
+            output,
+        }) => {
+            let repos = filters
+                .to_filter(&db)
+                .await?
+                .apply(db.get_repositories_by_status(CloneStatus::Cloned).await?);
+            let git_ops = git_operations(&config)?;
+            let tags = db.get_all_repository_tags().await?;
+            let root = PathBuf::from(&config.clone_base_path);
+
+            let mut manifest = Manifest::default();
+            for repo in repos {
+                let Some(path) = repo.local_path.as_deref().map(PathBuf::from) else {
+                    continue;
+                };
+                let status = match git_ops.backend().status(&path).await {
+                    Ok(status) => status,
                     Err(e) => {
-                        let mut updated_repo = repo.clone();
-                        updated_repo.update_status(CloneStatus::Error);
-                        db.update_repository(&updated_repo).await?;
-                        eprintln!("❌ Failed: {}", e);
+                        eprintln!("⚠️  Skipping {}: {}", repo.full_name, e);
+                        continue;
                     }
+                };
+                let options = repo.clone_options();
+                let url = if config.use_ssh {
+                    repo.clone_url_ssh.clone()
+                } else {
+                    repo.clone_url_https.clone()
+                };
+                manifest.repositories.push(ManifestEntry {
+                    name: repo.full_name.clone(),
+                    url: Some(url),
+                    provider: Some(repo.provider.clone()),
+                    path: (path != git_ops.get_repo_path(&repo))
+                        .then(|| manifest_path(&path, &root)),
+                    branch: status.branch,
+                    revision: if pin { status.head } else { None },
+                    depth: options.depth,
+                    filter: options.filter,
+                    single_branch: options.single_branch,
+                    sparse: repo.sparse_paths(),
+                    tags: tags.get(&repo.id).cloned().unwrap_or_default(),
+                });
+            }
+
+            match output {
+                Some(path) => {
+                    manifest.write(&path)?;
+                    println!(
+                        "📝 Wrote {} repositories to {}",
+                        manifest.repositories.len(),
+                        path.display()
+                    );
                 }
+                None => print!("{}", manifest.to_toml()?),
+            }
+        }
+        Some(Commands::Prune {
+            filters,
+            upstream,
+            apply,
+            trash,
+            delete,
+        }) => {
+            let repos = db.get_all_repositories().await?;
+            let selected = if filters.is_set() {
+                let matching = filters.to_filter(&db).await?.apply(repos.clone());
+                Some(matching.into_iter().map(|repo| repo.id).collect())
             } else {
-                eprintln!("Repository not found: {}", repo);
-                eprintln!("First discover it using 'clone-user' or 'clone-org' command");
+                None
+            };
+            let states = if upstream {
+                upstream_states(&config, &repos).await?
+            } else {
+                HashMap::new()
+            };
+
+            let clone_root = PathBuf::from(&config.clone_base_path);
+            let trash_root = trash.unwrap_or_else(|| {
+                Path::new(&config.database_url)
+                    .parent()
+                    .unwrap_or(Path::new("."))
+                    .join("trash")
+            });
+            let git_ops = git_operations(&config)?;
+            let items = prune::plan(
+                &repos,
+                selected.as_ref(),
+                &states,
+                &clone_root,
+                &trash_root,
+                &git_ops,
+            )?;
+
+            if items.is_empty() {
+                println!("✨ Nothing to prune");
+                return Ok(());
+            }
+            println!("🧹 Prune plan:");
+            for item in &items {
+                let action = match (item.reason, &item.path) {
+                    (_, _) if item.is_blocked() => {
+                        format!("keep, it has {}", item.blocked_by.join(", "))
+                    }
+                    (PruneReason::DeletedUpstream, Some(_)) => {
+                        "remove clone and record".to_string()
+                    }
+                    (PruneReason::DeletedUpstream, None) => "remove record".to_string(),
+                    (PruneReason::MissingClone, _) => "mark as not cloned".to_string(),
+                    (PruneReason::Untracked, _) => "remove clone".to_string(),
+                    (_, _) => "remove clone, keep record".to_string(),
+                };
+                let icon = if item.is_blocked() { "⛔" } else { "🗑️ " };
+                println!("   {} {} ({}): {}", icon, item.label(), item.reason, action);
+                if let (Some(path), Some(_)) = (&item.path, &item.repository) {
+                    println!("      📁 {}", path.display());
+                }
+            }
+            let blocked = items.iter().filter(|item| item.is_blocked()).count();
+
+            if !apply {
+                println!(
+                    "Dry run: {} to prune, {} kept for local work; run again with --apply",
+                    items.len() - blocked,
+                    blocked
+                );
+                return Ok(());
+            }
+
+            let disposal = if delete {
+                Disposal::Delete
+            } else {
+                Disposal::Trash(
+                    trash_root.join(chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string()),
+                )
+            };
+            let mut pruned = 0;
+            for item in items.iter().filter(|item| !item.is_blocked()) {
+                match item.execute(&db, &clone_root, &disposal).await {
+                    Ok(Some(trashed)) => {
+                        println!("   ✅ {} -> {}", item.label(), trashed.display());
+                        pruned += 1;
+                    }
+                    Ok(None) => {
+                        println!("   ✅ {}", item.label());
+                        pruned += 1;
+                    }
+                    Err(e) => eprintln!("   ❌ {}: {}", item.label(), e),
+                }
             }
+            println!("✨ Pruned {}, kept {} with local work", pruned, blocked);
         }
+        Some(Commands::Verify { fix, reclone }) => {
+            let repos = db.get_all_repositories().await?;
+            let git_ops = git_operations(&config)?;
+            let clone_root = PathBuf::from(&config.clone_base_path);
+            let findings = verify::verify(&repos, &clone_root, &git_ops)?;
+
+            if findings.is_empty() {
+                println!(
+                    "✨ {} repositories verified, no problems found",
+                    repos.len()
+                );
+                return Ok(());
+            }
+            println!("🔍 Found {} problems:", findings.len());
+            for finding in &findings {
+                let label = match &finding.repository {
+                    Some(repo) => repo.full_name.clone(),
+                    None => finding.path.display().to_string(),
+                };
+                println!("   ⚠️  {} ({})", label, finding.problem);
+                if finding.repository.is_some() {
+                    println!("      📁 {}", finding.path.display());
+                }
+                if let Some(origin) = &finding.origin {
+                    println!("      🔗 origin {}", redact(&config, origin));
+                }
+            }
+
+            if !fix && !reclone {
+                println!("💡 Run again with --fix to update the records, or --reclone to also clone missing repositories");
+                return Err(anyhow::anyhow!("{} problems found", findings.len()).into());
+            }
+
+            let mut fixed = 0;
+            for finding in &findings {
+                if let Some(repo) = finding.fixed_repository() {
+                    db.update_repository(&repo).await?;
+                    fixed += 1;
+                }
+            }
+            let unknown = findings
+                .iter()
+                .filter(|finding| finding.problem == Problem::UnknownClone)
+                .count();
+            println!(
+                "✨ Fixed {} records, left {} unknown clones alone",
+                fixed, unknown
+            );
+
+            if reclone {
+                // A clone found elsewhere on disk was adopted instead
+                let adopted: HashSet<&str> = findings
+                    .iter()
+                    .filter(|finding| finding.problem == Problem::UnrecordedClone)
+                    .filter_map(|finding| finding.repository.as_ref())
+                    .map(|repo| repo.id.as_str())
+                    .collect();
+                let mut missing = Vec::new();
+                for finding in &findings {
+                    let Some(repo) = &finding.repository else {
+                        continue;
+                    };
+                    if finding.problem == Problem::MissingPath
+                        && !adopted.contains(repo.id.as_str())
+                    {
+                        if let Some(repo) = db.get_repository(&repo.id).await? {
+                            missing.push(repo);
+                        }
+                    }
+                }
+                if !missing.is_empty() {
+                    clone_repositories(&db, &config, "verify --reclone", &missing).await?;
+                }
+            }
+        }
+        Some(Commands::Tag { command }) => match command {
+            TagCommand::Add { tag, repos } => {
+                let tag = normalize_tag(&tag)?;
+                for full_name in repos {
+                    let repo = find_repository(&db, &full_name).await?;
+                    if db.add_tag(&repo.id, &tag).await? {
+                        println!("🏷  Tagged {} with {}", repo.full_name, tag);
+                    } else {
+                        println!("{} already has tag {}", repo.full_name, tag);
+                    }
+                }
+            }
+            TagCommand::Remove { tag, repos } => {
+                let tag = normalize_tag(&tag)?;
+                for full_name in repos {
+                    let repo = find_repository(&db, &full_name).await?;
+                    if db.remove_tag(&repo.id, &tag).await? {
+                        println!("Removed tag {} from {}", tag, repo.full_name);
+                    } else {
+                        println!("{} doesn't have tag {}", repo.full_name, tag);
+                    }
+                }
+            }
+            TagCommand::Delete { tag } => {
+                let tag = normalize_tag(&tag)?;
+                if db.delete_tag(&tag).await? {
+                    println!("🗑️  Deleted tag {}", tag);
+                } else {
+                    return Err(anyhow::anyhow!("Tag not found: {}", tag).into());
+                }
+            }
+            TagCommand::List {
+                repo: Some(full_name),
+            } => {
+                let repo = find_repository(&db, &full_name).await?;
+                let tags = db.get_repository_tags(&repo.id).await?;
+                if tags.is_empty() {
+                    println!("{} has no tags.", repo.full_name);
+                } else {
+                    println!("🏷  {}: {}", repo.full_name, tags.join(", "));
+                }
+            }
+            TagCommand::List { repo: None } => {
+                let tags = db.get_tags().await?;
+                if tags.is_empty() {
+                    println!("No tags defined.");
+                }
+                for (tag, count) in tags {
+                    println!("🏷  {:<20} {} repositories", tag, count);
+                }
+            }
+            TagCommand::Rule { command } => match command {
+                TagRuleCommand::Add { tag, kind, pattern } => {
+                    let kind: TagRuleKind = kind.parse()?;
+                    let rule = TagRule::new(normalize_tag(&tag)?, kind, pattern);
+                    db.create_tag_rule(&rule).await?;
+                    let tagged = db
+                        .apply_tag_rules(&db.get_all_repositories().await?)
+                        .await?;
+                    println!(
+                        "✅ Rule {} tags repositories whose {} matches '{}' with {} ({} tagged now)",
+                        rule.short_id(),
+                        rule.kind,
+                        rule.pattern,
+                        rule.tag,
+                        tagged
+                    );
+                }
+                TagRuleCommand::List => {
+                    let rules = db.get_tag_rules().await?;
+                    if rules.is_empty() {
+                        println!("No tag rules defined.");
+                    }
+                    for rule in rules {
+                        println!(
+                            "{}  {:<20} {:<5}  {}",
+                            rule.short_id(),
+                            rule.tag,
+                            rule.kind,
+                            rule.pattern
+                        );
+                    }
+                }
+                TagRuleCommand::Remove { id } => {
+                    let rule = db
+                        .find_tag_rule(&id)
+                        .await?
+                        .ok_or_else(|| anyhow::anyhow!("Tag rule not found: {}", id))?;
+                    db.delete_tag_rule(&rule.id).await?;
+                    println!("🗑️  Deleted rule {} ({})", rule.short_id(), rule.tag);
+                }
+            },
+        },
+        Some(Commands::Jobs { command }) => match command {
+            JobsCommand::List { limit } => {
+                let jobs = db.get_jobs(limit).await?;
+                if jobs.is_empty() {
+                    println!("No jobs found.");
+                }
+                for job in jobs {
+                    let tasks = db.get_job_tasks(&job.id).await?;
+                    let count = |status: TaskStatus| {
+                        tasks
+                            .iter()
+                            .filter(|t| t.status == status.to_string())
+                            .count()
+                    };
+                    println!(
+                        "{}  {:<5}  {:<11}  {}/{} done, {} failed  {}  {}",
+                        job.short_id(),
+                        job.kind,
+                        job.status,
+                        count(TaskStatus::Done),
+                        tasks.len(),
+                        count(TaskStatus::Failed),
+                        job.created_at.format("%Y-%m-%d %H:%M"),
+                        job.description
+                    );
+                }
+            }
+            JobsCommand::Resume { id } => {
+                let job = db
+                    .find_job(&id)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("Job not found: {}", id))?;
+                ensure_not_running(&job)?;
+                let tasks: Vec<JobTask> = db
+                    .get_job_tasks(&job.id)
+                    .await?
+                    .into_iter()
+                    .filter(|t| t.is_unfinished())
+                    .collect();
+                if tasks.is_empty() {
+                    println!("Job {} has nothing left to do.", job.short_id());
+                    return Ok(());
+                }
+                println!("▶️  Resuming {} repositories", tasks.len());
+                run_job(&db, &config, job, tasks, Retry::once()).await?;
+                println!("✨ Done!");
+            }
+            JobsCommand::RetryFailed {
+                job,
+                attempts,
+                backoff,
+            } => {
+                let found = match &job {
+                    Some(id) => db.find_job(id).await?,
+                    None => db.get_latest_job_with_failures().await?,
+                };
+                let Some(job) = found else {
+                    println!("No job with failed repositories found.");
+                    return Ok(());
+                };
+                ensure_not_running(&job)?;
+                let tasks: Vec<JobTask> = db
+                    .get_job_tasks(&job.id)
+                    .await?
+                    .into_iter()
+                    .filter(|t| t.status == TaskStatus::Failed.to_string())
+                    .collect();
+                if tasks.is_empty() {
+                    println!("Job {} has no failed repositories.", job.short_id());
+                    return Ok(());
+                }
+                println!("🔁 Retrying {} failed repositories", tasks.len());
+                let retry = Retry {
+                    attempts: attempts.max(1),
+                    backoff: Duration::from_secs(backoff),
+                };
+                run_job(&db, &config, job, tasks, retry).await?;
+                println!("✨ Done!");
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// Build git operations for the configured clone path, layout and tokens
+fn git_operations(config: &Config) -> anyhow::Result<GitOperations> {
+    let path_template: PathTemplate = config.path_template.parse()?;
+    let mut git_ops = GitOperations::with_tokens(
+        PathBuf::from(&config.clone_base_path),
+        config.github_token.clone(),
+        config.gitlab_token.clone(),
+    );
+    if let Some(host) = config.gitlab_base_url.as_deref().and_then(host_of) {
+        git_ops = git_ops.with_gitlab_host(host);
+    }
+    Ok(git_ops
+        .with_path_template(path_template)
+        .with_checkout_options(config.checkout_options.clone())
+        .with_limits(config.run_limits.clone())
+        .with_backend(backend_by_name(&config.git_backend, &config.run_limits)?))
+}
+
+/// Clone directories of the repositories that have one
+fn clone_targets(repos: Vec<Repository>) -> Vec<ExecTarget> {
+    repos
+        .into_iter()
+        .filter_map(|repo| {
+            repo.local_path.map(|path| ExecTarget {
+                name: repo.full_name,
+                path: PathBuf::from(path),
+            })
+        })
+        .collect()
+}
+
+/// Print one entry of a repository's clone and pull history
+fn print_operation(operation: &Operation) {
+    let icon = match operation.outcome.as_str() {
+        "success" => "✓",
+        "failed" => "✗",
+        _ => "⏹",
+    };
+    let exit_code = operation
+        .exit_code
+        .map(|code| format!(", exit code {}", code))
+        .unwrap_or_default();
+    println!(
+        "  {} {} {} {} ({:.1}s{})",
+        icon,
+        operation.started_at.format("%Y-%m-%d %H:%M"),
+        operation.kind,
+        operation.outcome,
+        operation.duration_ms as f64 / 1000.0,
+        exit_code
+    );
+    if let Some(error) = &operation.error {
+        for line in error.lines() {
+            println!("      {}", line);
+        }
+    }
+}
+
+/// Whether an operation failed because the user pressed Ctrl-C
+fn is_cancelled(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<GitError>(), Some(GitError::Cancelled))
+}
+
+/// Update submodules and LFS content of a fresh clone or pull and print what was done
+async fn update_checkout(
+    git_ops: &GitOperations,
+    repo: &Repository,
+    path: &str,
+    reporter: &ProgressReporter,
+) {
+    match git_ops.update_checkout(path).await {
+        Ok(report) => {
+            for note in report.notes() {
+                reporter.println(format!("   ℹ️  {}: {}", repo.full_name, note));
+            }
+        }
+        Err(e) => reporter.eprintln(format!("   ⚠️  {}: {}", repo.full_name, e)),
+    }
+}
+
+/// Fail early when a command needs to act as the authenticated user
+fn require_token(config: &Config, provider: &Provider) -> anyhow::Result<()> {
+    match provider {
+        Provider::GitHub if config.github_token.is_none() => Err(anyhow::anyhow!(
+            "GitHub token is required for this command. Set GITHUB_TOKEN env var or use --github-token flag."
+        )),
+        Provider::GitLab if config.gitlab_token.is_none() => Err(anyhow::anyhow!(
+            "GitLab token is required for this command. Set GITLAB_TOKEN env var or use --gitlab-token flag."
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Record the outcome of creating or updating a mirror
+async fn finish_mirror(
+    db: &RepositoryDatabase,
+    mirror: &mut Mirror,
+    result: anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    match result {
+        Ok(_) => {
+            mirror.update_status(CloneStatus::Cloned);
+            mirror.update_mirrored_at();
+            println!("✅");
+        }
+        Err(e) => {
+            mirror.update_status(CloneStatus::Error);
+            println!("❌ Failed: {}", e);
+        }
+    }
+    db.save_mirror(mirror).await?;
+    Ok(())
+}
+
+/// Look up a repository by full name that has a local clone
+async fn find_cloned_repository(
+    db: &RepositoryDatabase,
+    full_name: &str,
+) -> anyhow::Result<Repository> {
+    let repo = db
+        .get_repository_by_full_name(full_name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Repository not found: {}", full_name))?;
+    if repo.local_path.is_none() {
+        return Err(anyhow::anyhow!("Repository is not cloned: {}", full_name));
+    }
+    Ok(repo)
+}
+
+/// Store discovered repositories (and their wikis when enabled) and clone them
+async fn save_and_clone(
+    db: &RepositoryDatabase,
+    config: &Config,
+    description: &str,
+    repos: &[Repository],
+) -> anyhow::Result<()> {
+    let mut repos = save_repositories(db, repos).await?;
+    if config.with_wikis {
+        repos = add_wikis(db, config, repos).await?;
+    }
+    clone_repositories(db, config, description, &repos).await
+}
+
+/// Store the sparse checkout directories of the repositories about to be cloned.
+///
+/// Existing clones keep their checkout; `sparse` changes those.
+async fn record_sparse_paths(
+    db: &RepositoryDatabase,
+    config: &Config,
+    repos: &mut [Repository],
+    paths: &[String],
+) -> anyhow::Result<()> {
+    let git_ops = git_operations(config)?;
+    for repo in repos {
+        if git_ops.get_repo_path(repo).exists() {
+            println!(
+                "   ⏭️  {} is already cloned; use 'sparse' to change its checkout",
+                repo.full_name
+            );
+            continue;
+        }
+        repo.set_sparse_paths(paths);
+        db.update_repository(repo).await?;
+    }
+    Ok(())
+}
+
+/// Register linked wiki records and place each one right after its repository.
+///
+/// Wikis that are enabled but have no pages yet have no repository to clone,
+/// so they only get a record once `git ls-remote` finds them.
+async fn add_wikis(
+    db: &RepositoryDatabase,
+    config: &Config,
+    repos: Vec<Repository>,
+) -> anyhow::Result<Vec<Repository>> {
+    let git_ops = git_operations(config)?;
+    let mut with_wikis = Vec::with_capacity(repos.len());
+    for repo in repos {
+        let wiki = if repo.has_wiki && repo.kind == RepositoryKind::Repository.to_string() {
+            let wiki = repo.wiki_repository();
+            let known = db.get_repository_by_full_name(&wiki.full_name).await?;
+            let exists = match known {
+                Some(_) => true,
+                None => match git_ops.wiki_exists(&wiki, config.use_ssh).await {
+                    Ok(exists) => exists,
+                    Err(e) => {
+                        // Let the clone report errors other than a missing wiki
+                        eprintln!("   ⚠️  {}: {}", wiki.full_name, e);
+                        true
+                    }
+                },
+            };
+            if exists {
+                save_repositories(db, std::slice::from_ref(&wiki))
+                    .await?
+                    .pop()
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+        with_wikis.push(repo);
+        with_wikis.extend(wiki);
+    }
+    Ok(with_wikis)
+}
+
+/// Bring the clone of one manifest entry in line with the manifest: clone it when
+/// missing, otherwise move it to the manifest path and update it
+async fn apply_entry(
+    db: &RepositoryDatabase,
+    config: &Config,
+    git_ops: &GitOperations,
+    reporter: &ProgressReporter,
+    entry: &ManifestEntry,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let options = entry.clone_options()?;
+    let known = db.get_repository_by_full_name(&entry.name).await?;
+    let mut repo = match &known {
+        Some(repo) => repo.clone(),
+        None => {
+            let gitlab_host = config.gitlab_base_url.as_deref().and_then(host_of);
+            entry.to_repository(gitlab_host.as_deref())?
+        }
+    };
+
+    let current = repo
+        .local_path
+        .as_deref()
+        .map(PathBuf::from)
+        .filter(|path| path.join(".git").exists());
+    let target = entry
+        .target_path(Path::new(&config.clone_base_path))
+        .or_else(|| current.clone())
+        .unwrap_or_else(|| git_ops.get_repo_path(&repo));
+    let moved_from = current.filter(|path| *path != target);
+    let exists = moved_from.is_some() || target.join(".git").exists();
+
+    if dry_run {
+        match (&moved_from, exists) {
+            (Some(from), _) => println!(
+                "🚚 {}: move {} to {} and update",
+                entry.name,
+                from.display(),
+                target.display()
+            ),
+            (None, true) => println!("🔄 {}: update {}", entry.name, target.display()),
+            (None, false) => println!("⬇️  {}: clone into {}", entry.name, target.display()),
+        }
+        return Ok(());
+    }
+
+    if known.is_none() {
+        repo = save_repositories(db, std::slice::from_ref(&repo))
+            .await?
+            .pop()
+            .unwrap_or(repo);
+    }
+    let sparse_changed = repo.sparse_paths() != entry.sparse;
+    repo.set_clone_options(&options);
+    repo.set_sparse_paths(&entry.sparse);
+    for tag in &entry.tags {
+        db.add_tag(&repo.id, &normalize_tag(tag)?).await?;
+    }
+
+    if let Some(from) = &moved_from {
+        git_ops.move_clone(from, &target)?;
+        reporter.println(format!("🚚 Moved {} to {}", entry.name, target.display()));
+    }
+    let path = target.to_string_lossy().to_string();
+
+    if !exists {
+        reporter.println(format!("⬇️  Cloning: {}", repo.full_name));
+        repo.update_status(CloneStatus::Cloning);
+        db.update_repository(&repo).await?;
+        let task = reporter.task(&repo.full_name);
+        let operation = Operation::start(repo.id.clone(), OperationKind::Clone);
+        let (source, use_ssh) = entry
+            .clone_source(&repo)
+            .unwrap_or_else(|| (repo.clone(), config.use_ssh));
+        let mut result = git_ops
+            .clone_repository_to(&source, &target, &options, use_ssh, Some(task.callback()))
+            .await;
+        if let (Ok(_), Some(revision)) = (&result, &entry.revision) {
+            if let Err(e) = git_ops.checkout_revision(&path, revision).await {
+                result = Err(e);
+            }
+        }
+        task.finish();
+        let error = record_operation(db, config, operation, &result).await?;
+        if result.is_err() {
+            repo.update_status(CloneStatus::Error);
+            db.update_repository(&repo).await?;
+            return Err(anyhow::anyhow!(error.unwrap_or_default()));
+        }
+        repo.set_local_path(path.clone());
+        repo.update_status(CloneStatus::Cloned);
+        db.update_repository(&repo).await?;
+        reporter.println(format!("   ✅ Cloned to: {}", path));
+    } else {
+        reporter.println(format!("🔄 Updating: {}", repo.full_name));
+        repo.set_local_path(path.clone());
+        repo.update_status(CloneStatus::Cloned);
+        db.update_repository(&repo).await?;
+        if sparse_changed {
+            if entry.sparse.is_empty() {
+                git_ops.disable_sparse_checkout(&path).await?;
+            } else {
+                git_ops.set_sparse_checkout(&path, &entry.sparse).await?;
+            }
+        }
+        let task = reporter.task(&repo.full_name);
+        let operation = Operation::start(repo.id.clone(), OperationKind::Pull);
+        let result = update_to_entry(git_ops, &repo, &path, entry, &task).await;
+        task.finish();
+        let error = record_operation(db, config, operation, &result).await?;
+        if result.is_err() {
+            return Err(anyhow::anyhow!(error.unwrap_or_default()));
+        }
+        repo.update_pulled_at();
+        db.update_repository(&repo).await?;
+    }
+    update_checkout(git_ops, &repo, &path, reporter).await;
+    Ok(())
+}
+
+/// Update an existing clone to the branch or commit a manifest entry asks for
+async fn update_to_entry(
+    git_ops: &GitOperations,
+    repo: &Repository,
+    path: &str,
+    entry: &ManifestEntry,
+    task: &TaskProgress,
+) -> anyhow::Result<()> {
+    if let Some(revision) = &entry.revision {
+        git_ops
+            .backend()
+            .fetch(Path::new(path), "origin", Some(task.callback()))
+            .await?;
+        return git_ops.checkout_revision(path, revision).await;
+    }
+    if let Some(branch) = &entry.branch {
+        let status = git_ops.backend().status(Path::new(path)).await?;
+        if status.branch.as_deref() != Some(branch.as_str()) {
+            git_ops.switch_branch(path, branch).await?;
+        }
+    }
+    git_ops
+        .pull_repository_with_progress(path, &repo.clone_options(), Some(task.callback()))
+        .await
+}
+
+/// Origin URL of the git repository holding a `repo` manifest, either the
+/// manifest's directory or the `manifests` checkout next to `.repo/manifest.xml`
+fn manifest_repository_url(config: &Config, manifest: &Path) -> anyhow::Result<Option<String>> {
+    let git_ops = git_operations(config)?;
+    let dir = manifest.parent().unwrap_or(Path::new("."));
+    for dir in [dir.to_path_buf(), dir.join("manifests")] {
+        if !dir.join(".git").exists() {
+            continue;
+        }
+        if let Ok(Some(url)) = git_ops.get_remote_url(&dir.to_string_lossy(), "origin") {
+            return Ok(Some(url));
+        }
+    }
+    Ok(None)
+}
+
+/// Register the repositories another tool lists and, with `--clone`, clone the
+/// ones not on disk yet to where that tool keeps them
+async fn import_entries(
+    db: &RepositoryDatabase,
+    config: &Config,
+    entries: Vec<ImportEntry>,
+    args: &ImportArgs,
+) -> anyhow::Result<()> {
+    let provider = args
+        .provider
+        .as_deref()
+        .map(str::parse::<Provider>)
+        .transpose()?;
+    let gitlab_host = config.gitlab_base_url.as_deref().and_then(host_of);
+    println!("📦 Found {} repositories", entries.len());
+
+    let cwd = std::env::current_dir()?;
+    let mut imported = Vec::with_capacity(entries.len());
+    for mut entry in entries {
+        entry.path = cwd.join(&entry.path);
+        match entry.to_repository(provider.clone(), gitlab_host.as_deref()) {
+            Ok(repo) => imported.push((entry, repo)),
+            Err(e) => eprintln!("   ⚠️  Skipping {}: {}", redact(config, &entry.url), e),
+        }
+    }
+
+    if args.dry_run {
+        for (entry, repo) in &imported {
+            let action = match (&repo.local_path, args.clone) {
+                (Some(_), _) => "already cloned",
+                (None, true) => "clone",
+                (None, false) => "not cloned",
+            };
+            println!(
+                "   {} -> {} ({})",
+                repo.full_name,
+                entry.path.display(),
+                action
+            );
+        }
+        println!("Dry run: nothing was changed");
+        return Ok(());
+    }
+
+    let repos: Vec<Repository> = imported.iter().map(|(_, repo)| repo.clone()).collect();
+    let saved = save_repositories(db, &repos).await?;
+    let mut missing = Vec::new();
+    for ((entry, repo), mut saved) in imported.into_iter().zip(saved) {
+        match &repo.local_path {
+            Some(path) => {
+                saved.set_local_path(path.clone());
+                saved.update_status(CloneStatus::Cloned);
+                db.update_repository(&saved).await?;
+                println!("   ✅ {} -> {}", saved.full_name, path);
+            }
+            None => {
+                println!("   📝 {} (not cloned)", saved.full_name);
+                // A clone made elsewhere, e.g. by super-clone itself, is kept
+                if saved.status != CloneStatus::Cloned.to_string() {
+                    missing.push((entry, saved));
+                }
+            }
+        }
+    }
+    println!("✨ Imported {} repositories", repos.len());
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !args.clone {
+        println!(
+            "💡 {} repositories are not cloned yet; import again with --clone to clone them",
+            missing.len()
+        );
+        return Ok(());
+    }
+
+    let git_ops = git_operations(config)?;
+    let reporter = ProgressReporter::new(missing.len(), "Cloning");
+    let mut failed = 0;
+    for (entry, repo) in &missing {
+        if config.run_limits.cancel.is_cancelled() {
+            break;
+        }
+        let manifest_entry = ManifestEntry {
+            name: repo.full_name.clone(),
+            path: Some(entry.path.to_string_lossy().to_string()),
+            branch: entry.branch(),
+            revision: entry.commit(),
+            depth: config.clone_options.depth,
+            filter: config.clone_options.filter.clone(),
+            single_branch: config.clone_options.single_branch,
+            ..Default::default()
+        };
+        if let Err(e) = apply_entry(db, config, &git_ops, &reporter, &manifest_entry, false).await {
+            failed += 1;
+            reporter.eprintln(format!(
+                "   ❌ {}: {}",
+                repo.full_name,
+                redact(config, &e.to_string())
+            ));
+        }
+    }
+    reporter.finish();
+    if failed > 0 {
+        return Err(anyhow::anyhow!(
+            "{} of {} repositories could not be cloned",
+            failed,
+            missing.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Upstream state of every cloned repository, by record ID; gists and wikis
+/// are not looked up
+async fn upstream_states(
+    config: &Config,
+    repos: &[Repository],
+) -> anyhow::Result<HashMap<String, UpstreamState>> {
+    let mut github = None;
+    let mut gitlab = None;
+    let mut states = HashMap::new();
+    for repo in repos {
+        if repo.status != CloneStatus::Cloned.to_string()
+            || repo.kind != RepositoryKind::Repository.to_string()
+        {
+            continue;
+        }
+        let state = match repo.provider.parse::<Provider>()? {
+            Provider::GitHub => {
+                if github.is_none() {
+                    github = Some(GitHubClient::new(config.github_token.clone())?);
+                }
+                let client = github.as_ref().expect("client was just created");
+                client.repository_state(repo).await
+            }
+            Provider::GitLab => {
+                if gitlab.is_none() {
+                    gitlab = Some(GitLabClient::new(
+                        config.gitlab_token.clone(),
+                        config.gitlab_base_url.clone(),
+                    )?);
+                }
+                let client = gitlab.as_ref().expect("client was just created");
+                client.repository_state(repo).await
+            }
+        };
+        match state {
+            Ok(state) => {
+                states.insert(repo.id.clone(), state);
+            }
+            Err(e) => eprintln!("⚠️  Could not check {}: {}", repo.full_name, e),
+        }
+    }
+    Ok(states)
+}
+
+/// Refuse to continue a job another process is still running; jobs of dead
+/// runs are marked interrupted by the next run that has the database to itself
+fn ensure_not_running(job: &Job) -> anyhow::Result<()> {
+    if job.status == JobStatus::Running.to_string() {
+        return Err(anyhow::anyhow!(
+            "Job {} is still running in another super-clone process; if that process \
+             died, resume the job once no other super-clone is running",
+            job.short_id()
+        ));
+    }
+    Ok(())
+}
+
+/// Look up a discovered repository by full name
+async fn find_repository(db: &RepositoryDatabase, full_name: &str) -> anyhow::Result<Repository> {
+    db.get_repository_by_full_name(full_name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Repository not found: {}", full_name))
+}
+
+/// Store newly discovered repositories and return their database records
+async fn save_repositories(
+    db: &RepositoryDatabase,
+    repos: &[Repository],
+) -> anyhow::Result<Vec<Repository>> {
+    let mut saved = Vec::with_capacity(repos.len());
+    for repo in repos {
+        match db.get_repository_by_full_name(&repo.full_name).await? {
+            Some(mut existing) => {
+                // Wiki availability, description and topics can change after
+                // the first discovery; keep them current for search
+                let mut changed = false;
+                if repo.has_wiki && !existing.has_wiki {
+                    existing.has_wiki = true;
+                    changed = true;
+                }
+                if repo.description.is_some() && repo.description != existing.description {
+                    existing.description = repo.description.clone();
+                    changed = true;
+                }
+                if repo.topics.is_some() && repo.topics != existing.topics {
+                    existing.topics = repo.topics.clone();
+                    changed = true;
+                }
+                // A repository found again by starring or watching keeps its
+                // earlier sources too
+                for source in repo.sources() {
+                    changed |= existing.add_source(source);
+                }
+                if changed {
+                    existing.touch_metadata();
+                    db.update_repository(&existing).await?;
+                }
+                saved.push(existing)
+            }
+            None => {
+                db.create_repository(repo).await?;
+                saved.push(repo.clone());
+            }
+        }
+    }
+    db.apply_tag_rules(&saved).await?;
+    Ok(saved)
+}
+
+/// Clone repositories one by one as a resumable job
+async fn clone_repositories(
+    db: &RepositoryDatabase,
+    config: &Config,
+    description: &str,
+    repos: &[Repository],
+) -> anyhow::Result<()> {
+    let git_ops = git_operations(config)?;
+    let job = Job::new(JobKind::Clone, description.to_string());
+    let mut seen = BTreeSet::new();
+    let mut tasks = Vec::with_capacity(repos.len());
+    for repo in repos {
+        if !seen.insert(repo.id.clone()) {
+            continue;
+        }
+        // The configured mode only applies to clones made now, not existing ones;
+        // it is recorded up front so that a resumed job clones the same way
+        if !config.clone_options.is_full() && !git_ops.get_repo_path(repo).exists() {
+            let mut repo = repo.clone();
+            repo.set_clone_options(&config.clone_options);
+            db.update_repository(&repo).await?;
+        }
+        tasks.push(JobTask::new(
+            job.id.clone(),
+            repo.id.clone(),
+            tasks.len() as u32,
+        ));
+    }
+    if tasks.is_empty() {
+        return Ok(());
+    }
+    db.create_job(&job, &tasks).await?;
+    run_job(db, config, job, tasks, Retry::once()).await
+}
+
+/// How often a failing task is attempted within one run
+#[derive(Debug, Clone, Copy)]
+struct Retry {
+    attempts: u32,
+    /// Wait before the second attempt, doubled for each one after it
+    backoff: Duration,
+}
+
+impl Retry {
+    fn once() -> Self {
+        Self {
+            attempts: 1,
+            backoff: Duration::ZERO,
+        }
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        self.backoff * 2u32.saturating_pow(attempt.saturating_sub(1))
+    }
+}
+
+/// Result of cloning or pulling one repository
+enum Outcome {
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+/// Run the given tasks of a job, recording each outcome as soon as it is known
+async fn run_job(
+    db: &RepositoryDatabase,
+    config: &Config,
+    mut job: Job,
+    tasks: Vec<JobTask>,
+    retry: Retry,
+) -> anyhow::Result<()> {
+    let kind: JobKind = job.kind.parse()?;
+    let git_ops = Arc::new(git_operations(config)?);
+    job.update_status(JobStatus::Running);
+    db.update_job(&job).await?;
+    println!("📋 Job {}: {}", job.short_id(), job.description);
+
+    let action = match kind {
+        JobKind::Clone => "Cloning",
+        JobKind::Pull => "Pulling",
+    };
+    let reporter = Arc::new(ProgressReporter::new(tasks.len(), action));
+    let config = Arc::new(config.clone());
+    // Each worker draws its own progress line while its repository is in flight
+    let slots = Arc::new(Semaphore::new(config.parallel.max(1)));
+    let mut running = JoinSet::new();
+    let mut interrupted = false;
+    for task in tasks {
+        let permit = slots.clone().acquire_owned().await?;
+        if config.run_limits.cancel.is_cancelled() {
+            interrupted = true;
+            break;
+        }
+        let (db, config, git_ops, reporter) = (
+            db.clone(),
+            config.clone(),
+            git_ops.clone(),
+            reporter.clone(),
+        );
+        running.spawn(async move {
+            let result =
+                run_task(&db, &config, &git_ops, &reporter, kind, retry, task.clone()).await;
+            drop(permit);
+            (task, result)
+        });
+    }
+
+    // Every worker is waited for, so the job is always finished below and no
+    // clone is aborted halfway by dropping the set
+    let mut first_error: Option<anyhow::Error> = None;
+    while let Some(joined) = running.join_next().await {
+        let error = match joined {
+            Ok((_, Ok(cancelled))) => {
+                interrupted |= cancelled;
+                continue;
+            }
+            Ok((mut task, Err(e))) => {
+                task.finish(TaskStatus::Failed, Some(e.to_string()));
+                if let Err(e) = db.update_job_task(&task).await {
+                    reporter.println(format!("   ⚠️  Could not record a failed task: {}", e));
+                }
+                e
+            }
+            Err(e) => anyhow::anyhow!("Worker failed: {}", e),
+        };
+        reporter.println(format!("   ❌ {}", error));
+        first_error.get_or_insert(error);
+    }
+    reporter.finish();
+
+    let tasks = db.get_job_tasks(&job.id).await?;
+    let failed = tasks
+        .iter()
+        .filter(|t| t.status == TaskStatus::Failed.to_string())
+        .count();
+    job.update_status(if interrupted {
+        JobStatus::Interrupted
+    } else if failed > 0 || first_error.is_some() {
+        JobStatus::Failed
+    } else {
+        JobStatus::Completed
+    });
+    db.update_job(&job).await?;
+    if let Some(error) = first_error {
+        return Err(error);
     }
 
+    if interrupted {
+        println!(
+            "⏹️  Job {} interrupted, continue it with: super-clone jobs resume {}",
+            job.short_id(),
+            job.short_id()
+        );
+    } else if failed > 0 {
+        println!(
+            "⚠️  {} of {} repositories failed, retry them with: super-clone jobs retry-failed --job {}",
+            failed,
+            tasks.len(),
+            job.short_id()
+        );
+    }
     Ok(())
 }
+
+/// Clone or pull the repository of one job task, retrying failures; returns
+/// whether the user cancelled it
+async fn run_task(
+    db: &RepositoryDatabase,
+    config: &Config,
+    git_ops: &GitOperations,
+    reporter: &ProgressReporter,
+    kind: JobKind,
+    retry: Retry,
+    mut task: JobTask,
+) -> anyhow::Result<bool> {
+    let Some(repo) = db.get_repository(&task.repository_id).await? else {
+        task.finish(
+            TaskStatus::Failed,
+            Some("repository is no longer tracked".to_string()),
+        );
+        db.update_job_task(&task).await?;
+        return Ok(false);
+    };
+
+    let mut attempt = 1;
+    let outcome = loop {
+        task.start();
+        db.update_job_task(&task).await?;
+        let outcome = match kind {
+            JobKind::Clone => clone_one(db, config, git_ops, reporter, &repo).await?,
+            JobKind::Pull => pull_one(db, config, git_ops, reporter, &repo).await?,
+        };
+        match outcome {
+            Outcome::Failed(error) if attempt < retry.attempts => {
+                let delay = retry.delay(attempt);
+                reporter.println(format!(
+                    "   🔁 Retrying {} in {}s",
+                    repo.full_name,
+                    delay.as_secs()
+                ));
+                task.finish(TaskStatus::Failed, Some(error));
+                db.update_job_task(&task).await?;
+                if !wait_unless_cancelled(delay, &config.run_limits.cancel).await {
+                    break Outcome::Cancelled;
+                }
+                attempt += 1;
+            }
+            outcome => break outcome,
+        }
+    };
+
+    let cancelled = matches!(outcome, Outcome::Cancelled);
+    match outcome {
+        Outcome::Done => task.finish(TaskStatus::Done, None),
+        Outcome::Failed(error) => task.finish(TaskStatus::Failed, Some(error)),
+        Outcome::Cancelled => task.finish(TaskStatus::Pending, None),
+    }
+    db.update_job_task(&task).await?;
+    Ok(cancelled)
+}
+
+/// Wait for `delay`, returning false as soon as the user cancels
+async fn wait_unless_cancelled(delay: Duration, cancel: &CancelFlag) -> bool {
+    let deadline = tokio::time::Instant::now() + delay;
+    loop {
+        if cancel.is_cancelled() {
+            return false;
+        }
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        tokio::time::sleep((deadline - now).min(Duration::from_millis(100))).await;
+    }
+}
+
+/// Clone one repository, recording the outcome in the database
+async fn clone_one(
+    db: &RepositoryDatabase,
+    config: &Config,
+    git_ops: &GitOperations,
+    reporter: &ProgressReporter,
+    repo: &Repository,
+) -> anyhow::Result<Outcome> {
+    let mut repo = repo.clone();
+    reporter.println(format!("⬇️  Cloning: {}", repo.full_name));
+    let previous_status = repo.status.clone();
+    repo.update_status(CloneStatus::Cloning);
+    db.update_repository(&repo).await?;
+
+    let task = reporter.task(&repo.full_name);
+    let operation = Operation::start(repo.id.clone(), OperationKind::Clone);
+    let result = git_ops
+        .clone_repository_with_progress(&repo, config.use_ssh, Some(task.callback()))
+        .await;
+    task.finish();
+    let error = record_operation(db, config, operation, &result).await?;
+    match result {
+        Err(e) if is_cancelled(&e) => {
+            repo.status = previous_status;
+            db.update_repository(&repo).await?;
+            reporter.eprintln(format!("   ⏹️  {}: cancelled", repo.full_name));
+            Ok(Outcome::Cancelled)
+        }
+        Ok(path) => {
+            repo.set_local_path(path.clone());
+            repo.update_status(CloneStatus::Cloned);
+            db.update_repository(&repo).await?;
+            reporter.println(format!("   ✅ {} cloned to: {}", repo.full_name, path));
+            update_checkout(git_ops, &repo, &path, reporter).await;
+            Ok(Outcome::Done)
+        }
+        Err(_) => {
+            let error = error.unwrap_or_default();
+            repo.update_status(CloneStatus::Error);
+            db.update_repository(&repo).await?;
+            reporter.eprintln(format!("   ❌ {} failed: {}", repo.full_name, error));
+            Ok(Outcome::Failed(error))
+        }
+    }
+}
+
+/// Pull one cloned repository, recording the outcome in the database
+async fn pull_one(
+    db: &RepositoryDatabase,
+    config: &Config,
+    git_ops: &GitOperations,
+    reporter: &ProgressReporter,
+    repo: &Repository,
+) -> anyhow::Result<Outcome> {
+    let Some(path) = &repo.local_path else {
+        return Ok(Outcome::Failed("repository is not cloned".to_string()));
+    };
+    let mut updated_repo = repo.clone();
+    updated_repo.update_status(CloneStatus::Updating);
+    db.update_repository(&updated_repo).await?;
+
+    let task = reporter.task(&repo.full_name);
+    let operation = Operation::start(repo.id.clone(), OperationKind::Pull);
+    let result = git_ops
+        .pull_repository_with_progress(path, &repo.clone_options(), Some(task.callback()))
+        .await;
+    task.finish();
+    let error = record_operation(db, config, operation, &result).await?;
+    updated_repo.update_status(CloneStatus::Cloned);
+    match result {
+        Err(e) if is_cancelled(&e) => {
+            db.update_repository(&updated_repo).await?;
+            reporter.eprintln(format!("⬇️  Pulling: {} ... ⏹️  Cancelled", repo.full_name));
+            Ok(Outcome::Cancelled)
+        }
+        Ok(_) => {
+            updated_repo.update_pulled_at();
+            db.update_repository(&updated_repo).await?;
+            reporter.println(format!("⬇️  Pulling: {} ... ✅", repo.full_name));
+            update_checkout(git_ops, repo, path, reporter).await;
+            Ok(Outcome::Done)
+        }
+        Err(_) => {
+            let error = error.unwrap_or_default();
+            db.update_repository(&updated_repo).await?;
+            reporter.println(format!(
+                "⬇️  Pulling: {} ... ❌ Failed: {}",
+                repo.full_name, error
+            ));
+            Ok(Outcome::Failed(error))
+        }
+    }
+}
+
+/// Store a finished clone or pull in the repository's history and return the
+/// redacted error message when it failed
+async fn record_operation<T>(
+    db: &RepositoryDatabase,
+    config: &Config,
+    mut operation: Operation,
+    result: &anyhow::Result<T>,
+) -> anyhow::Result<Option<String>> {
+    let error = match result {
+        Ok(_) => {
+            operation.finish(OperationOutcome::Success, Some(0), None);
+            None
+        }
+        Err(e) => {
+            let outcome = if is_cancelled(e) {
+                OperationOutcome::Cancelled
+            } else {
+                OperationOutcome::Failed
+            };
+            let exit_code = e.downcast_ref::<GitError>().and_then(GitError::exit_code);
+            let error = redact(config, &format!("{:#}", e));
+            operation.finish(outcome, exit_code, Some(error.clone()));
+            Some(error)
+        }
+    };
+    db.create_operation(&operation).await?;
+    Ok(error)
+}
+
+/// Remove access tokens from text that is printed or stored
+fn redact(config: &Config, text: &str) -> String {
+    let mut redacted = redact_credentials(text);
+    for token in [&config.github_token, &config.gitlab_token]
+        .into_iter()
+        .flatten()
+        .filter(|token| !token.is_empty())
+    {
+        redacted = redacted.replace(token.as_str(), "***");
+    }
+    redacted
+}
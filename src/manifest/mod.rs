@@ -0,0 +1,322 @@
+use crate::models::{normalize_tag, CloneOptions, Provider, Repository};
+use crate::remote::RemoteUrl;
+use crate::Result;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// File name `apply` reads by default
+pub const MANIFEST_FILE: &str = "super-clone.toml";
+
+/// Repositories a workspace should contain, as checked in to `super-clone.toml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default, rename = "repository")]
+    pub repositories: Vec<ManifestEntry>,
+}
+
+/// One repository of the manifest with where and how it is cloned
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestEntry {
+    /// Full name, e.g. `owner/name`
+    pub name: String,
+    /// Clone URL; without one the repository has to be discovered already
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// `github` or `gitlab`; guessed from the URL host when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Clone directory, relative to the clone path unless absolute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Branch to check out instead of the default branch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Commit to check out with a detached HEAD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub single_branch: bool,
+    /// Directories for a cone-mode sparse checkout
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sparse: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Manifest {
+    /// Parse and validate a manifest
+    pub fn parse(content: &str) -> Result<Self> {
+        let manifest: Manifest = toml::from_str(content)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Read and validate a manifest file
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid manifest {}", path.display()))
+    }
+
+    /// Manifest as TOML text
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Write the manifest to a file
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_toml()?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Whether the manifest lists a repository
+    pub fn contains(&self, full_name: &str) -> bool {
+        self.repositories
+            .iter()
+            .any(|entry| entry.name == full_name)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for entry in &self.repositories {
+            if !entry.name.contains('/') || entry.name.starts_with('/') || entry.name.ends_with('/')
+            {
+                return Err(anyhow::anyhow!(
+                    "Repository name must be a full name like owner/name: {}",
+                    entry.name
+                ));
+            }
+            if !seen.insert(entry.name.as_str()) {
+                return Err(anyhow::anyhow!("Repository listed twice: {}", entry.name));
+            }
+            entry
+                .clone_options()
+                .with_context(|| format!("Invalid clone options for {}", entry.name))?;
+            for tag in &entry.tags {
+                normalize_tag(tag)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ManifestEntry {
+    /// Clone mode of the entry, including the branch to check out
+    pub fn clone_options(&self) -> Result<CloneOptions> {
+        let mut options = CloneOptions::new(self.depth, self.filter.clone(), self.single_branch)?;
+        options.branch = self.branch.clone();
+        Ok(options)
+    }
+
+    /// Provider of the entry, given explicitly or guessed from the URL host
+    pub fn provider(&self, gitlab_host: Option<&str>) -> Result<Provider> {
+        if let Some(provider) = &self.provider {
+            return provider.parse();
+        }
+        self.url
+            .as_deref()
+            .and_then(|url| RemoteUrl::parse(url).ok())
+            .and_then(|remote| remote.provider(gitlab_host))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Cannot tell the provider of {}; set provider = \"github\" or \"gitlab\"",
+                    self.name
+                )
+            })
+    }
+
+    /// Build a repository record from the entry's URL, without any provider API
+    pub fn to_repository(&self, gitlab_host: Option<&str>) -> Result<Repository> {
+        let url = self.url.clone().ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} is not discovered yet; give it a url in the manifest",
+                self.name
+            )
+        })?;
+        let (owner, name) = self
+            .name
+            .rsplit_once('/')
+            .ok_or_else(|| anyhow::anyhow!("Invalid repository name: {}", self.name))?;
+        // The URL goes in the field of its scheme, the other one points at the
+        // same repository where the URL allows it
+        let remote = RemoteUrl::parse(&url).ok();
+        let (https, ssh) = match (is_ssh_url(&url), remote) {
+            (true, Some(remote)) => (remote.https_url(), url),
+            (false, Some(remote)) => (url, remote.ssh_url()),
+            (_, None) => (url.clone(), url),
+        };
+        Ok(Repository::new(
+            name.to_string(),
+            self.name.clone(),
+            owner.to_string(),
+            self.provider(gitlab_host)?,
+            https,
+            ssh,
+            None,
+            false,
+        ))
+    }
+
+    /// Copy of `repo` that clones from the entry's URL, and whether that URL is SSH.
+    ///
+    /// The record of a known repository keeps the URLs its provider reported; the
+    /// entry's URL is only used for the clone itself.
+    pub fn clone_source(&self, repo: &Repository) -> Option<(Repository, bool)> {
+        let url = self.url.as_ref()?;
+        let mut source = repo.clone();
+        let ssh = is_ssh_url(url);
+        if ssh {
+            source.clone_url_ssh = url.clone();
+        } else {
+            source.clone_url_https = url.clone();
+        }
+        Some((source, ssh))
+    }
+
+    /// Clone directory of the entry below `clone_root`, when the entry sets one
+    pub fn target_path(&self, clone_root: &Path) -> Option<PathBuf> {
+        self.path.as_ref().map(|path| clone_root.join(path))
+    }
+}
+
+/// Whether a clone URL uses SSH (`ssh://` or scp-like `git@host:path`)
+fn is_ssh_url(url: &str) -> bool {
+    url.starts_with("ssh://") || (!url.contains("://") && RemoteUrl::parse(url).is_ok())
+}
+
+/// Path as written to a manifest: relative when below `clone_root`, absolute otherwise
+pub fn manifest_path(path: &Path, clone_root: &Path) -> String {
+    path.strip_prefix(clone_root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+[[repository]]
+name = "acme/ledger"
+path = "payments/ledger"
+branch = "release"
+depth = 1
+tags = ["payments"]
+
+[[repository]]
+name = "tools/linter"
+url = "https://git.example.com/tools/linter.git"
+provider = "gitlab"
+revision = "0123456789abcdef0123456789abcdef01234567"
+sparse = ["rules"]
+"#;
+
+    #[test]
+    fn test_parse_and_write_manifest() {
+        let manifest = Manifest::parse(MANIFEST).unwrap();
+        assert_eq!(manifest.repositories.len(), 2);
+        assert!(manifest.contains("tools/linter"));
+
+        let ledger = &manifest.repositories[0];
+        let options = ledger.clone_options().unwrap();
+        assert_eq!(options.depth, Some(1));
+        assert_eq!(options.branch.as_deref(), Some("release"));
+        assert_eq!(
+            ledger.target_path(Path::new("/work")),
+            Some(PathBuf::from("/work/payments/ledger"))
+        );
+        assert!(ledger.to_repository(None).is_err());
+
+        let linter = manifest.repositories[1].to_repository(None).unwrap();
+        assert_eq!(linter.owner, "tools");
+        assert_eq!(linter.name, "linter");
+        assert_eq!(linter.provider, "gitlab");
+        assert_eq!(
+            linter.clone_url_https,
+            "https://git.example.com/tools/linter.git"
+        );
+        assert_eq!(linter.clone_url_ssh, "git@git.example.com:tools/linter.git");
+
+        let written = manifest.to_toml().unwrap();
+        assert!(!written.contains("single_branch"));
+        assert_eq!(Manifest::parse(&written).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_clone_source_keeps_the_record() {
+        let repo = Repository::new(
+            "linter".to_string(),
+            "tools/linter".to_string(),
+            "tools".to_string(),
+            Provider::GitHub,
+            "https://github.com/tools/linter.git".to_string(),
+            "git@github.com:tools/linter.git".to_string(),
+            None,
+            true,
+        );
+        let mut entry = ManifestEntry {
+            name: "tools/linter".to_string(),
+            ..Default::default()
+        };
+        assert!(entry.clone_source(&repo).is_none());
+
+        entry.url = Some("https://mirror.example.com/tools/linter.git".to_string());
+        let (source, ssh) = entry.clone_source(&repo).unwrap();
+        assert!(!ssh);
+        assert_eq!(
+            source.clone_url_https,
+            "https://mirror.example.com/tools/linter.git"
+        );
+        assert_eq!(source.clone_url_ssh, repo.clone_url_ssh);
+
+        entry.url = Some("git@mirror.example.com:tools/linter.git".to_string());
+        let (source, ssh) = entry.clone_source(&repo).unwrap();
+        assert!(ssh);
+        assert_eq!(source.clone_url_https, repo.clone_url_https);
+        assert_eq!(
+            source.clone_url_ssh,
+            "git@mirror.example.com:tools/linter.git"
+        );
+        assert!(!is_ssh_url("/srv/git/linter.git"));
+        assert!(!is_ssh_url("file:///srv/git/linter.git"));
+    }
+
+    #[test]
+    fn test_invalid_manifests() {
+        let twice = "[[repository]]\nname = \"a/b\"\n[[repository]]\nname = \"a/b\"\n";
+        assert!(Manifest::parse(twice).is_err());
+        assert!(Manifest::parse("[[repository]]\nname = \"b\"\n").is_err());
+        assert!(Manifest::parse("[[repository]]\nname = \"a/b\"\nfilter = \"x\"\n").is_err());
+        assert!(Manifest::parse("[[repository]]\nname = \"a/b\"\nbrnch = \"x\"\n").is_err());
+
+        let unknown_host = ManifestEntry {
+            name: "a/b".to_string(),
+            url: Some("https://git.example.com/a/b.git".to_string()),
+            ..Default::default()
+        };
+        assert!(unknown_host.provider(None).is_err());
+        assert_eq!(
+            unknown_host.provider(Some("git.example.com")).unwrap(),
+            Provider::GitLab
+        );
+    }
+
+    #[test]
+    fn test_manifest_path() {
+        let root = Path::new("/work");
+        assert_eq!(manifest_path(Path::new("/work/a/b"), root), "a/b");
+        assert_eq!(
+            manifest_path(Path::new("/elsewhere/b"), root),
+            "/elsewhere/b"
+        );
+    }
+}
@@ -56,6 +56,172 @@ impl std::fmt::Display for CloneStatus {
     }
 }
 
+/// How a repository came to be tracked
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum RepositorySource {
+    /// Discovered from a user, organization or group listing
+    Discovered,
+    /// Starred by a user
+    Starred,
+    /// Watched by a user
+    Watched,
+}
+
+impl std::fmt::Display for RepositorySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepositorySource::Discovered => write!(f, "discovered"),
+            RepositorySource::Starred => write!(f, "starred"),
+            RepositorySource::Watched => write!(f, "watched"),
+        }
+    }
+}
+
+impl std::str::FromStr for RepositorySource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "discovered" => Ok(RepositorySource::Discovered),
+            "starred" => Ok(RepositorySource::Starred),
+            "watched" => Ok(RepositorySource::Watched),
+            _ => Err(anyhow::anyhow!("Invalid repository source: {}", s)),
+        }
+    }
+}
+
+/// What kind of git repository a record points at
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RepositoryKind {
+    /// A regular project repository
+    Repository,
+    /// A GitHub gist
+    Gist,
+    /// A project wiki, linked to its parent repository
+    Wiki,
+}
+
+impl std::fmt::Display for RepositoryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepositoryKind::Repository => write!(f, "repository"),
+            RepositoryKind::Gist => write!(f, "gist"),
+            RepositoryKind::Wiki => write!(f, "wiki"),
+        }
+    }
+}
+
+impl std::str::FromStr for RepositoryKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "repository" => Ok(RepositoryKind::Repository),
+            "gist" => Ok(RepositoryKind::Gist),
+            "wiki" => Ok(RepositoryKind::Wiki),
+            _ => Err(anyhow::anyhow!("Invalid repository kind: {}", s)),
+        }
+    }
+}
+
+/// How much history and which objects a clone fetches
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloneOptions {
+    /// Shallow clone with this many commits of history (`--depth`)
+    pub depth: Option<u32>,
+    /// Partial clone filter (`--filter`), e.g. `blob:none` or `tree:0`
+    pub filter: Option<String>,
+    /// Only fetch the default branch (`--single-branch`)
+    pub single_branch: bool,
+    /// Check out this branch instead of the remote's default (`--branch`);
+    /// not recorded per repository
+    pub branch: Option<String>,
+}
+
+impl CloneOptions {
+    /// Create clone options, validating the partial clone filter
+    pub fn new(
+        depth: Option<u32>,
+        filter: Option<String>,
+        single_branch: bool,
+    ) -> anyhow::Result<Self> {
+        if depth == Some(0) {
+            return Err(anyhow::anyhow!("Clone depth must be at least 1"));
+        }
+        if let Some(filter) = &filter {
+            let valid = filter == "blob:none"
+                || filter == "tree:0"
+                || filter
+                    .strip_prefix("blob:limit=")
+                    .is_some_and(|limit| !limit.is_empty());
+            if !valid {
+                return Err(anyhow::anyhow!(
+                    "Invalid clone filter: {} (expected blob:none, tree:0 or blob:limit=<size>)",
+                    filter
+                ));
+            }
+        }
+        Ok(Self {
+            depth,
+            filter,
+            single_branch,
+            branch: None,
+        })
+    }
+
+    /// Whether this is a regular clone with full history and all objects
+    pub fn is_full(&self) -> bool {
+        self.depth.is_none() && self.filter.is_none() && !self.single_branch
+    }
+}
+
+/// How Git LFS content is handled after clone and pull
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LfsMode {
+    /// Pull LFS objects when the repository uses LFS and git-lfs is installed
+    #[default]
+    Auto,
+    /// Always pull LFS objects, failing when git-lfs is missing
+    Pull,
+    /// Leave LFS pointer files in the working tree
+    Skip,
+}
+
+impl std::fmt::Display for LfsMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LfsMode::Auto => write!(f, "auto"),
+            LfsMode::Pull => write!(f, "pull"),
+            LfsMode::Skip => write!(f, "skip"),
+        }
+    }
+}
+
+impl std::str::FromStr for LfsMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(LfsMode::Auto),
+            "pull" => Ok(LfsMode::Pull),
+            "skip" => Ok(LfsMode::Skip),
+            _ => Err(anyhow::anyhow!("Invalid LFS mode: {}", s)),
+        }
+    }
+}
+
+/// What to do with submodules and LFS content once a clone or pull is done
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckoutOptions {
+    /// Recursively init and update submodules
+    pub submodules: bool,
+    /// Git LFS handling
+    pub lfs: LfsMode,
+}
+
 /// Repository model
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Repository {
@@ -70,7 +236,23 @@ pub struct Repository {
     pub is_private: bool,
     pub local_path: Option<String>,
     pub status: String,
+    /// Comma separated sources the repository was found through
+    pub source: String,
+    pub kind: String,
+    pub has_wiki: bool,
+    pub parent_id: Option<String>,
+    pub clone_depth: Option<u32>,
+    pub clone_filter: Option<String>,
+    pub single_branch: bool,
+    /// Newline separated cone-mode sparse checkout directories
+    pub sparse_paths: Option<String>,
+    /// Newline separated provider topics
+    pub topics: Option<String>,
     pub last_pulled_at: Option<DateTime<Utc>>,
+    /// When discovery last changed the provider metadata; unlike `updated_at`
+    /// local clones and pulls leave it alone
+    #[serde(default)]
+    pub metadata_updated_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -101,18 +283,176 @@ impl Repository {
             is_private,
             local_path: None,
             status: CloneStatus::NotCloned.to_string(),
+            source: RepositorySource::Discovered.to_string(),
+            kind: RepositoryKind::Repository.to_string(),
+            has_wiki: false,
+            parent_id: None,
+            clone_depth: None,
+            clone_filter: None,
+            single_branch: false,
+            sparse_paths: None,
+            topics: None,
             last_pulled_at: None,
+            metadata_updated_at: Some(now),
             created_at: now,
             updated_at: now,
         }
     }
 
+    /// When the provider metadata was last changed, for records that predate
+    /// `metadata_updated_at` the last update of any kind
+    pub fn metadata_updated(&self) -> DateTime<Utc> {
+        self.metadata_updated_at.unwrap_or(self.updated_at)
+    }
+
+    /// Record that discovery changed the provider metadata
+    pub fn touch_metadata(&mut self) {
+        let now = Utc::now();
+        self.metadata_updated_at = Some(now);
+        self.updated_at = now;
+    }
+
     /// Update repository status
     pub fn update_status(&mut self, status: CloneStatus) {
         self.status = status.to_string();
         self.updated_at = Utc::now();
     }
 
+    /// Record how the repository was found
+    pub fn set_source(&mut self, source: RepositorySource) {
+        self.source = source.to_string();
+        self.updated_at = Utc::now();
+    }
+
+    /// Every source the repository was found through
+    pub fn sources(&self) -> Vec<RepositorySource> {
+        self.source
+            .split(',')
+            .filter_map(|source| source.trim().parse().ok())
+            .collect()
+    }
+
+    /// Whether the repository was found through `source`, among others
+    pub fn has_source(&self, source: &RepositorySource) -> bool {
+        self.sources().contains(source)
+    }
+
+    /// Add a source the repository was also found through; false if it was known
+    pub fn add_source(&mut self, source: RepositorySource) -> bool {
+        let mut sources = self.sources();
+        if sources.contains(&source) {
+            return false;
+        }
+        sources.push(source);
+        sources.sort();
+        self.source = sources
+            .iter()
+            .map(|source| source.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        self.updated_at = Utc::now();
+        true
+    }
+
+    /// Record what kind of git repository this is
+    pub fn set_kind(&mut self, kind: RepositoryKind) {
+        self.kind = kind.to_string();
+        self.updated_at = Utc::now();
+    }
+
+    /// Build the linked record for this repository's wiki
+    pub fn wiki_repository(&self) -> Repository {
+        let to_wiki_url = |url: &str| match url.strip_suffix(".git") {
+            Some(base) => format!("{}.wiki.git", base),
+            None => format!("{}.wiki.git", url),
+        };
+
+        let mut wiki = Repository::new(
+            format!("{}.wiki", self.name),
+            format!("{}.wiki", self.full_name),
+            self.owner.clone(),
+            self.provider.parse().unwrap_or(Provider::GitHub),
+            to_wiki_url(&self.clone_url_https),
+            to_wiki_url(&self.clone_url_ssh),
+            self.description.clone(),
+            self.is_private,
+        );
+        wiki.source = self.source.clone();
+        wiki.kind = RepositoryKind::Wiki.to_string();
+        wiki.parent_id = Some(self.id.clone());
+        wiki
+    }
+
+    /// Clone mode recorded for this repository
+    pub fn clone_options(&self) -> CloneOptions {
+        CloneOptions {
+            depth: self.clone_depth,
+            filter: self.clone_filter.clone(),
+            single_branch: self.single_branch,
+            branch: None,
+        }
+    }
+
+    /// Record the clone mode used for this repository
+    pub fn set_clone_options(&mut self, options: &CloneOptions) {
+        self.clone_depth = options.depth;
+        self.clone_filter = options.filter.clone();
+        self.single_branch = options.single_branch;
+        self.updated_at = Utc::now();
+    }
+
+    /// Directories checked out in cone-mode sparse checkout (empty for a full checkout)
+    pub fn sparse_paths(&self) -> Vec<String> {
+        self.sparse_paths
+            .as_deref()
+            .map(|paths| {
+                paths
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(|line| line.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Record the sparse checkout directories (empty for a full checkout)
+    pub fn set_sparse_paths(&mut self, paths: &[String]) {
+        let paths: Vec<&str> = paths
+            .iter()
+            .map(|p| p.trim().trim_matches('/'))
+            .filter(|p| !p.is_empty())
+            .collect();
+        self.sparse_paths = if paths.is_empty() {
+            None
+        } else {
+            Some(paths.join("\n"))
+        };
+        self.updated_at = Utc::now();
+    }
+
+    /// Topics the provider lists for this repository
+    pub fn topics(&self) -> Vec<String> {
+        self.topics
+            .as_deref()
+            .map(|topics| topics.lines().map(|t| t.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Record the provider topics
+    pub fn set_topics(&mut self, topics: &[String]) {
+        let topics: Vec<&str> = topics
+            .iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .collect();
+        self.topics = if topics.is_empty() {
+            None
+        } else {
+            Some(topics.join("\n"))
+        };
+        self.updated_at = Utc::now();
+    }
+
     /// Set local path after cloning
     pub fn set_local_path(&mut self, path: String) {
         self.local_path = Some(path);
@@ -126,6 +466,494 @@ impl Repository {
     }
 }
 
+/// Turn free text into a lowercase, dash separated name safe for paths
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= 50 {
+            break;
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Bare mirror of a repository kept for backups, separate from its working clone
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Mirror {
+    pub repository_id: String,
+    pub local_path: String,
+    pub include_pull_refs: bool,
+    pub status: String,
+    pub last_updated_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Mirror {
+    /// Create a new mirror record for a repository
+    pub fn new(repository_id: String, local_path: String, include_pull_refs: bool) -> Self {
+        let now = Utc::now();
+        Self {
+            repository_id,
+            local_path,
+            include_pull_refs,
+            status: CloneStatus::NotCloned.to_string(),
+            last_updated_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Update mirror status
+    pub fn update_status(&mut self, status: CloneStatus) {
+        self.status = status.to_string();
+        self.updated_at = Utc::now();
+    }
+
+    /// Update last mirrored timestamp
+    pub fn update_mirrored_at(&mut self) {
+        self.last_updated_at = Some(Utc::now());
+        self.updated_at = Utc::now();
+    }
+}
+
+/// A git bundle written for a repository by `export-bundles`
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BundleExport {
+    pub id: String,
+    pub repository_id: String,
+    pub bundle_path: String,
+    pub incremental: bool,
+    /// JSON object mapping ref names to the commits they pointed at
+    pub refs: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BundleExport {
+    /// Create a new export record
+    pub fn new(
+        repository_id: String,
+        bundle_path: String,
+        incremental: bool,
+        refs: &std::collections::BTreeMap<String, String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            repository_id,
+            bundle_path,
+            incremental,
+            refs: serde_json::to_string(refs).unwrap_or_else(|_| "{}".to_string()),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Refs and commits recorded at export time
+    pub fn refs(&self) -> std::collections::BTreeMap<String, String> {
+        serde_json::from_str(&self.refs).unwrap_or_default()
+    }
+}
+
+/// What a batch job does to each of its repositories
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    Clone,
+    Pull,
+}
+
+impl std::fmt::Display for JobKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobKind::Clone => write!(f, "clone"),
+            JobKind::Pull => write!(f, "pull"),
+        }
+    }
+}
+
+impl std::str::FromStr for JobKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "clone" => Ok(JobKind::Clone),
+            "pull" => Ok(JobKind::Pull),
+            _ => Err(anyhow::anyhow!("Invalid job kind: {}", s)),
+        }
+    }
+}
+
+/// State of a batch job as a whole
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    /// Every task finished successfully
+    Completed,
+    /// All tasks ran, some of them failed
+    Failed,
+    /// Stopped by Ctrl-C or a killed process before all tasks ran
+    Interrupted,
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobStatus::Running => write!(f, "running"),
+            JobStatus::Completed => write!(f, "completed"),
+            JobStatus::Failed => write!(f, "failed"),
+            JobStatus::Interrupted => write!(f, "interrupted"),
+        }
+    }
+}
+
+impl std::str::FromStr for JobStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "running" => Ok(JobStatus::Running),
+            "completed" => Ok(JobStatus::Completed),
+            "failed" => Ok(JobStatus::Failed),
+            "interrupted" => Ok(JobStatus::Interrupted),
+            _ => Err(anyhow::anyhow!("Invalid job status: {}", s)),
+        }
+    }
+}
+
+/// State of one repository within a batch job
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl std::fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskStatus::Pending => write!(f, "pending"),
+            TaskStatus::Running => write!(f, "running"),
+            TaskStatus::Done => write!(f, "done"),
+            TaskStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl std::str::FromStr for TaskStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pending" => Ok(TaskStatus::Pending),
+            "running" => Ok(TaskStatus::Running),
+            "done" => Ok(TaskStatus::Done),
+            "failed" => Ok(TaskStatus::Failed),
+            _ => Err(anyhow::anyhow!("Invalid task status: {}", s)),
+        }
+    }
+}
+
+/// A batch clone or pull run that can be resumed after it was interrupted
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Job {
+    pub id: String,
+    pub kind: String,
+    /// The command that started the job, e.g. `clone-org github acme`
+    pub description: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl Job {
+    /// Create a new running job
+    pub fn new(kind: JobKind, description: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            kind: kind.to_string(),
+            description,
+            status: JobStatus::Running.to_string(),
+            created_at: now,
+            updated_at: now,
+            finished_at: None,
+        }
+    }
+
+    /// Short form of the ID for display; any unique prefix finds the job
+    pub fn short_id(&self) -> &str {
+        &self.id[..8.min(self.id.len())]
+    }
+
+    /// Update job status, recording when it stopped running
+    pub fn update_status(&mut self, status: JobStatus) {
+        self.status = status.to_string();
+        self.updated_at = Utc::now();
+        self.finished_at = match status {
+            JobStatus::Running => None,
+            _ => Some(self.updated_at),
+        };
+    }
+}
+
+/// One repository of a batch job
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct JobTask {
+    pub job_id: String,
+    pub repository_id: String,
+    /// Order in which the job processes its tasks
+    pub position: u32,
+    pub status: String,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl JobTask {
+    /// Create a pending task
+    pub fn new(job_id: String, repository_id: String, position: u32) -> Self {
+        Self {
+            job_id,
+            repository_id,
+            position,
+            status: TaskStatus::Pending.to_string(),
+            attempts: 0,
+            last_error: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Mark the task as running and count the attempt
+    pub fn start(&mut self) {
+        self.status = TaskStatus::Running.to_string();
+        self.attempts += 1;
+        self.updated_at = Utc::now();
+    }
+
+    /// Record the outcome of the last attempt
+    pub fn finish(&mut self, status: TaskStatus, error: Option<String>) {
+        self.status = status.to_string();
+        if status != TaskStatus::Pending {
+            self.last_error = error;
+        }
+        self.updated_at = Utc::now();
+    }
+
+    /// Whether a resumed job still has to run this task
+    pub fn is_unfinished(&self) -> bool {
+        self.status != TaskStatus::Done.to_string()
+    }
+}
+
+/// Git operation recorded in a repository's history
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Clone,
+    Pull,
+}
+
+impl std::fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationKind::Clone => write!(f, "clone"),
+            OperationKind::Pull => write!(f, "pull"),
+        }
+    }
+}
+
+impl std::str::FromStr for OperationKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "clone" => Ok(OperationKind::Clone),
+            "pull" => Ok(OperationKind::Pull),
+            _ => Err(anyhow::anyhow!("Invalid operation kind: {}", s)),
+        }
+    }
+}
+
+/// How a recorded operation ended
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationOutcome {
+    Success,
+    Failed,
+    Cancelled,
+}
+
+impl std::fmt::Display for OperationOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationOutcome::Success => write!(f, "success"),
+            OperationOutcome::Failed => write!(f, "failed"),
+            OperationOutcome::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl std::str::FromStr for OperationOutcome {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "success" => Ok(OperationOutcome::Success),
+            "failed" => Ok(OperationOutcome::Failed),
+            "cancelled" => Ok(OperationOutcome::Cancelled),
+            _ => Err(anyhow::anyhow!("Invalid operation outcome: {}", s)),
+        }
+    }
+}
+
+/// One clone or pull of a repository, kept as history
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Operation {
+    pub id: String,
+    pub repository_id: String,
+    pub kind: String,
+    pub outcome: String,
+    /// Exit code of the git process, when one ran
+    pub exit_code: Option<i32>,
+    /// Error output with credentials removed
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: i64,
+}
+
+impl Operation {
+    /// Start recording an operation at the current time
+    pub fn start(repository_id: String, kind: OperationKind) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            repository_id,
+            kind: kind.to_string(),
+            outcome: OperationOutcome::Success.to_string(),
+            exit_code: None,
+            error: None,
+            started_at: now,
+            finished_at: now,
+            duration_ms: 0,
+        }
+    }
+
+    /// Record how the operation ended and how long it took
+    pub fn finish(
+        &mut self,
+        outcome: OperationOutcome,
+        exit_code: Option<i32>,
+        error: Option<String>,
+    ) {
+        self.outcome = outcome.to_string();
+        self.exit_code = exit_code;
+        self.error = error;
+        self.finished_at = Utc::now();
+        self.duration_ms = (self.finished_at - self.started_at).num_milliseconds();
+    }
+
+    /// Whether the operation failed (cancellations don't count)
+    pub fn is_failure(&self) -> bool {
+        self.outcome == OperationOutcome::Failed.to_string()
+    }
+}
+
+/// Normalize a user-defined tag name: trimmed and lowercase, without
+/// whitespace or commas so tags can be listed and typed comma-separated
+pub fn normalize_tag(name: &str) -> anyhow::Result<String> {
+    let name = name.trim().to_lowercase();
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("Tag name is empty"));
+    }
+    if name.contains(|c: char| c.is_whitespace() || c == ',') {
+        return Err(anyhow::anyhow!(
+            "Invalid tag name: {} (no spaces or commas)",
+            name
+        ));
+    }
+    Ok(name)
+}
+
+/// What a tag rule's pattern is matched against
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TagRuleKind {
+    /// Any of the repository's provider topics
+    Topic,
+    /// The repository's full name
+    Name,
+}
+
+impl std::fmt::Display for TagRuleKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TagRuleKind::Topic => write!(f, "topic"),
+            TagRuleKind::Name => write!(f, "name"),
+        }
+    }
+}
+
+impl std::str::FromStr for TagRuleKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "topic" => Ok(TagRuleKind::Topic),
+            "name" => Ok(TagRuleKind::Name),
+            _ => Err(anyhow::anyhow!("Invalid tag rule kind: {}", s)),
+        }
+    }
+}
+
+/// Assigns a tag to every repository whose topic or name matches a pattern
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TagRule {
+    pub id: String,
+    pub tag: String,
+    pub kind: String,
+    /// `*` and `?` wildcard pattern
+    pub pattern: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TagRule {
+    pub fn new(tag: String, kind: TagRuleKind, pattern: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            tag,
+            kind: kind.to_string(),
+            pattern,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Short form of the ID for display; any unique prefix finds the rule
+    pub fn short_id(&self) -> &str {
+        &self.id[..8.min(self.id.len())]
+    }
+
+    /// Whether the rule tags this repository
+    pub fn matches(&self, repo: &Repository) -> bool {
+        match self.kind.parse() {
+            Ok(TagRuleKind::Topic) => repo
+                .topics()
+                .iter()
+                .any(|topic| crate::filter::glob_match(&self.pattern, topic)),
+            Ok(TagRuleKind::Name) => crate::filter::glob_match(&self.pattern, &repo.full_name),
+            Err(_) => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,6 +975,27 @@ mod tests {
         assert_eq!(repo.provider, "github");
         assert!(!repo.is_private);
         assert_eq!(repo.status, "not_cloned");
+        assert_eq!(repo.source, "discovered");
+    }
+
+    #[test]
+    fn test_repository_sources() {
+        let mut repo = Repository::new(
+            "tokio".to_string(),
+            "tokio-rs/tokio".to_string(),
+            "tokio-rs".to_string(),
+            Provider::GitHub,
+            "https://github.com/tokio-rs/tokio.git".to_string(),
+            "git@github.com:tokio-rs/tokio.git".to_string(),
+            None,
+            false,
+        );
+        repo.set_source(RepositorySource::Watched);
+        assert!(repo.add_source(RepositorySource::Discovered));
+        assert!(!repo.add_source(RepositorySource::Watched));
+        assert_eq!(repo.source, "discovered,watched");
+        assert!(repo.has_source(&RepositorySource::Watched));
+        assert!(!repo.has_source(&RepositorySource::Starred));
     }
 
     #[test]
@@ -160,4 +1009,172 @@ mod tests {
         assert_eq!("github".parse::<Provider>().unwrap(), Provider::GitHub);
         assert_eq!("GitLab".parse::<Provider>().unwrap(), Provider::GitLab);
     }
+
+    #[test]
+    fn test_wiki_repository() {
+        let repo = Repository::new(
+            "project".to_string(),
+            "group/project".to_string(),
+            "group".to_string(),
+            Provider::GitLab,
+            "https://gitlab.com/group/project.git".to_string(),
+            "git@gitlab.com:group/project.git".to_string(),
+            None,
+            true,
+        );
+        let wiki = repo.wiki_repository();
+        assert_eq!(wiki.name, "project.wiki");
+        assert_eq!(wiki.full_name, "group/project.wiki");
+        assert_eq!(wiki.provider, "gitlab");
+        assert_eq!(
+            wiki.clone_url_https,
+            "https://gitlab.com/group/project.wiki.git"
+        );
+        assert_eq!(wiki.clone_url_ssh, "git@gitlab.com:group/project.wiki.git");
+        assert_eq!(wiki.kind, "wiki");
+        assert_eq!(wiki.parent_id.as_deref(), Some(repo.id.as_str()));
+        assert!(wiki.is_private);
+    }
+
+    #[test]
+    fn test_clone_options_validation() {
+        assert!(CloneOptions::new(Some(1), Some("blob:none".to_string()), true).is_ok());
+        assert!(CloneOptions::new(None, Some("tree:0".to_string()), false).is_ok());
+        assert!(CloneOptions::new(None, Some("blob:limit=1m".to_string()), false).is_ok());
+        assert!(CloneOptions::new(None, Some("sparse:oid=x".to_string()), false).is_err());
+        assert!(CloneOptions::new(Some(0), None, false).is_err());
+        assert!(CloneOptions::default().is_full());
+    }
+
+    #[test]
+    fn test_lfs_mode_parsing() {
+        assert_eq!("auto".parse::<LfsMode>().unwrap(), LfsMode::Auto);
+        assert_eq!("PULL".parse::<LfsMode>().unwrap(), LfsMode::Pull);
+        assert_eq!("skip".parse::<LfsMode>().unwrap(), LfsMode::Skip);
+        assert!("fetch".parse::<LfsMode>().is_err());
+        assert_eq!(LfsMode::default().to_string(), "auto");
+    }
+
+    #[test]
+    fn test_sparse_paths() {
+        let mut repo = Repository::new(
+            "monorepo".to_string(),
+            "org/monorepo".to_string(),
+            "org".to_string(),
+            Provider::GitHub,
+            "https://github.com/org/monorepo.git".to_string(),
+            "git@github.com:org/monorepo.git".to_string(),
+            None,
+            false,
+        );
+        assert!(repo.sparse_paths().is_empty());
+
+        repo.set_sparse_paths(&["services/payments/".to_string(), " libs/core".to_string()]);
+        assert_eq!(repo.sparse_paths(), vec!["services/payments", "libs/core"]);
+
+        repo.set_sparse_paths(&[]);
+        assert_eq!(repo.sparse_paths, None);
+
+        repo.set_topics(&[
+            "payments".to_string(),
+            " ".to_string(),
+            "ledger".to_string(),
+        ]);
+        assert_eq!(repo.topics(), vec!["payments", "ledger"]);
+    }
+
+    #[test]
+    fn test_job_and_task_lifecycle() {
+        let mut job = Job::new(JobKind::Clone, "clone-org github acme".to_string());
+        assert_eq!(job.status, "running");
+        assert_eq!(job.short_id().len(), 8);
+        job.update_status(JobStatus::Interrupted);
+        assert!(job.finished_at.is_some());
+        job.update_status(JobStatus::Running);
+        assert!(job.finished_at.is_none());
+
+        let mut task = JobTask::new(job.id.clone(), "repo-id".to_string(), 0);
+        assert!(task.is_unfinished());
+        task.start();
+        task.finish(TaskStatus::Failed, Some("network down".to_string()));
+        assert_eq!(task.attempts, 1);
+        assert_eq!(task.last_error.as_deref(), Some("network down"));
+        task.start();
+        task.finish(TaskStatus::Done, None);
+        assert_eq!(task.attempts, 2);
+        assert_eq!(task.last_error, None);
+        assert!(!task.is_unfinished());
+
+        assert_eq!("pull".parse::<JobKind>().unwrap(), JobKind::Pull);
+        assert_eq!(
+            "interrupted".parse::<JobStatus>().unwrap(),
+            JobStatus::Interrupted
+        );
+        assert_eq!("failed".parse::<TaskStatus>().unwrap(), TaskStatus::Failed);
+        assert!("paused".parse::<JobStatus>().is_err());
+    }
+
+    #[test]
+    fn test_operation_finish() {
+        let mut operation = Operation::start("repo-id".to_string(), OperationKind::Clone);
+        assert_eq!(operation.kind, "clone");
+        operation.finish(
+            OperationOutcome::Failed,
+            Some(128),
+            Some("fatal: repository not found".to_string()),
+        );
+        assert!(operation.is_failure());
+        assert_eq!(operation.exit_code, Some(128));
+        assert!(operation.duration_ms >= 0);
+        assert!(operation.finished_at >= operation.started_at);
+        assert_eq!(
+            "cancelled".parse::<OperationOutcome>().unwrap(),
+            OperationOutcome::Cancelled
+        );
+        assert!("fetch".parse::<OperationKind>().is_err());
+    }
+
+    #[test]
+    fn test_tags_and_rules() {
+        assert_eq!(normalize_tag(" Payments ").unwrap(), "payments");
+        assert!(normalize_tag("  ").is_err());
+        assert!(normalize_tag("team a").is_err());
+        assert!(normalize_tag("a,b").is_err());
+
+        let mut repo = Repository::new(
+            "ledger".to_string(),
+            "acme/ledger".to_string(),
+            "acme".to_string(),
+            Provider::GitHub,
+            "https://github.com/acme/ledger.git".to_string(),
+            "git@github.com:acme/ledger.git".to_string(),
+            None,
+            false,
+        );
+        repo.set_topics(&["payments-core".to_string()]);
+
+        let topic = TagRule::new(
+            "payments".to_string(),
+            TagRuleKind::Topic,
+            "payments*".to_string(),
+        );
+        let name = TagRule::new(
+            "platform".to_string(),
+            TagRuleKind::Name,
+            "infra/*".to_string(),
+        );
+        assert!(topic.matches(&repo));
+        assert!(!name.matches(&repo));
+        assert_eq!(topic.short_id().len(), 8);
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(
+            slugify("Deploy script for Staging!"),
+            "deploy-script-for-staging"
+        );
+        assert_eq!(slugify("  --weird__name.sh "), "weird-name-sh");
+        assert_eq!(slugify("日本語"), "");
+    }
 }
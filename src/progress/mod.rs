@@ -0,0 +1,240 @@
+use crate::git::backend::{ProgressCallback, ProgressPhase, TransferProgress};
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::IsTerminal;
+use std::sync::{Arc, Mutex};
+
+/// Progress output for a batch of clones or pulls.
+///
+/// On a terminal this draws an overall bar plus one bar per repository in flight,
+/// so parallel workers each get their own line.
+/// When stdout is not a terminal it falls back to plain log lines.
+pub struct ProgressReporter {
+    bars: Option<Bars>,
+}
+
+#[derive(Clone)]
+struct Bars {
+    multi: MultiProgress,
+    overall: ProgressBar,
+}
+
+impl ProgressReporter {
+    /// Reporter for `total` repositories; `action` labels the overall bar (e.g. "Cloning")
+    pub fn new(total: usize, action: &str) -> Self {
+        if std::io::stdout().is_terminal() {
+            Self::with_draw_target(total, action, ProgressDrawTarget::stdout())
+        } else {
+            Self::plain()
+        }
+    }
+
+    /// Reporter that only prints log lines
+    pub fn plain() -> Self {
+        Self { bars: None }
+    }
+
+    fn with_draw_target(total: usize, action: &str, target: ProgressDrawTarget) -> Self {
+        let multi = MultiProgress::with_draw_target(target);
+        let overall = multi.add(ProgressBar::new(total as u64));
+        overall.set_style(
+            ProgressStyle::with_template(
+                "{prefix:.bold} [{bar:30.cyan/blue}] {pos}/{len} repositories ({elapsed})",
+            )
+            .expect("valid progress template")
+            .progress_chars("=> "),
+        );
+        overall.set_prefix(action.to_string());
+        Self {
+            bars: Some(Bars { multi, overall }),
+        }
+    }
+
+    /// Print a line without tearing the bars
+    pub fn println(&self, message: impl AsRef<str>) {
+        match &self.bars {
+            Some(bars) => {
+                let _ = bars.multi.println(message.as_ref());
+            }
+            None => println!("{}", message.as_ref()),
+        }
+    }
+
+    /// Print an error line without tearing the bars
+    pub fn eprintln(&self, message: impl AsRef<str>) {
+        match &self.bars {
+            Some(bars) => {
+                let _ = bars.multi.println(message.as_ref());
+            }
+            None => eprintln!("{}", message.as_ref()),
+        }
+    }
+
+    /// Start tracking one repository
+    pub fn task(&self, name: &str) -> TaskProgress {
+        let bar = self.bars.as_ref().map(|bars| {
+            // Keep the overall bar last so worker lines stack above it
+            let bar = bars.multi.insert_before(&bars.overall, ProgressBar::new(0));
+            bar.set_style(
+                ProgressStyle::with_template("  {prefix:30!} [{bar:25}] {pos}/{len} {msg}")
+                    .expect("valid progress template")
+                    .progress_chars("=> "),
+            );
+            bar.set_prefix(name.to_string());
+            bar
+        });
+        TaskProgress {
+            name: name.to_string(),
+            bar,
+            bars: self.bars.clone(),
+            logged: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Remove the bars once the batch is done
+    pub fn finish(&self) {
+        if let Some(bars) = &self.bars {
+            bars.overall.finish_and_clear();
+            let _ = bars.multi.clear();
+        }
+    }
+}
+
+/// Progress of a single repository in a batch
+pub struct TaskProgress {
+    name: String,
+    bar: Option<ProgressBar>,
+    bars: Option<Bars>,
+    /// Phases already reported as plain log lines
+    logged: Arc<Mutex<Vec<ProgressPhase>>>,
+}
+
+impl TaskProgress {
+    /// Callback to hand to a git backend
+    pub fn callback(&self) -> ProgressCallback {
+        match &self.bar {
+            Some(bar) => {
+                let bar = bar.clone();
+                Arc::new(move |update: &TransferProgress| {
+                    bar.set_length(update.total as u64);
+                    bar.set_position(update.current as u64);
+                    if update.received_bytes > 0 {
+                        bar.set_message(format!(
+                            "{} {}",
+                            update.phase,
+                            HumanBytes(update.received_bytes)
+                        ));
+                    } else {
+                        bar.set_message(update.phase.to_string());
+                    }
+                })
+            }
+            None => {
+                let name = self.name.clone();
+                let logged = self.logged.clone();
+                Arc::new(move |update: &TransferProgress| {
+                    let done = update.total > 0 && update.current >= update.total;
+                    if !done || update.phase == ProgressPhase::Preparing {
+                        return;
+                    }
+                    let mut logged = logged.lock().unwrap_or_else(|e| e.into_inner());
+                    if logged.contains(&update.phase) {
+                        return;
+                    }
+                    logged.push(update.phase);
+                    if update.received_bytes > 0 {
+                        println!(
+                            "   {}: {} done ({}, {})",
+                            name,
+                            update.phase,
+                            update.total,
+                            HumanBytes(update.received_bytes)
+                        );
+                    } else {
+                        println!("   {}: {} done ({})", name, update.phase, update.total);
+                    }
+                })
+            }
+        }
+    }
+
+    /// Remove this repository's bar and advance the overall bar
+    pub fn finish(self) {
+        if let Some(bars) = &self.bars {
+            if let Some(bar) = &self.bar {
+                bar.finish_and_clear();
+                bars.multi.remove(bar);
+            }
+            bars.overall.inc(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_bars_follow_callbacks() {
+        let reporter =
+            ProgressReporter::with_draw_target(2, "Cloning", ProgressDrawTarget::hidden());
+        let task = reporter.task("owner/repo");
+        let callback = task.callback();
+        callback(&TransferProgress {
+            phase: ProgressPhase::Receiving,
+            current: 40,
+            total: 100,
+            received_bytes: 2048,
+        });
+
+        let bar = task.bar.clone().unwrap();
+        assert_eq!(bar.length(), Some(100));
+        assert_eq!(bar.position(), 40);
+        assert_eq!(bar.message(), "receiving objects 2.00 KiB");
+
+        task.finish();
+        let overall = &reporter.bars.as_ref().unwrap().overall;
+        assert_eq!(overall.position(), 1);
+        reporter.finish();
+    }
+
+    #[test]
+    fn test_parallel_tasks_get_their_own_bars() {
+        let reporter =
+            ProgressReporter::with_draw_target(3, "Pulling", ProgressDrawTarget::hidden());
+        let first = reporter.task("owner/first");
+        let second = reporter.task("owner/second");
+        let progress = |current| TransferProgress {
+            phase: ProgressPhase::Receiving,
+            current,
+            total: 10,
+            received_bytes: 0,
+        };
+        first.callback()(&progress(3));
+        second.callback()(&progress(7));
+        assert_eq!(first.bar.as_ref().unwrap().position(), 3);
+        assert_eq!(second.bar.as_ref().unwrap().position(), 7);
+        assert_eq!(first.bar.as_ref().unwrap().prefix(), "owner/first");
+
+        second.finish();
+        first.finish();
+        assert_eq!(reporter.bars.as_ref().unwrap().overall.position(), 2);
+        reporter.finish();
+    }
+
+    #[test]
+    fn test_plain_reporter_logs_each_phase_once() {
+        let reporter = ProgressReporter::plain();
+        let task = reporter.task("owner/repo");
+        let callback = task.callback();
+        for current in [50, 100, 100] {
+            callback(&TransferProgress {
+                phase: ProgressPhase::Receiving,
+                current,
+                total: 100,
+                received_bytes: 0,
+            });
+        }
+        assert_eq!(*task.logged.lock().unwrap(), vec![ProgressPhase::Receiving]);
+        task.finish();
+    }
+}
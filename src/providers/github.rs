@@ -1,5 +1,8 @@
-use crate::models::{Provider, Repository};
-use crate::providers::RepositoryProvider;
+use crate::models::{slugify, Provider, Repository, RepositoryKind, RepositorySource};
+use crate::providers::{
+    ensure_visible, paginated_url, tag_source, AuthenticatedDiscovery, RepositoryProvider,
+    UpstreamState,
+};
 use crate::Result;
 use anyhow::Context;
 use serde::Deserialize;
@@ -13,6 +16,17 @@ struct GitHubRepo {
     ssh_url: String,
     description: Option<String>,
     private: bool,
+    #[serde(default)]
+    has_wiki: bool,
+    #[serde(default)]
+    topics: Vec<String>,
+}
+
+/// The part of a single repository response `prune` looks at
+#[derive(Debug, Deserialize)]
+struct GitHubRepoState {
+    #[serde(default)]
+    archived: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -20,6 +34,15 @@ struct GitHubOwner {
     login: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct GitHubGist {
+    id: String,
+    description: Option<String>,
+    public: bool,
+    owner: Option<GitHubOwner>,
+    git_pull_url: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct GitHubUser {
     login: String,
@@ -32,7 +55,6 @@ struct GitHubOrg {
 
 pub struct GitHubClient {
     client: reqwest::Client,
-    #[allow(dead_code)]
     token: Option<String>,
 }
 
@@ -133,13 +155,59 @@ impl GitHubClient {
         Ok(all_orgs)
     }
 
+    /// Discover gists for a user, or for the authenticated user when `None`
+    pub async fn discover_gists(&self, username: Option<&str>) -> Result<Vec<Repository>> {
+        let url = match username {
+            Some(username) => format!("https://api.github.com/users/{}/gists", username),
+            None => "https://api.github.com/gists".to_string(),
+        };
+
+        let mut all_gists = Vec::new();
+        let mut page = 1;
+        let per_page = 100;
+
+        loop {
+            let url = paginated_url(&url, page, per_page);
+            let response = self
+                .client
+                .get(&url)
+                .send()
+                .await
+                .context("Failed to fetch gists from GitHub")?;
+
+            if !response.status().is_success() {
+                return Err(anyhow::anyhow!(
+                    "GitHub API error: {} - {}",
+                    response.status(),
+                    response.text().await.unwrap_or_default()
+                ));
+            }
+
+            let body = response
+                .text()
+                .await
+                .context("Failed to read GitHub gists response")?;
+            let gists = gists_from_json(&body, username)?;
+
+            if gists.is_empty() {
+                break;
+            }
+
+            all_gists.extend(gists);
+
+            page += 1;
+        }
+
+        Ok(all_gists)
+    }
+
     async fn fetch_repos(&self, url: &str) -> Result<Vec<Repository>> {
         let mut all_repos = Vec::new();
         let mut page = 1;
         let per_page = 100;
 
         loop {
-            let url = format!("{}?page={}&per_page={}", url, page, per_page);
+            let url = paginated_url(url, page, per_page);
             let response = self
                 .client
                 .get(&url)
@@ -165,7 +233,7 @@ impl GitHubClient {
             }
 
             for repo in repos {
-                all_repos.push(Repository::new(
+                let mut repository = Repository::new(
                     repo.name,
                     repo.full_name,
                     repo.owner.login,
@@ -174,7 +242,10 @@ impl GitHubClient {
                     repo.ssh_url,
                     repo.description,
                     repo.private,
-                ));
+                );
+                repository.has_wiki = repo.has_wiki;
+                repository.set_topics(&repo.topics);
+                all_repos.push(repository);
             }
 
             page += 1;
@@ -184,6 +255,49 @@ impl GitHubClient {
     }
 }
 
+/// Repository records for one page of a gists response.
+///
+/// Gists of `username` (the authenticated user when `None`) may omit their owner.
+pub fn gists_from_json(body: &str, username: Option<&str>) -> Result<Vec<Repository>> {
+    let gists: Vec<GitHubGist> =
+        serde_json::from_str(body).context("Failed to parse GitHub gists response")?;
+    Ok(gists
+        .into_iter()
+        .map(|gist| gist_to_repository(gist, username))
+        .collect())
+}
+
+/// Map a gist onto a repository record named after its description and id.
+///
+/// Descriptions are not unique, so the id keeps gists that share one apart
+/// in clone paths.
+fn gist_to_repository(gist: GitHubGist, username: Option<&str>) -> Repository {
+    let owner = gist
+        .owner
+        .map(|o| o.login)
+        .or_else(|| username.map(|u| u.to_string()))
+        .unwrap_or_else(|| "anonymous".to_string());
+    let slug = gist.description.as_deref().map(slugify).unwrap_or_default();
+    let name = if slug.is_empty() {
+        gist.id.clone()
+    } else {
+        format!("{}-{}", slug, gist.id)
+    };
+
+    let mut repo = Repository::new(
+        name,
+        format!("gists/{}/{}", owner, gist.id),
+        owner,
+        Provider::GitHub,
+        gist.git_pull_url,
+        format!("git@gist.github.com:{}.git", gist.id),
+        gist.description,
+        !gist.public,
+    );
+    repo.set_kind(RepositoryKind::Gist);
+    repo
+}
+
 #[async_trait::async_trait]
 impl RepositoryProvider for GitHubClient {
     async fn discover_user_repos(&self, username: &str) -> Result<Vec<Repository>> {
@@ -195,4 +309,72 @@ impl RepositoryProvider for GitHubClient {
         let url = format!("https://api.github.com/orgs/{}/repos", org);
         self.fetch_repos(&url).await
     }
+
+    async fn discover_authenticated_repos(
+        &self,
+        options: &AuthenticatedDiscovery,
+    ) -> Result<Vec<Repository>> {
+        let affiliation = options
+            .affiliations
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let url = format!(
+            "https://api.github.com/user/repos?affiliation={}",
+            affiliation
+        );
+        self.fetch_repos(&url).await
+    }
+
+    async fn repository_state(&self, repo: &Repository) -> Result<UpstreamState> {
+        ensure_visible(repo, self.token.is_some())?;
+        let full_name = &repo.full_name;
+        let url = format!("https://api.github.com/repos/{}", full_name);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to fetch repository from GitHub")?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(UpstreamState::Missing);
+        }
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "GitHub API error: {} - {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        let repo: GitHubRepoState = response
+            .json()
+            .await
+            .context("Failed to parse GitHub API response")?;
+        Ok(if repo.archived {
+            UpstreamState::Archived
+        } else {
+            UpstreamState::Active
+        })
+    }
+
+    async fn discover_starred_repos(&self, username: Option<&str>) -> Result<Vec<Repository>> {
+        let url = match username {
+            Some(username) => format!("https://api.github.com/users/{}/starred", username),
+            None => "https://api.github.com/user/starred".to_string(),
+        };
+        let repos = self.fetch_repos(&url).await?;
+        Ok(tag_source(repos, RepositorySource::Starred))
+    }
+
+    async fn discover_watched_repos(&self, username: Option<&str>) -> Result<Vec<Repository>> {
+        let url = match username {
+            Some(username) => format!("https://api.github.com/users/{}/subscriptions", username),
+            None => "https://api.github.com/user/subscriptions".to_string(),
+        };
+        let repos = self.fetch_repos(&url).await?;
+        Ok(tag_source(repos, RepositorySource::Watched))
+    }
 }
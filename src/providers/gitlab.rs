@@ -1,5 +1,8 @@
-use crate::models::{Provider, Repository};
-use crate::providers::RepositoryProvider;
+use crate::models::{Provider, Repository, RepositorySource};
+use crate::providers::{
+    ensure_visible, paginated_url, tag_source, AuthenticatedDiscovery, RepositoryProvider,
+    UpstreamState,
+};
 use crate::Result;
 use anyhow::Context;
 use serde::Deserialize;
@@ -13,6 +16,17 @@ struct GitLabProject {
     ssh_url_to_repo: String,
     description: Option<String>,
     visibility: String,
+    #[serde(default)]
+    wiki_enabled: bool,
+    #[serde(default)]
+    topics: Vec<String>,
+}
+
+/// The part of a single project response `prune` looks at
+#[derive(Debug, Deserialize)]
+struct GitLabProjectState {
+    #[serde(default)]
+    archived: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -32,7 +46,6 @@ struct GitLabGroup {
 
 pub struct GitLabClient {
     client: reqwest::Client,
-    #[allow(dead_code)]
     token: Option<String>,
     base_url: String,
 }
@@ -143,7 +156,7 @@ impl GitLabClient {
         let per_page = 100;
 
         loop {
-            let url = format!("{}?page={}&per_page={}", url, page, per_page);
+            let url = paginated_url(url, page, per_page);
             let response = self
                 .client
                 .get(&url)
@@ -170,7 +183,7 @@ impl GitLabClient {
 
             for project in projects {
                 let is_private = project.visibility != "public";
-                all_repos.push(Repository::new(
+                let mut repository = Repository::new(
                     project.name,
                     project.path_with_namespace.clone(),
                     project.namespace.path,
@@ -179,7 +192,10 @@ impl GitLabClient {
                     project.ssh_url_to_repo,
                     project.description,
                     is_private,
-                ));
+                );
+                repository.has_wiki = project.wiki_enabled;
+                repository.set_topics(&project.topics);
+                all_repos.push(repository);
             }
 
             page += 1;
@@ -200,4 +216,69 @@ impl RepositoryProvider for GitLabClient {
         let url = format!("{}/api/v4/groups/{}/projects", self.base_url, group);
         self.fetch_projects(&url).await
     }
+
+    async fn discover_authenticated_repos(
+        &self,
+        options: &AuthenticatedDiscovery,
+    ) -> Result<Vec<Repository>> {
+        // GitLab has no collaborator/member split; membership covers both
+        let mut url = if options.owner_only() {
+            format!("{}/api/v4/projects?owned=true", self.base_url)
+        } else {
+            format!("{}/api/v4/projects?membership=true", self.base_url)
+        };
+        if let Some(level) = options.min_access_level {
+            url.push_str(&format!("&min_access_level={}", level));
+        }
+        self.fetch_projects(&url).await
+    }
+
+    async fn repository_state(&self, repo: &Repository) -> Result<UpstreamState> {
+        ensure_visible(repo, self.token.is_some())?;
+        let full_name = &repo.full_name;
+        let url = format!(
+            "{}/api/v4/projects/{}",
+            self.base_url,
+            full_name.replace('/', "%2F")
+        );
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to fetch project from GitLab")?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(UpstreamState::Missing);
+        }
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "GitLab API error: {} - {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+
+        let project: GitLabProjectState = response
+            .json()
+            .await
+            .context("Failed to parse GitLab API response")?;
+        Ok(if project.archived {
+            UpstreamState::Archived
+        } else {
+            UpstreamState::Active
+        })
+    }
+
+    async fn discover_starred_repos(&self, username: Option<&str>) -> Result<Vec<Repository>> {
+        let url = match username {
+            Some(username) => format!(
+                "{}/api/v4/users/{}/starred_projects",
+                self.base_url, username
+            ),
+            None => format!("{}/api/v4/projects?starred=true", self.base_url),
+        };
+        let repos = self.fetch_projects(&url).await?;
+        Ok(tag_source(repos, RepositorySource::Starred))
+    }
 }
@@ -1,9 +1,99 @@
 pub mod github;
 pub mod gitlab;
 
-use crate::models::Repository;
+use crate::models::{Repository, RepositorySource};
 use crate::Result;
 
+/// Relationship between the authenticated user and a repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affiliation {
+    /// Repositories owned by the user
+    Owner,
+    /// Repositories the user was added to as a collaborator
+    Collaborator,
+    /// Repositories the user can access through an organization/group membership
+    OrganizationMember,
+}
+
+impl std::fmt::Display for Affiliation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Affiliation::Owner => write!(f, "owner"),
+            Affiliation::Collaborator => write!(f, "collaborator"),
+            Affiliation::OrganizationMember => write!(f, "organization_member"),
+        }
+    }
+}
+
+impl std::str::FromStr for Affiliation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "owner" => Ok(Affiliation::Owner),
+            "collaborator" => Ok(Affiliation::Collaborator),
+            "organization_member" | "member" => Ok(Affiliation::OrganizationMember),
+            _ => Err(anyhow::anyhow!("Invalid affiliation: {}", s)),
+        }
+    }
+}
+
+/// Options for discovering repositories visible to the authenticated user
+#[derive(Debug, Clone)]
+pub struct AuthenticatedDiscovery {
+    /// Affiliations to include
+    pub affiliations: Vec<Affiliation>,
+    /// Minimum GitLab access level (10=guest, 20=reporter, 30=developer, 40=maintainer, 50=owner)
+    pub min_access_level: Option<u32>,
+}
+
+impl Default for AuthenticatedDiscovery {
+    fn default() -> Self {
+        Self {
+            affiliations: vec![Affiliation::Owner],
+            min_access_level: None,
+        }
+    }
+}
+
+impl AuthenticatedDiscovery {
+    /// Include owned, collaborator and organization member repositories
+    pub fn all() -> Self {
+        Self {
+            affiliations: vec![
+                Affiliation::Owner,
+                Affiliation::Collaborator,
+                Affiliation::OrganizationMember,
+            ],
+            min_access_level: None,
+        }
+    }
+
+    /// Whether only repositories owned by the user are requested
+    pub fn owner_only(&self) -> bool {
+        !self.affiliations.is_empty() && self.affiliations.iter().all(|a| *a == Affiliation::Owner)
+    }
+}
+
+/// Whether a repository still exists upstream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpstreamState {
+    Active,
+    Archived,
+    /// Deleted, renamed away or no longer visible with the current token
+    Missing,
+}
+
+impl std::fmt::Display for UpstreamState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpstreamState::Active => write!(f, "active"),
+            UpstreamState::Archived => write!(f, "archived"),
+            UpstreamState::Missing => write!(f, "missing"),
+        }
+    }
+}
+
 /// Trait for repository providers
 #[async_trait::async_trait]
 pub trait RepositoryProvider {
@@ -12,4 +102,53 @@ pub trait RepositoryProvider {
 
     /// Discover repositories for an organization/group
     async fn discover_org_repos(&self, org: &str) -> Result<Vec<Repository>>;
+
+    /// Discover repositories the authenticated user can access, including private ones
+    async fn discover_authenticated_repos(
+        &self,
+        options: &AuthenticatedDiscovery,
+    ) -> Result<Vec<Repository>>;
+
+    /// Discover repositories starred by a user, or by the authenticated user when `None`
+    async fn discover_starred_repos(&self, username: Option<&str>) -> Result<Vec<Repository>>;
+
+    /// Look up whether a repository still exists upstream and is not archived
+    async fn repository_state(&self, repo: &Repository) -> Result<UpstreamState>;
+
+    /// Discover repositories watched by a user, or by the authenticated user when `None`
+    async fn discover_watched_repos(&self, username: Option<&str>) -> Result<Vec<Repository>> {
+        let _ = username;
+        Err(anyhow::anyhow!(
+            "Watched repositories are not supported by this provider"
+        ))
+    }
+}
+
+/// Refuse to look up a private repository without a token: providers answer
+/// such requests with the same 404 as for a deleted repository
+pub(crate) fn ensure_visible(repo: &Repository, authenticated: bool) -> Result<()> {
+    if repo.is_private && !authenticated {
+        return Err(anyhow::anyhow!(
+            "{} is private; a token is needed to tell whether it still exists",
+            repo.full_name
+        ));
+    }
+    Ok(())
+}
+
+/// Mark repositories with the source they were discovered through
+pub(crate) fn tag_source(repos: Vec<Repository>, source: RepositorySource) -> Vec<Repository> {
+    repos
+        .into_iter()
+        .map(|mut repo| {
+            repo.set_source(source.clone());
+            repo
+        })
+        .collect()
+}
+
+/// Append pagination parameters to an API URL that may already carry a query string
+pub(crate) fn paginated_url(url: &str, page: u32, per_page: u32) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{}{}page={}&per_page={}", url, separator, page, per_page)
 }
@@ -0,0 +1,256 @@
+use crate::database::RepositoryDatabase;
+use crate::git::GitOperations;
+use crate::importers::local::find_git_repositories;
+use crate::models::{CloneStatus, Repository};
+use crate::providers::UpstreamState;
+use crate::Result;
+use anyhow::Context;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+/// Why `prune` removes a clone or record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneReason {
+    /// The repository was archived upstream
+    Archived,
+    /// The repository no longer exists upstream; its record is deleted too
+    DeletedUpstream,
+    /// The repository does not match the filters given to `prune`
+    Excluded,
+    /// The record says cloned but the clone is gone
+    MissingClone,
+    /// A clone below the clone path that no record points at
+    Untracked,
+}
+
+impl std::fmt::Display for PruneReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PruneReason::Archived => write!(f, "archived upstream"),
+            PruneReason::DeletedUpstream => write!(f, "deleted upstream"),
+            PruneReason::Excluded => write!(f, "excluded by filters"),
+            PruneReason::MissingClone => write!(f, "clone missing"),
+            PruneReason::Untracked => write!(f, "not in the database"),
+        }
+    }
+}
+
+/// What happens to pruned clones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Disposal {
+    /// Move clones into this directory, keeping their path below the clone path
+    Trash(PathBuf),
+    /// Delete clones for good
+    Delete,
+}
+
+/// One clone or record `prune` removes
+#[derive(Debug, Clone)]
+pub struct PruneItem {
+    pub reason: PruneReason,
+    /// Database record, `None` for untracked clones
+    pub repository: Option<Repository>,
+    /// Clone on disk, `None` when it is already gone
+    pub path: Option<PathBuf>,
+    /// Work that only exists in the clone; the item is kept when there is any
+    pub blocked_by: Vec<String>,
+}
+
+impl PruneItem {
+    /// Name to show in the plan: the full name, or the path of an untracked clone
+    pub fn label(&self) -> String {
+        match (&self.repository, &self.path) {
+            (Some(repo), _) => repo.full_name.clone(),
+            (None, Some(path)) => path.display().to_string(),
+            (None, None) => "-".to_string(),
+        }
+    }
+
+    /// Whether the clone has work that would be lost
+    pub fn is_blocked(&self) -> bool {
+        !self.blocked_by.is_empty()
+    }
+
+    /// Remove the clone and update or delete the record; returns where the
+    /// clone was moved to, if it was trashed
+    pub async fn execute(
+        &self,
+        db: &RepositoryDatabase,
+        clone_root: &Path,
+        disposal: &Disposal,
+    ) -> Result<Option<PathBuf>> {
+        if self.is_blocked() {
+            return Err(anyhow::anyhow!(
+                "{} has {}",
+                self.label(),
+                self.blocked_by.join(", ")
+            ));
+        }
+
+        let mut trashed = None;
+        if let Some(path) = &self.path {
+            match disposal {
+                Disposal::Trash(trash) => {
+                    let target = trash_path(trash, clone_root, path);
+                    if target.exists() {
+                        return Err(anyhow::anyhow!(
+                            "Trash already contains {}",
+                            target.display()
+                        ));
+                    }
+                    if let Some(parent) = target.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::rename(path, &target).with_context(|| {
+                        format!(
+                            "Failed to move {} to {}; pick a trash directory on the same filesystem",
+                            path.display(),
+                            target.display()
+                        )
+                    })?;
+                    trashed = Some(target);
+                }
+                Disposal::Delete => std::fs::remove_dir_all(path)
+                    .with_context(|| format!("Failed to delete {}", path.display()))?,
+            }
+            remove_empty_parents(path, clone_root);
+        }
+
+        if let Some(repo) = &self.repository {
+            if self.reason == PruneReason::DeletedUpstream {
+                db.delete_repository(&repo.id).await?;
+            } else {
+                let mut repo = repo.clone();
+                repo.local_path = None;
+                repo.update_status(CloneStatus::NotCloned);
+                db.update_repository(&repo).await?;
+            }
+        }
+        Ok(trashed)
+    }
+}
+
+/// Work out what to prune.
+///
+/// `selected` holds the IDs of repositories matching the filters, `None` when
+/// no filter was given; `upstream` maps repository IDs to their upstream state
+/// for the repositories that were checked. Wikis follow their repository.
+/// Clones below `clone_root` without a record are found too, except inside
+/// `trash_root`.
+pub fn plan(
+    repos: &[Repository],
+    selected: Option<&HashSet<String>>,
+    upstream: &HashMap<String, UpstreamState>,
+    clone_root: &Path,
+    trash_root: &Path,
+    git_ops: &GitOperations,
+) -> Result<Vec<PruneItem>> {
+    let mut items = Vec::new();
+    let mut known = HashSet::new();
+
+    for repo in repos {
+        if repo.status != CloneStatus::Cloned.to_string() {
+            continue;
+        }
+        let Some(path) = repo.local_path.as_deref().map(PathBuf::from) else {
+            continue;
+        };
+        let exists = path.join(".git").exists();
+        if exists {
+            known.insert(path.canonicalize().unwrap_or_else(|_| path.clone()));
+        }
+
+        let state = upstream.get(&repo.id).or_else(|| {
+            repo.parent_id
+                .as_ref()
+                .and_then(|parent| upstream.get(parent))
+        });
+        let reason = match state {
+            Some(UpstreamState::Missing) => PruneReason::DeletedUpstream,
+            Some(UpstreamState::Archived) if exists => PruneReason::Archived,
+            _ if !exists => PruneReason::MissingClone,
+            _ if selected.is_some_and(|ids| !ids.contains(&repo.id)) => PruneReason::Excluded,
+            _ => continue,
+        };
+        let path = exists.then_some(path);
+        items.push(PruneItem {
+            reason,
+            repository: Some(repo.clone()),
+            blocked_by: path
+                .as_deref()
+                .map(|path| local_work(git_ops, path))
+                .unwrap_or_default(),
+            path,
+        });
+    }
+
+    if clone_root.exists() {
+        let trash_root = trash_root
+            .canonicalize()
+            .unwrap_or_else(|_| trash_root.to_path_buf());
+        for path in find_git_repositories(clone_root)? {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if known.contains(&canonical) || canonical.starts_with(&trash_root) {
+                continue;
+            }
+            items.push(PruneItem {
+                reason: PruneReason::Untracked,
+                repository: None,
+                blocked_by: local_work(git_ops, &path),
+                path: Some(path),
+            });
+        }
+    }
+    Ok(items)
+}
+
+/// Local work of a clone; a clone that can't be inspected is never pruned
+fn local_work(git_ops: &GitOperations, path: &Path) -> Vec<String> {
+    git_ops
+        .local_work(&path.to_string_lossy())
+        .unwrap_or_else(|e| vec![format!("unreadable git state ({})", e)])
+}
+
+/// Where a clone goes in the trash: its path below the clone path, or its
+/// full path for clones elsewhere
+pub fn trash_path(trash: &Path, clone_root: &Path, clone: &Path) -> PathBuf {
+    let relative: PathBuf = match clone.strip_prefix(clone_root) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => clone
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect(),
+    };
+    trash.join(relative)
+}
+
+/// Remove the directories a removed clone leaves empty, up to the clone path
+fn remove_empty_parents(path: &Path, clone_root: &Path) {
+    for dir in path.ancestors().skip(1) {
+        if !dir.starts_with(clone_root) || dir == clone_root {
+            break;
+        }
+        // Fails, and stops, at the first directory that isn't empty
+        if std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_path() {
+        let trash = Path::new("/trash/20240101-120000");
+        assert_eq!(
+            trash_path(trash, Path::new("/repos"), Path::new("/repos/acme/api")),
+            PathBuf::from("/trash/20240101-120000/acme/api")
+        );
+        assert_eq!(
+            trash_path(trash, Path::new("/repos"), Path::new("/src/api")),
+            PathBuf::from("/trash/20240101-120000/src/api")
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use crate::database::RepositoryDatabase;
-use crate::models::{CloneStatus, Repository};
+use crate::exec::ExecTarget;
+use crate::grep::{GrepMatch, GrepOptions};
+use crate::models::{normalize_tag, CloneStatus, Operation, Repository, RepositorySource};
 use crate::Result;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
@@ -8,11 +10,13 @@ use crossterm::{
 };
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
 };
+use std::collections::HashMap;
 use std::io;
 use tokio::time::Duration;
 
@@ -23,6 +27,35 @@ pub struct App {
     selected: ListState,
     status_message: String,
     filter: Filter,
+    /// Clone and pull history of the selected repository while the detail view is open
+    details: Option<Vec<Operation>>,
+    /// Search pattern being typed after `/`
+    search_input: Option<String>,
+    /// Results of the last code search while they are shown
+    search: Option<SearchResults>,
+    /// Metadata query being typed after `f`; the list follows it as it changes
+    filter_input: Option<String>,
+    /// Tags of the listed repositories, keyed by repository ID
+    tags: HashMap<String, Vec<String>>,
+    /// Comma-separated tags of the selected repository being edited after `t`
+    tag_input: Option<String>,
+}
+
+/// Operations shown in the detail view
+const HISTORY_LIMIT: u32 = 20;
+
+/// Repositories searched at the same time
+const SEARCH_PARALLELISM: usize = 8;
+
+/// Repositories shown for a metadata query
+const FILTER_LIMIT: u32 = 200;
+
+/// Matches of a code search across the listed repositories
+struct SearchResults {
+    pattern: String,
+    /// Repository full name and match
+    hits: Vec<(String, GrepMatch)>,
+    selected: ListState,
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +65,9 @@ pub enum Filter {
     GitLab,
     Cloned,
     NotCloned,
+    Starred,
+    /// Full-text query over names, descriptions, topics and owners
+    Search(String),
 }
 
 impl App {
@@ -45,6 +81,12 @@ impl App {
             selected,
             status_message: "Welcome to Super Clone! Press 'h' for help.".to_string(),
             filter: Filter::All,
+            details: None,
+            search_input: None,
+            search: None,
+            filter_input: None,
+            tags: HashMap::new(),
+            tag_input: None,
         }
     }
 
@@ -89,10 +131,35 @@ impl App {
     }
 
     async fn handle_input(&mut self, key: KeyCode) -> Result<bool> {
+        if self.filter_input.is_some() {
+            return self.handle_filter_input(key).await;
+        }
+        if self.tag_input.is_some() {
+            return self.handle_tag_input(key).await;
+        }
+        if let Some(input) = &mut self.search_input {
+            match key {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Esc => self.search_input = None,
+                KeyCode::Enter => {
+                    let pattern = self.search_input.take().unwrap_or_default();
+                    self.run_search(pattern).await?;
+                }
+                _ => {}
+            }
+            return Ok(false);
+        }
+        if self.search.is_some() {
+            return self.handle_search_input(key).await;
+        }
+
         match key {
             KeyCode::Char('q') => return Ok(true),
             KeyCode::Char('h') => {
-                self.status_message = "Commands: q=quit, d=delete, r=refresh, g=GitHub only, l=GitLab only, a=all, c=cloned, n=not cloned, ↑↓=navigate".to_string();
+                self.status_message = "Commands: q=quit, Enter=details, t=edit tags, f=filter, /=search code, d=delete, r=refresh, g=GitHub only, l=GitLab only, a=all, c=cloned, n=not cloned, s=starred, ↑↓=navigate".to_string();
             }
             KeyCode::Char('d') => {
                 if let Some(index) = self.selected.selected() {
@@ -104,6 +171,32 @@ impl App {
                     }
                 }
             }
+            KeyCode::Enter => {
+                if self.details.is_some() {
+                    self.details = None;
+                } else {
+                    self.load_details().await?;
+                }
+            }
+            KeyCode::Esc => {
+                self.details = None;
+            }
+            KeyCode::Char('/') => {
+                self.search_input = Some(String::new());
+            }
+            KeyCode::Char('t') => {
+                if let Some(repo) = self.selected_repo() {
+                    let current = self.tags.get(&repo.id).cloned().unwrap_or_default();
+                    self.tag_input = Some(current.join(", "));
+                }
+            }
+            KeyCode::Char('f') => {
+                let query = match &self.filter {
+                    Filter::Search(query) => query.clone(),
+                    _ => String::new(),
+                };
+                self.filter_input = Some(query);
+            }
             KeyCode::Char('r') => {
                 self.refresh_repos().await?;
                 self.status_message = "Repositories refreshed!".to_string();
@@ -133,6 +226,11 @@ impl App {
                 self.refresh_repos().await?;
                 self.status_message = "Showing not cloned repositories".to_string();
             }
+            KeyCode::Char('s') => {
+                self.filter = Filter::Starred;
+                self.refresh_repos().await?;
+                self.status_message = "Showing starred repositories".to_string();
+            }
             KeyCode::Down => {
                 let i = match self.selected.selected() {
                     Some(i) => {
@@ -145,6 +243,9 @@ impl App {
                     None => 0,
                 };
                 self.selected.select(Some(i));
+                if self.details.is_some() {
+                    self.load_details().await?;
+                }
             }
             KeyCode::Up => {
                 let i = match self.selected.selected() {
@@ -158,14 +259,301 @@ impl App {
                     None => 0,
                 };
                 self.selected.select(Some(i));
+                if self.details.is_some() {
+                    self.load_details().await?;
+                }
             }
             _ => {}
         }
         Ok(false)
     }
 
+    /// Keys typed while editing the tags of the selected repository
+    async fn handle_tag_input(&mut self, key: KeyCode) -> Result<bool> {
+        let Some(input) = &mut self.tag_input else {
+            return Ok(false);
+        };
+        match key {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => self.tag_input = None,
+            KeyCode::Enter => {
+                let input = self.tag_input.take().unwrap_or_default();
+                self.save_tags(&input).await?;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Replace the tags of the selected repository with a comma-separated list
+    async fn save_tags(&mut self, input: &str) -> Result<()> {
+        let Some(repo) = self.selected_repo().cloned() else {
+            return Ok(());
+        };
+        let wanted = match input
+            .split(',')
+            .filter(|tag| !tag.trim().is_empty())
+            .map(normalize_tag)
+            .collect::<Result<Vec<_>>>()
+        {
+            Ok(wanted) => wanted,
+            Err(e) => {
+                self.status_message = format!("❌ {}", e);
+                return Ok(());
+            }
+        };
+
+        let current = self.tags.get(&repo.id).cloned().unwrap_or_default();
+        for tag in current.iter().filter(|tag| !wanted.contains(tag)) {
+            self.db.remove_tag(&repo.id, tag).await?;
+        }
+        for tag in wanted.iter().filter(|tag| !current.contains(tag)) {
+            self.db.add_tag(&repo.id, tag).await?;
+        }
+        self.status_message = if wanted.is_empty() {
+            format!("Removed all tags from {}", repo.full_name)
+        } else {
+            format!("Tagged {} with {}", repo.full_name, wanted.join(", "))
+        };
+        self.refresh_repos().await
+    }
+
+    /// Keys typed into the filter bar; the list is searched again on every change
+    async fn handle_filter_input(&mut self, key: KeyCode) -> Result<bool> {
+        let Some(input) = &mut self.filter_input else {
+            return Ok(false);
+        };
+        match key {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => {
+                self.filter_input = None;
+                self.filter = Filter::All;
+                self.refresh_repos().await?;
+                self.status_message = "Filter cleared".to_string();
+                return Ok(false);
+            }
+            KeyCode::Enter => {
+                self.filter_input = None;
+                return Ok(false);
+            }
+            _ => return Ok(false),
+        }
+
+        let query = input.trim().to_string();
+        self.filter = if query.is_empty() {
+            Filter::All
+        } else {
+            Filter::Search(query)
+        };
+        self.selected.select(Some(0));
+        self.refresh_repos().await?;
+        Ok(false)
+    }
+
+    async fn handle_search_input(&mut self, key: KeyCode) -> Result<bool> {
+        let Some(search) = &mut self.search else {
+            return Ok(false);
+        };
+        let count = search.hits.len();
+        match key {
+            KeyCode::Char('q') => return Ok(true),
+            KeyCode::Esc => self.search = None,
+            KeyCode::Char('/') => {
+                self.search = None;
+                self.search_input = Some(String::new());
+            }
+            KeyCode::Down if count > 0 => {
+                let i = search.selected.selected().map_or(0, |i| (i + 1) % count);
+                search.selected.select(Some(i));
+            }
+            KeyCode::Up if count > 0 => {
+                let i =
+                    search
+                        .selected
+                        .selected()
+                        .map_or(0, |i| if i == 0 { count - 1 } else { i - 1 });
+                search.selected.select(Some(i));
+            }
+            KeyCode::Enter => {
+                let repository = search
+                    .selected
+                    .selected()
+                    .and_then(|i| search.hits.get(i))
+                    .map(|(repository, _)| repository.clone());
+                if let Some(repository) = repository {
+                    self.search = None;
+                    self.jump_to(&repository).await?;
+                }
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Search the files of the listed repositories that are cloned
+    async fn run_search(&mut self, pattern: String) -> Result<()> {
+        if pattern.is_empty() {
+            return Ok(());
+        }
+        let targets: Vec<ExecTarget> = self
+            .repos
+            .iter()
+            .filter_map(|repo| {
+                repo.local_path.as_ref().map(|path| ExecTarget {
+                    name: repo.full_name.clone(),
+                    path: path.into(),
+                })
+            })
+            .collect();
+        let options = GrepOptions {
+            pattern: pattern.clone(),
+            parallel: SEARCH_PARALLELISM,
+            ..Default::default()
+        };
+
+        let mut hits = Vec::new();
+        let mut failed = 0;
+        for result in crate::grep::search(&targets, &options).await? {
+            if result.error.is_some() {
+                failed += 1;
+            }
+            for found in result.matches {
+                hits.push((result.repository.clone(), found));
+            }
+        }
+        let repos: std::collections::BTreeSet<&str> =
+            hits.iter().map(|(repo, _)| repo.as_str()).collect();
+        self.status_message = format!(
+            "{} matches for '{}' in {} of {} repositories{}. Enter=jump to repository, Esc=close",
+            hits.len(),
+            pattern,
+            repos.len(),
+            targets.len(),
+            if failed > 0 {
+                format!(" ({} could not be searched)", failed)
+            } else {
+                String::new()
+            }
+        );
+
+        let mut selected = ListState::default();
+        if !hits.is_empty() {
+            selected.select(Some(0));
+        }
+        self.search = Some(SearchResults {
+            pattern,
+            hits,
+            selected,
+        });
+        Ok(())
+    }
+
+    /// Select a repository in the list, showing all repositories if the
+    /// current filter hides it
+    async fn jump_to(&mut self, full_name: &str) -> Result<()> {
+        let mut index = self.repos.iter().position(|r| r.full_name == full_name);
+        if index.is_none() {
+            self.filter = Filter::All;
+            self.refresh_repos().await?;
+            index = self.repos.iter().position(|r| r.full_name == full_name);
+        }
+        if let Some(index) = index {
+            self.selected.select(Some(index));
+            self.status_message = format!("Jumped to {}", full_name);
+            if self.details.is_some() {
+                self.load_details().await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn selected_repo(&self) -> Option<&Repository> {
+        self.selected.selected().and_then(|i| self.repos.get(i))
+    }
+
+    /// Load the history of the selected repository into the detail view
+    async fn load_details(&mut self) -> Result<()> {
+        let Some(repo_id) = self.selected_repo().map(|repo| repo.id.clone()) else {
+            self.details = None;
+            return Ok(());
+        };
+        self.details = Some(self.db.get_operations(&repo_id, HISTORY_LIMIT).await?);
+        Ok(())
+    }
+
+    /// Text of the detail view: the last failure reason and the history
+    fn details_text(repo: &Repository, operations: &[Operation]) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(Span::styled(
+                repo.full_name.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Line::from(format!("Status: {}", repo.status)),
+            Line::from(format!(
+                "Path: {}",
+                repo.local_path.as_deref().unwrap_or("-")
+            )),
+            Line::from(""),
+        ];
+
+        match operations.iter().find(|op| op.is_failure()) {
+            Some(failure) => {
+                lines.push(Line::from(Span::styled(
+                    format!(
+                        "Last failure ({} {}):",
+                        failure.kind,
+                        failure.started_at.format("%Y-%m-%d %H:%M")
+                    ),
+                    Style::default().fg(Color::Red),
+                )));
+                for line in failure.error.as_deref().unwrap_or("").lines() {
+                    lines.push(Line::from(format!("  {}", line)));
+                }
+            }
+            None => lines.push(Line::from("No recorded failures")),
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "History:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        if operations.is_empty() {
+            lines.push(Line::from("  No clone or pull recorded"));
+        }
+        for op in operations {
+            let color = match op.outcome.as_str() {
+                "success" => Color::Green,
+                "failed" => Color::Red,
+                _ => Color::Yellow,
+            };
+            let exit_code = op
+                .exit_code
+                .map(|code| format!(" exit {}", code))
+                .unwrap_or_default();
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "  {} {:<5} {:<9} {:.1}s{}",
+                    op.started_at.format("%Y-%m-%d %H:%M"),
+                    op.kind,
+                    op.outcome,
+                    op.duration_ms as f64 / 1000.0,
+                    exit_code
+                ),
+                Style::default().fg(color),
+            )));
+        }
+        lines
+    }
+
     async fn refresh_repos(&mut self) -> Result<()> {
-        self.repos = match self.filter {
+        self.repos = match &self.filter {
             Filter::All => self.db.get_all_repositories().await?,
             Filter::GitHub => self.db.get_repositories_by_provider("github").await?,
             Filter::GitLab => self.db.get_repositories_by_provider("gitlab").await?,
@@ -179,7 +567,14 @@ impl App {
                     .get_repositories_by_status(CloneStatus::NotCloned)
                     .await?
             }
+            Filter::Starred => {
+                self.db
+                    .get_repositories_by_source(RepositorySource::Starred)
+                    .await?
+            }
+            Filter::Search(query) => self.db.search(query, FILTER_LIMIT).await?,
         };
+        self.tags = self.db.get_all_repository_tags().await?;
 
         // Adjust selection if needed
         if self.repos.is_empty() {
@@ -191,6 +586,9 @@ impl App {
         } else {
             self.selected.select(Some(0));
         }
+        if self.details.is_some() {
+            self.load_details().await?;
+        }
 
         Ok(())
     }
@@ -247,20 +645,25 @@ impl App {
                     _ => Style::default().fg(Color::White),
                 };
 
-                let content = format!(
+                let mut content = format!(
                     "{} {} {} {}",
                     status_icon, provider_icon, privacy, repo.full_name
                 );
+                if let Some(tags) = self.tags.get(&repo.id) {
+                    content.push_str(&format!("  [{}]", tags.join(", ")));
+                }
                 ListItem::new(content).style(style)
             })
             .collect();
 
-        let filter_text = match self.filter {
-            Filter::All => "All",
-            Filter::GitHub => "GitHub",
-            Filter::GitLab => "GitLab",
-            Filter::Cloned => "Cloned",
-            Filter::NotCloned => "Not Cloned",
+        let filter_text = match &self.filter {
+            Filter::All => "All".to_string(),
+            Filter::GitHub => "GitHub".to_string(),
+            Filter::GitLab => "GitLab".to_string(),
+            Filter::Cloned => "Cloned".to_string(),
+            Filter::NotCloned => "Not Cloned".to_string(),
+            Filter::Starred => "Starred".to_string(),
+            Filter::Search(query) => format!("Matching '{}'", query),
         };
 
         let repos_list = List::new(repos)
@@ -276,14 +679,86 @@ impl App {
             )
             .highlight_symbol(">> ");
 
-        f.render_stateful_widget(repos_list, chunks[1], &mut self.selected);
+        if let Some(search) = &mut self.search {
+            let hits: Vec<ListItem> = search
+                .hits
+                .iter()
+                .map(|(repository, found)| {
+                    ListItem::new(Line::from(vec![
+                        Span::styled(repository.clone(), Style::default().fg(Color::Cyan)),
+                        Span::raw(format!(
+                            " {}:{}: {}",
+                            found.path,
+                            found.line,
+                            found.text.trim()
+                        )),
+                    ]))
+                })
+                .collect();
+            let results = List::new(hits)
+                .block(Block::default().borders(Borders::ALL).title(format!(
+                    "Search '{}' ({} matches)",
+                    search.pattern,
+                    search.hits.len()
+                )))
+                .highlight_style(
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .highlight_symbol(">> ");
+            f.render_stateful_widget(results, chunks[1], &mut search.selected);
+            self.render_status(f, chunks[2]);
+            return;
+        }
+
+        let detail_text = match (&self.details, self.selected_repo()) {
+            (Some(operations), Some(repo)) => Some(Self::details_text(repo, operations)),
+            _ => None,
+        };
+        match detail_text {
+            Some(text) => {
+                let panes = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .split(chunks[1]);
+                f.render_stateful_widget(repos_list, panes[0], &mut self.selected);
+                let details = Paragraph::new(text)
+                    .wrap(Wrap { trim: false })
+                    .block(Block::default().borders(Borders::ALL).title("Details"));
+                f.render_widget(details, panes[1]);
+            }
+            None => f.render_stateful_widget(repos_list, chunks[1], &mut self.selected),
+        }
+
+        self.render_status(f, chunks[2]);
+    }
 
-        // Status bar
-        let status = Paragraph::new(self.status_message.clone())
+    /// Status bar, or the prompt of the filter, tag editor or search while one is typed
+    fn render_status(&self, f: &mut Frame, area: Rect) {
+        let (title, text) = if let Some(input) = &self.filter_input {
+            (
+                "Filter repositories (Enter=keep, Esc=clear)",
+                format!("{}█", input),
+            )
+        } else if let Some(input) = &self.tag_input {
+            (
+                "Tags, comma-separated (Enter=save, Esc=cancel)",
+                format!("{}█", input),
+            )
+        } else if let Some(input) = &self.search_input {
+            (
+                "Search code (Enter=search, Esc=cancel)",
+                format!("/{}█", input),
+            )
+        } else {
+            ("Status", self.status_message.clone())
+        };
+        let status = Paragraph::new(text)
             .style(Style::default())
             .wrap(Wrap { trim: true })
-            .block(Block::default().borders(Borders::ALL).title("Status"));
+            .block(Block::default().borders(Borders::ALL).title(title));
 
-        f.render_widget(status, chunks[2]);
+        f.render_widget(status, area);
     }
 }
@@ -0,0 +1,163 @@
+use crate::git::GitOperations;
+use crate::importers::local::find_git_repositories;
+use crate::models::{CloneStatus, Repository};
+use crate::remote::same_repository;
+use crate::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// A mismatch between the database and the disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    /// Recorded as cloned, but the directory is gone
+    MissingPath,
+    /// Recorded as cloned, but the directory is not a git working tree
+    NotGitRepository,
+    /// Recorded as cloned, but `origin` is another repository or missing
+    OriginMismatch,
+    /// A clone of a known repository that is not recorded as cloned there
+    UnrecordedClone,
+    /// A clone below the clone path that matches no repository record
+    UnknownClone,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::MissingPath => write!(f, "clone missing"),
+            Problem::NotGitRepository => write!(f, "not a git repository"),
+            Problem::OriginMismatch => write!(f, "origin mismatch"),
+            Problem::UnrecordedClone => write!(f, "clone not recorded"),
+            Problem::UnknownClone => write!(f, "unknown clone"),
+        }
+    }
+}
+
+/// One problem found by [`verify`]
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub problem: Problem,
+    /// Record concerned, `None` for unknown clones
+    pub repository: Option<Repository>,
+    /// Directory concerned
+    pub path: PathBuf,
+    /// `origin` URL of the clone, when there is one
+    pub origin: Option<String>,
+}
+
+impl Finding {
+    /// The record as it should be stored to match the disk, `None` when the
+    /// database has nothing to fix
+    pub fn fixed_repository(&self) -> Option<Repository> {
+        let mut repo = self.repository.clone()?;
+        match self.problem {
+            Problem::MissingPath | Problem::NotGitRepository | Problem::OriginMismatch => {
+                repo.local_path = None;
+                repo.update_status(CloneStatus::NotCloned);
+            }
+            Problem::UnrecordedClone => {
+                repo.set_local_path(self.path.to_string_lossy().to_string());
+                repo.update_status(CloneStatus::Cloned);
+            }
+            Problem::UnknownClone => return None,
+        }
+        Some(repo)
+    }
+}
+
+/// Reconcile the records with the disk.
+///
+/// Every record marked as cloned is checked for its directory, a git working
+/// tree in it and an `origin` pointing at the recorded repository. Clones below
+/// `clone_root` that no such record points at are matched to records by their
+/// `origin`: a repository not (correctly) recorded as cloned adopts it, any
+/// other clone is reported as unknown, unless a record already points at it.
+pub fn verify(
+    repos: &[Repository],
+    clone_root: &Path,
+    git_ops: &GitOperations,
+) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    let mut verified = HashSet::new();
+    // Recorded clones with a problem, reported already
+    let mut flagged = HashSet::new();
+    // Records that could point at a clone found on disk
+    let mut adoptable = HashSet::new();
+
+    for repo in repos {
+        let recorded = repo.status == CloneStatus::Cloned.to_string();
+        let path = repo.local_path.as_deref().map(PathBuf::from);
+        let Some(path) = path.filter(|_| recorded) else {
+            adoptable.insert(repo.id.clone());
+            continue;
+        };
+
+        let problem = if !path.exists() {
+            Some((Problem::MissingPath, None))
+        } else if !path.join(".git").exists() {
+            Some((Problem::NotGitRepository, None))
+        } else {
+            let origin = git_ops.get_remote_url(&path.to_string_lossy(), "origin")?;
+            let matches = origin
+                .as_deref()
+                .is_some_and(|origin| is_clone_of(origin, repo));
+            (!matches).then_some((Problem::OriginMismatch, origin))
+        };
+        match problem {
+            Some((problem, origin)) => {
+                adoptable.insert(repo.id.clone());
+                flagged.insert(path.canonicalize().unwrap_or_else(|_| path.clone()));
+                findings.push(Finding {
+                    problem,
+                    repository: Some(repo.clone()),
+                    path,
+                    origin,
+                });
+            }
+            None => {
+                verified.insert(path.canonicalize().unwrap_or(path));
+            }
+        }
+    }
+
+    if clone_root.exists() {
+        for path in find_git_repositories(clone_root)? {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if verified.contains(&canonical) {
+                continue;
+            }
+            let origin = git_ops.get_remote_url(&path.to_string_lossy(), "origin")?;
+            let owner = origin.as_deref().and_then(|origin| {
+                repos
+                    .iter()
+                    .find(|repo| adoptable.contains(&repo.id) && is_clone_of(origin, repo))
+            });
+            let finding = match owner {
+                Some(repo) => {
+                    // A second clone of the same repository stays unknown
+                    adoptable.remove(&repo.id);
+                    Finding {
+                        problem: Problem::UnrecordedClone,
+                        repository: Some(repo.clone()),
+                        path,
+                        origin,
+                    }
+                }
+                // A clone of another repository where a record expects its own
+                None if flagged.contains(&canonical) => continue,
+                None => Finding {
+                    problem: Problem::UnknownClone,
+                    repository: None,
+                    path,
+                    origin,
+                },
+            };
+            findings.push(finding);
+        }
+    }
+    Ok(findings)
+}
+
+fn is_clone_of(origin: &str, repo: &Repository) -> bool {
+    same_repository(origin, &repo.clone_url_https) || same_repository(origin, &repo.clone_url_ssh)
+}
@@ -106,3 +106,1809 @@ fn test_config_default_gitlab_url() {
     // (This will vary based on env, so we just check it's an Option)
     assert!(config.gitlab_base_url.is_none() || config.gitlab_base_url.is_some());
 }
+
+#[test]
+fn test_affiliation_parsing() {
+    use super_clone::providers::{Affiliation, AuthenticatedDiscovery};
+
+    assert_eq!("owner".parse::<Affiliation>().unwrap(), Affiliation::Owner);
+    assert_eq!(
+        "organization-member".parse::<Affiliation>().unwrap(),
+        Affiliation::OrganizationMember
+    );
+    assert!("stranger".parse::<Affiliation>().is_err());
+    assert_eq!(
+        Affiliation::OrganizationMember.to_string(),
+        "organization_member"
+    );
+
+    assert!(AuthenticatedDiscovery::default().owner_only());
+    assert!(!AuthenticatedDiscovery::all().owner_only());
+    let none = AuthenticatedDiscovery {
+        affiliations: Vec::new(),
+        min_access_level: None,
+    };
+    assert!(!none.owner_only());
+}
+
+#[tokio::test]
+async fn test_database_source_filter() {
+    use super_clone::database::RepositoryDatabase;
+    use super_clone::models::RepositorySource;
+
+    let db = RepositoryDatabase::new(":memory:").await.unwrap();
+
+    let work = Repository::new(
+        "service".to_string(),
+        "team/service".to_string(),
+        "team".to_string(),
+        Provider::GitHub,
+        "https://github.com/team/service.git".to_string(),
+        "git@github.com:team/service.git".to_string(),
+        None,
+        true,
+    );
+    let mut starred = Repository::new(
+        "tokio".to_string(),
+        "tokio-rs/tokio".to_string(),
+        "tokio-rs".to_string(),
+        Provider::GitHub,
+        "https://github.com/tokio-rs/tokio.git".to_string(),
+        "git@github.com:tokio-rs/tokio.git".to_string(),
+        None,
+        false,
+    );
+    starred.set_source(RepositorySource::Starred);
+
+    db.create_repository(&work).await.unwrap();
+    db.create_repository(&starred).await.unwrap();
+
+    let repos = db
+        .get_repositories_by_source(RepositorySource::Starred)
+        .await
+        .unwrap();
+    assert_eq!(repos.len(), 1);
+    assert_eq!(repos[0].full_name, "tokio-rs/tokio");
+    assert_eq!(repos[0].source, "starred");
+
+    // A discovered repository that gets starred is found through both
+    let mut work = work;
+    assert!(work.add_source(RepositorySource::Starred));
+    db.update_repository(&work).await.unwrap();
+    let starred = db
+        .get_repositories_by_source(RepositorySource::Starred)
+        .await
+        .unwrap();
+    assert_eq!(starred.len(), 2);
+    let discovered = db
+        .get_repositories_by_source(RepositorySource::Discovered)
+        .await
+        .unwrap();
+    assert_eq!(discovered.len(), 1);
+    assert_eq!(discovered[0].source, "discovered,starred");
+}
+
+#[tokio::test]
+async fn test_full_text_search() {
+    use super_clone::database::RepositoryDatabase;
+
+    let db = RepositoryDatabase::new(":memory:").await.unwrap();
+    let repo = |full_name: &str, description: Option<&str>| {
+        let (owner, name) = full_name.split_once('/').unwrap();
+        Repository::new(
+            name.to_string(),
+            full_name.to_string(),
+            owner.to_string(),
+            Provider::GitHub,
+            format!("https://github.com/{}.git", full_name),
+            format!("git@github.com:{}.git", full_name),
+            description.map(|d| d.to_string()),
+            false,
+        )
+    };
+
+    let payments = repo("acme/payments", Some("Double-entry ledger and payouts"));
+    let mut ledger = repo("acme/ledger-tools", None);
+    ledger.set_topics(&["accounting".to_string()]);
+    let mut web = repo("acme/web", Some("Marketing site"));
+    db.create_repository(&payments).await.unwrap();
+    db.create_repository(&ledger).await.unwrap();
+    db.create_repository(&web).await.unwrap();
+
+    // Name matches rank above description matches; words match by prefix
+    let names = |repos: Vec<Repository>| -> Vec<String> {
+        repos.into_iter().map(|r| r.full_name).collect()
+    };
+    assert_eq!(
+        names(db.search("ledg", 10).await.unwrap()),
+        vec!["acme/ledger-tools", "acme/payments"]
+    );
+    assert_eq!(
+        names(db.search("payouts ledger", 10).await.unwrap()),
+        vec!["acme/payments"]
+    );
+    assert_eq!(
+        names(db.search("accounting", 10).await.unwrap()),
+        vec!["acme/ledger-tools"]
+    );
+    assert_eq!(db.search("acme", 2).await.unwrap().len(), 2);
+    assert!(db.search("\"(*", 10).await.unwrap().is_empty());
+
+    // The index follows updates and deletes
+    web.description = Some("Ledger dashboards".to_string());
+    db.update_repository(&web).await.unwrap();
+    db.delete_repository(&payments.id).await.unwrap();
+    assert_eq!(
+        names(db.search("ledger", 10).await.unwrap()),
+        vec!["acme/ledger-tools", "acme/web"]
+    );
+    assert!(db.search("payouts", 10).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_tags_and_tag_rules() {
+    use super_clone::database::RepositoryDatabase;
+    use super_clone::filter::RepositoryFilter;
+    use super_clone::models::{TagRule, TagRuleKind};
+
+    let db = RepositoryDatabase::new(":memory:").await.unwrap();
+    let repo = |full_name: &str| {
+        let (owner, name) = full_name.split_once('/').unwrap();
+        Repository::new(
+            name.to_string(),
+            full_name.to_string(),
+            owner.to_string(),
+            Provider::GitHub,
+            format!("https://github.com/{}.git", full_name),
+            format!("git@github.com:{}.git", full_name),
+            None,
+            false,
+        )
+    };
+    let mut ledger = repo("acme/ledger");
+    ledger.set_topics(&["payments".to_string()]);
+    let billing = repo("acme/billing-api");
+    let web = repo("acme/web");
+    for r in [&ledger, &billing, &web] {
+        db.create_repository(r).await.unwrap();
+    }
+
+    assert!(db.add_tag(&web.id, "deprecated").await.unwrap());
+    assert!(!db.add_tag(&web.id, "deprecated").await.unwrap());
+
+    db.create_tag_rule(&TagRule::new(
+        "payments".to_string(),
+        TagRuleKind::Topic,
+        "pay*".to_string(),
+    ))
+    .await
+    .unwrap();
+    db.create_tag_rule(&TagRule::new(
+        "payments".to_string(),
+        TagRuleKind::Name,
+        "acme/billing-*".to_string(),
+    ))
+    .await
+    .unwrap();
+    let all = db.get_all_repositories().await.unwrap();
+    assert_eq!(db.apply_tag_rules(&all).await.unwrap(), 2);
+    assert_eq!(db.apply_tag_rules(&all).await.unwrap(), 0);
+
+    assert_eq!(
+        db.get_tags().await.unwrap(),
+        vec![("deprecated".to_string(), 1), ("payments".to_string(), 2)]
+    );
+    assert_eq!(
+        db.get_repository_tags(&ledger.id).await.unwrap(),
+        vec!["payments"]
+    );
+
+    let filter = RepositoryFilter {
+        tagged: Some(
+            db.get_tagged_repository_ids(&["payments".to_string()])
+                .await
+                .unwrap(),
+        ),
+        ..Default::default()
+    };
+    let names: Vec<String> = filter.apply(all).into_iter().map(|r| r.full_name).collect();
+    assert_eq!(names, vec!["acme/billing-api", "acme/ledger"]);
+
+    // Removing a link keeps the tag; deleting the tag drops its links and rules
+    assert!(db.remove_tag(&ledger.id, "payments").await.unwrap());
+    assert!(!db.remove_tag(&ledger.id, "payments").await.unwrap());
+    assert!(db.delete_tag("payments").await.unwrap());
+    assert!(db.get_tag_rules().await.unwrap().is_empty());
+    assert!(db
+        .get_repository_tags(&billing.id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Tags go away with their repository
+    db.delete_repository(&web.id).await.unwrap();
+    assert_eq!(
+        db.get_tags().await.unwrap(),
+        vec![("deprecated".to_string(), 0)]
+    );
+}
+
+#[tokio::test]
+async fn test_inventory_round_trip_and_merge() {
+    use super_clone::database::RepositoryDatabase;
+    use super_clone::inventory::{Inventory, InventoryFormat, MergeSummary};
+    use super_clone::models::CloneStatus;
+
+    let clone_dir =
+        std::env::temp_dir().join(format!("super-clone-inventory-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(clone_dir.join(".git")).unwrap();
+
+    let db = RepositoryDatabase::new(":memory:").await.unwrap();
+    let mut api = Repository::new(
+        "api".to_string(),
+        "acme/api".to_string(),
+        "acme".to_string(),
+        Provider::GitHub,
+        "https://github.com/acme/api.git".to_string(),
+        "git@github.com:acme/api.git".to_string(),
+        Some("Public API, \"v2\"\nwith quotes".to_string()),
+        true,
+    );
+    api.has_wiki = true;
+    api.set_topics(&["rest".to_string(), "payments".to_string()]);
+    api.set_sparse_paths(&["docs".to_string()]);
+    api.clone_depth = Some(1);
+    api.set_local_path(clone_dir.to_string_lossy().to_string());
+    api.update_status(CloneStatus::Cloned);
+    api.update_pulled_at();
+    let wiki = api.wiki_repository();
+    let mut web = Repository::new(
+        "web".to_string(),
+        "acme/web".to_string(),
+        "acme".to_string(),
+        Provider::GitLab,
+        "https://gitlab.com/acme/web.git".to_string(),
+        "git@gitlab.com:acme/web.git".to_string(),
+        None,
+        false,
+    );
+    // A clone that only exists on the exporting machine
+    web.set_local_path("/nonexistent/acme/web".to_string());
+    web.update_status(CloneStatus::Cloned);
+    for repo in [&api, &wiki, &web] {
+        db.create_repository(repo).await.unwrap();
+    }
+    db.add_tag(&api.id, "backend").await.unwrap();
+    db.add_tag(&api.id, "payments").await.unwrap();
+
+    let exported = Inventory::export(&db).await.unwrap();
+    assert_eq!(exported.repositories.len(), 3);
+    let records = |inventory: &Inventory| {
+        let mut records: Vec<_> = inventory
+            .repositories
+            .iter()
+            .map(|record| serde_json::to_value(record).unwrap())
+            .collect();
+        records.sort_by_key(|record| record["repository"]["full_name"].to_string());
+        records
+    };
+
+    for format in [InventoryFormat::Json, InventoryFormat::Csv] {
+        let mut dump = Vec::new();
+        exported.write(format, &mut dump).unwrap();
+        let inventory = Inventory::read(format, dump.as_slice()).unwrap();
+        assert_eq!(records(&inventory), records(&exported), "{}", format);
+
+        let other = RepositoryDatabase::new(":memory:").await.unwrap();
+        let summary = inventory.merge(&other, false).await.unwrap();
+        assert_eq!(
+            summary,
+            MergeSummary {
+                added: 3,
+                updated: 0,
+                unchanged: 0,
+                tags_added: 2,
+            }
+        );
+        let merged = Inventory::export(&other).await.unwrap();
+        let mut expected = records(&exported);
+        // The clone missing on this machine is recorded as not cloned
+        expected[2]["repository"]["local_path"] = serde_json::Value::Null;
+        expected[2]["repository"]["status"] = "not_cloned".into();
+        assert_eq!(records(&merged), expected, "{}", format);
+
+        // Merging again changes nothing
+        let again = inventory.merge(&other, false).await.unwrap();
+        assert_eq!(again.unchanged, 3);
+        assert_eq!(again.tags_added, 0);
+    }
+
+    // A machine that knows acme/api under another ID, cloned elsewhere, with
+    // older metadata, and acme/web with newer metadata
+    let local = RepositoryDatabase::new(":memory:").await.unwrap();
+    let mut old_api = Repository::new(
+        "api".to_string(),
+        "acme/api".to_string(),
+        "acme".to_string(),
+        Provider::GitHub,
+        "https://github.com/acme/api.git".to_string(),
+        "git@github.com:acme/api.git".to_string(),
+        Some("Old description".to_string()),
+        false,
+    );
+    old_api.set_local_path("/home/me/src/api".to_string());
+    old_api.update_status(CloneStatus::Cloned);
+    old_api.metadata_updated_at = Some(api.metadata_updated() - chrono::Duration::days(1));
+    // Pulled after the export, which leaves the metadata as old as it was
+    old_api.update_pulled_at();
+    assert!(old_api.updated_at > api.updated_at);
+    let mut new_web = web.clone();
+    new_web.id = uuid::Uuid::new_v4().to_string();
+    new_web.description = Some("Renamed upstream".to_string());
+    new_web.metadata_updated_at = Some(web.metadata_updated() + chrono::Duration::days(1));
+    local.create_repository(&old_api).await.unwrap();
+    local.create_repository(&new_web).await.unwrap();
+    local.add_tag(&old_api.id, "mine").await.unwrap();
+
+    let preview = exported.merge(&local, true).await.unwrap();
+    assert_eq!(
+        preview,
+        MergeSummary {
+            added: 1,
+            updated: 1,
+            unchanged: 1,
+            tags_added: 2,
+        }
+    );
+    assert_eq!(local.get_all_repositories().await.unwrap().len(), 2);
+    assert_eq!(exported.merge(&local, false).await.unwrap(), preview);
+
+    let merged_api = local.get_repository(&old_api.id).await.unwrap().unwrap();
+    assert_eq!(merged_api.description, api.description);
+    assert!(merged_api.is_private);
+    assert_eq!(merged_api.local_path.as_deref(), Some("/home/me/src/api"));
+    assert_eq!(merged_api.clone_depth, None);
+    assert_eq!(
+        local.get_repository_tags(&old_api.id).await.unwrap(),
+        vec!["backend", "mine", "payments"]
+    );
+    let merged_web = local.get_repository(&new_web.id).await.unwrap().unwrap();
+    assert_eq!(merged_web.description.as_deref(), Some("Renamed upstream"));
+    // The wiki is linked to the local record of its repository
+    let merged_wiki = local
+        .get_repository_by_full_name("acme/api.wiki")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(merged_wiki.parent_id, Some(old_api.id.clone()));
+
+    std::fs::remove_dir_all(&clone_dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_manifest_entry_clone_and_checkout() {
+    use std::process::Command;
+    use super_clone::git::GitOperations;
+    use super_clone::manifest::Manifest;
+
+    let source = create_source_repo("manifest", 3);
+    let rev = |rev: &str| {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&source)
+            .args(["rev-parse", rev])
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    };
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(&source)
+        .args(["branch", "release", "HEAD~1"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let manifest = Manifest::parse(&format!(
+        "[[repository]]\nname = \"acme/ledger\"\nurl = \"{}\"\nprovider = \"github\"\npath = \"team/ledger\"\nbranch = \"release\"\nsingle_branch = true\n",
+        source.display()
+    ))
+    .unwrap();
+    let entry = &manifest.repositories[0];
+    let repo = entry.to_repository(None).unwrap();
+
+    let base = std::env::temp_dir().join(format!("super-clone-apply-{}", uuid::Uuid::new_v4()));
+    let git_ops = GitOperations::new(base.clone());
+    let target = entry.target_path(&base).unwrap();
+    let path = git_ops
+        .clone_repository_to(&repo, &target, &entry.clone_options().unwrap(), false, None)
+        .await
+        .unwrap();
+    assert_eq!(target, base.join("team/ledger"));
+    let content = || std::fs::read_to_string(target.join("file.txt")).unwrap();
+    assert_eq!(content(), "version 1");
+
+    // Pinning fetches nothing new here, it only detaches at the commit
+    git_ops
+        .checkout_revision(&path, &rev("HEAD~2"))
+        .await
+        .unwrap();
+    assert_eq!(content(), "version 0");
+    let status = git_ops.backend().status(&target).await.unwrap();
+    assert_eq!(status.branch, None);
+    assert_eq!(status.head, Some(rev("HEAD~2")));
+
+    // main was never fetched into this single-branch clone
+    git_ops.switch_branch(&path, "main").await.unwrap();
+    assert_eq!(content(), "version 2");
+
+    // and pulls keep following it
+    std::fs::write(source.join("file.txt"), "version 3").unwrap();
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(&source)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(["commit", "-q", "-am", "commit 3"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    git_ops.pull_repository(&path).await.unwrap();
+    assert_eq!(content(), "version 3");
+
+    let _ = std::fs::remove_dir_all(&source);
+    let _ = std::fs::remove_dir_all(&base);
+}
+
+#[tokio::test]
+async fn test_existing_checkout_without_origin_is_not_cloned() {
+    use std::process::Command;
+    use super_clone::git::GitOperations;
+
+    let base = std::env::temp_dir().join(format!("super-clone-origin-{}", uuid::Uuid::new_v4()));
+    let target = base.join("acme").join("ledger");
+    std::fs::create_dir_all(&target).unwrap();
+    let status = Command::new("git")
+        .arg("init")
+        .arg("-q")
+        .arg(&target)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let repo = Repository::new(
+        "ledger".to_string(),
+        "acme/ledger".to_string(),
+        "acme".to_string(),
+        Provider::GitHub,
+        "https://github.com/acme/ledger.git".to_string(),
+        "git@github.com:acme/ledger.git".to_string(),
+        None,
+        false,
+    );
+    let git_ops = GitOperations::new(base.clone());
+    let result = git_ops
+        .clone_repository_to(&repo, &target, &repo.clone_options(), false, None)
+        .await;
+    assert!(result.unwrap_err().to_string().contains("no origin remote"));
+
+    let _ = std::fs::remove_dir_all(&base);
+}
+
+#[test]
+fn test_find_and_scan_local_clones() {
+    use std::process::Command;
+    use super_clone::git::GitOperations;
+    use super_clone::importers::local::{find_git_repositories, scan};
+
+    let root = std::env::temp_dir().join(format!("super-clone-import-{}", uuid::Uuid::new_v4()));
+    let with_origin = root.join("work").join("service");
+    let without_origin = root.join("scratch");
+    let inner = with_origin.join("vendor").join("nested");
+    std::fs::create_dir_all(&inner).unwrap();
+    std::fs::create_dir_all(&without_origin).unwrap();
+
+    for dir in [&with_origin, &without_origin, &inner] {
+        let status = Command::new("git")
+            .arg("init")
+            .arg("-q")
+            .arg(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(&with_origin)
+        .args(["remote", "add", "origin", "git@github.com:team/service.git"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // Clones are not descended into
+    let found = find_git_repositories(&root).unwrap();
+    assert_eq!(found, vec![without_origin.clone(), with_origin.clone()]);
+
+    let git_ops = GitOperations::new(root.clone());
+    let clones = scan(&root, &git_ops, None, None).unwrap();
+    assert_eq!(clones.len(), 2);
+
+    let scratch = clones.iter().find(|c| c.path.ends_with("scratch")).unwrap();
+    assert!(scratch.repository.is_err());
+
+    let service = clones.iter().find(|c| c.path.ends_with("service")).unwrap();
+    let repo = service.repository.as_ref().unwrap();
+    assert_eq!(repo.full_name, "team/service");
+    assert_eq!(repo.owner, "team");
+    assert_eq!(repo.provider, "github");
+    assert_eq!(repo.status, "cloned");
+    assert_eq!(repo.clone_url_https, "https://github.com/team/service.git");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_import_repo_manifest_and_ghq_root() {
+    use std::process::Command;
+    use super_clone::git::GitOperations;
+    use super_clone::importers::{ghq, repo_manifest};
+
+    let root = std::env::temp_dir().join(format!("super-clone-import-{}", uuid::Uuid::new_v4()));
+    let manifests = root.join(".repo").join("manifests");
+    std::fs::create_dir_all(&manifests).unwrap();
+    std::fs::write(
+        manifests.join("default.xml"),
+        r#"<manifest>
+  <remote name="origin" fetch="https://github.com/acme" />
+  <default remote="origin" revision="refs/heads/main" />
+  <include name="tools.xml" />
+  <project name="app" path="src/app" revision="refs/tags/v2" />
+</manifest>"#,
+    )
+    .unwrap();
+    std::fs::write(
+        manifests.join("tools.xml"),
+        r#"<manifest><project name="lint" path="tools/lint" /></manifest>"#,
+    )
+    .unwrap();
+
+    let checkout_root = repo_manifest::checkout_root(&manifests.join("default.xml")).unwrap();
+    assert_eq!(checkout_root, root.canonicalize().unwrap());
+    let entries = repo_manifest::read(&manifests.join("default.xml"), &root, None).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].url, "https://github.com/acme/lint");
+    assert_eq!(entries[0].path, root.join("tools/lint"));
+    assert_eq!(entries[0].branch().as_deref(), Some("main"));
+    assert_eq!(entries[1].url, "https://github.com/acme/app");
+    assert_eq!(entries[1].branch().as_deref(), Some("v2"));
+    let app = entries[1].to_repository(None, None).unwrap();
+    assert_eq!(app.full_name, "acme/app");
+    assert_eq!(app.status, "not_cloned");
+
+    // A ghq root with one clone that has an origin and one that has none
+    let ghq_root = root.join("ghq");
+    let with_origin = ghq_root.join("github.com/acme/api");
+    let without_origin = ghq_root.join("gitlab.com/acme/web");
+    for dir in [&with_origin, &without_origin] {
+        std::fs::create_dir_all(dir).unwrap();
+        let status = Command::new("git")
+            .arg("init")
+            .arg("-q")
+            .arg(dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(&with_origin)
+        .args(["remote", "add", "origin", "git@github.com:acme/api.git"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let git_ops = GitOperations::new(root.clone());
+    let entries = ghq::scan(&ghq_root, &git_ops).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].url, "git@github.com:acme/api.git");
+    assert_eq!(entries[1].url, "https://gitlab.com/acme/web.git");
+    let web = entries[1].to_repository(None, None).unwrap();
+    assert_eq!(web.provider, "gitlab");
+    assert_eq!(web.status, "cloned");
+    assert_eq!(
+        web.local_path,
+        Some(
+            without_origin
+                .canonicalize()
+                .unwrap()
+                .to_string_lossy()
+                .to_string()
+        )
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+/// Create a local repository with a few commits to clone from
+fn create_source_repo(name: &str, commits: usize) -> std::path::PathBuf {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("super-clone-{}-{}", name, uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&dir)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q", "-b", "main"]);
+    for i in 0..commits {
+        std::fs::write(dir.join("file.txt"), format!("version {}", i)).unwrap();
+        git(&["add", "file.txt"]);
+        git(&["commit", "-q", "-m", &format!("commit {}", i)]);
+    }
+    dir
+}
+
+#[tokio::test]
+async fn test_shallow_clone_and_unshallow() {
+    use std::process::Command;
+    use super_clone::git::GitOperations;
+    use super_clone::models::CloneOptions;
+
+    let source = create_source_repo("shallow-source", 3);
+    let base = std::env::temp_dir().join(format!("super-clone-shallow-{}", uuid::Uuid::new_v4()));
+    let url = format!("file://{}", source.display());
+
+    let mut repo = Repository::new(
+        "source".to_string(),
+        "local/source".to_string(),
+        "local".to_string(),
+        Provider::GitHub,
+        url.clone(),
+        url,
+        None,
+        false,
+    );
+    repo.set_clone_options(&CloneOptions::new(Some(1), None, false).unwrap());
+
+    let git_ops = GitOperations::new(base.clone());
+    let path = git_ops.clone_repository(&repo, false).await.unwrap();
+
+    let is_shallow = || {
+        let output = Command::new("git")
+            .args(["-C", &path, "rev-parse", "--is-shallow-repository"])
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    assert_eq!(is_shallow(), "true");
+
+    git_ops
+        .pull_repository_with_options(&path, &repo.clone_options())
+        .await
+        .unwrap();
+    assert_eq!(is_shallow(), "true");
+
+    // Upstream moves on; the shallow clone must still fast-forward
+    for i in 0..2 {
+        std::fs::write(source.join("file.txt"), format!("upstream {}", i)).unwrap();
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&source)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(["commit", "-q", "-am", &format!("upstream {}", i)])
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+    git_ops
+        .pull_repository_with_options(&path, &repo.clone_options())
+        .await
+        .unwrap();
+    assert_eq!(is_shallow(), "true");
+    assert_eq!(
+        std::fs::read_to_string(std::path::Path::new(&path).join("file.txt")).unwrap(),
+        "upstream 1"
+    );
+
+    git_ops
+        .unshallow_repository(&path, &repo.clone_options())
+        .await
+        .unwrap();
+    assert_eq!(is_shallow(), "false");
+
+    std::fs::remove_dir_all(&source).unwrap();
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[tokio::test]
+async fn test_prune_plan_and_trash() {
+    use std::collections::{HashMap, HashSet};
+    use std::process::Command;
+    use super_clone::database::RepositoryDatabase;
+    use super_clone::git::GitOperations;
+    use super_clone::models::CloneStatus;
+    use super_clone::providers::UpstreamState;
+    use super_clone::prune::{plan, Disposal, PruneReason};
+
+    let source = create_source_repo("prune-source", 1);
+    let base = std::env::temp_dir().join(format!("super-clone-prune-{}", uuid::Uuid::new_v4()));
+    let root = base.join("clones");
+    let trash = base.join("trash");
+    let url = format!("file://{}", source.display());
+    let git_ops = GitOperations::new(root.clone());
+    let db = RepositoryDatabase::new(":memory:").await.unwrap();
+
+    let mut repos = Vec::new();
+    for name in [
+        "kept", "archived", "deleted", "excluded", "unpushed", "missing",
+    ] {
+        let mut repo = Repository::new(
+            name.to_string(),
+            format!("acme/{}", name),
+            "acme".to_string(),
+            Provider::GitHub,
+            url.clone(),
+            url.clone(),
+            None,
+            false,
+        );
+        let path = git_ops.clone_repository(&repo, false).await.unwrap();
+        repo.set_local_path(path);
+        repo.update_status(CloneStatus::Cloned);
+        db.create_repository(&repo).await.unwrap();
+        repos.push(repo);
+    }
+    let path_of = |name: &str| root.join("acme").join(name);
+    let git = |dir: &std::path::Path, args: &[&str]| {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(
+        &path_of("unpushed"),
+        &["commit", "-q", "--allow-empty", "-m", "local"],
+    );
+    std::fs::remove_dir_all(path_of("missing")).unwrap();
+    std::fs::create_dir_all(root.join("stray")).unwrap();
+    git(&root.join("stray"), &["init", "-q"]);
+
+    let id_of = |name: &str| repos.iter().find(|r| r.name == name).unwrap().id.clone();
+    let selected: HashSet<String> = repos
+        .iter()
+        .filter(|r| r.name != "excluded" && r.name != "unpushed")
+        .map(|r| r.id.clone())
+        .collect();
+    let upstream = HashMap::from([
+        (id_of("kept"), UpstreamState::Active),
+        (id_of("archived"), UpstreamState::Archived),
+        (id_of("deleted"), UpstreamState::Missing),
+    ]);
+    let items = plan(&repos, Some(&selected), &upstream, &root, &trash, &git_ops).unwrap();
+    let reasons: HashMap<String, PruneReason> = items
+        .iter()
+        .map(|item| (item.label(), item.reason))
+        .collect();
+    assert_eq!(items.len(), 6);
+    assert!(!reasons.contains_key("acme/kept"));
+    assert_eq!(reasons["acme/archived"], PruneReason::Archived);
+    assert_eq!(reasons["acme/deleted"], PruneReason::DeletedUpstream);
+    assert_eq!(reasons["acme/excluded"], PruneReason::Excluded);
+    assert_eq!(reasons["acme/unpushed"], PruneReason::Excluded);
+    assert_eq!(reasons["acme/missing"], PruneReason::MissingClone);
+    let stray = items
+        .iter()
+        .find(|item| item.reason == PruneReason::Untracked)
+        .unwrap();
+    assert_eq!(stray.path.as_deref(), Some(root.join("stray").as_path()));
+
+    let unpushed = items.iter().find(|i| i.label() == "acme/unpushed").unwrap();
+    assert_eq!(unpushed.blocked_by, vec!["1 unpushed commits"]);
+    assert!(unpushed
+        .execute(&db, &root, &Disposal::Delete)
+        .await
+        .is_err());
+    assert!(path_of("unpushed").exists());
+
+    let disposal = Disposal::Trash(trash.join("run"));
+    for item in items.iter().filter(|item| !item.is_blocked()) {
+        item.execute(&db, &root, &disposal).await.unwrap();
+    }
+    assert!(trash.join("run/acme/archived/.git").exists());
+    assert!(trash.join("run/stray/.git").exists());
+    assert!(!path_of("excluded").exists());
+    assert!(path_of("kept").exists());
+
+    let archived = db
+        .get_repository_by_full_name("acme/archived")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(archived.status, "not_cloned");
+    assert_eq!(archived.local_path, None);
+    let missing = db.get_repository(&id_of("missing")).await.unwrap().unwrap();
+    assert_eq!(missing.status, "not_cloned");
+    assert!(db
+        .get_repository(&id_of("deleted"))
+        .await
+        .unwrap()
+        .is_none());
+
+    // Nothing left to do but the clone with local work
+    let repos = db.get_all_repositories().await.unwrap();
+    let items = plan(&repos, Some(&selected), &upstream, &root, &trash, &git_ops).unwrap();
+    assert_eq!(items.len(), 1);
+    assert!(items[0].is_blocked());
+
+    std::fs::remove_dir_all(&base).unwrap();
+    std::fs::remove_dir_all(&source).unwrap();
+}
+
+#[tokio::test]
+async fn test_mirror_and_update() {
+    use std::process::Command;
+    use super_clone::git::GitOperations;
+
+    let source = create_source_repo("mirror-source", 1);
+    let root = std::env::temp_dir().join(format!("super-clone-mirror-{}", uuid::Uuid::new_v4()));
+    let url = format!("file://{}", source.display());
+    let repo = Repository::new(
+        "source".to_string(),
+        "local/source".to_string(),
+        "local".to_string(),
+        Provider::GitHub,
+        url.clone(),
+        url,
+        None,
+        false,
+    );
+
+    let git_ops = GitOperations::new(root.join("clones"));
+    let mirror_path = git_ops.get_mirror_path(&root.join("mirrors"), &repo);
+    assert!(mirror_path.ends_with("local/source.git"));
+
+    git_ops
+        .mirror_repository(&repo, &mirror_path, false, false)
+        .await
+        .unwrap();
+
+    let head = |dir: &std::path::Path| {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["rev-parse", "refs/heads/main"])
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    assert_eq!(head(&mirror_path), head(&source));
+
+    // New upstream commits arrive with an update
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(&source)
+        .args([
+            "-c",
+            "user.name=Test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "-q",
+            "--allow-empty",
+            "-m",
+            "more",
+        ])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    git_ops
+        .update_mirror(&mirror_path.to_string_lossy())
+        .await
+        .unwrap();
+    assert_eq!(head(&mirror_path), head(&source));
+
+    // Pull request refs come and go with the flag
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(&source)
+        .args(["update-ref", "refs/pull/1/head", "HEAD"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let mirror = mirror_path.to_string_lossy().to_string();
+    let has_pull_ref = || {
+        git_ops
+            .list_refs(&mirror)
+            .unwrap()
+            .contains_key("refs/pull/1/head")
+    };
+    git_ops.sync_mirror(&mirror, false).await.unwrap();
+    assert!(!has_pull_ref());
+    git_ops
+        .mirror_repository(&repo, &mirror_path, false, true)
+        .await
+        .unwrap();
+    assert!(has_pull_ref());
+    git_ops.sync_mirror(&mirror, false).await.unwrap();
+    assert!(!has_pull_ref());
+    assert_eq!(head(&mirror_path), head(&source));
+
+    // Neither another directory nor a working tree is taken over as a mirror
+    let other = root.join("mirrors").join("other.git");
+    std::fs::create_dir_all(&other).unwrap();
+    assert!(git_ops
+        .mirror_repository(&repo, &other, false, false)
+        .await
+        .is_err());
+    assert!(git_ops
+        .sync_mirror(&source.to_string_lossy(), false)
+        .await
+        .is_err());
+
+    std::fs::remove_dir_all(&source).unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_full_and_incremental_bundles() {
+    use std::process::Command;
+    use super_clone::bundle::{bundle_file_name, BundleEntry, BundleManifest};
+    use super_clone::git::GitOperations;
+
+    let upstream = create_source_repo("bundle-upstream", 2);
+    let root = std::env::temp_dir().join(format!("super-clone-bundle-{}", uuid::Uuid::new_v4()));
+    let git = |dir: &std::path::Path, args: &[&str]| {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&upstream, &["branch", "feature"]);
+    // The exported clone has `feature` only as refs/remotes/origin/feature
+    let source = root.join("workstation");
+    let status = Command::new("git")
+        .args(["clone", "-q"])
+        .arg(&upstream)
+        .arg(&source)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let git_ops = GitOperations::new(root.join("clones"));
+    let source_path = source.to_string_lossy().to_string();
+    let repo = Repository::new(
+        "source".to_string(),
+        "local/source".to_string(),
+        "local".to_string(),
+        Provider::GitHub,
+        "https://github.com/local/source.git".to_string(),
+        "git@github.com:local/source.git".to_string(),
+        None,
+        false,
+    );
+
+    // Full export and import
+    let first_refs = git_ops.list_refs(&source_path).unwrap();
+    let full = root.join(bundle_file_name(&repo, chrono::Utc::now()));
+    git_ops
+        .create_bundle(&source_path, &full, &[])
+        .await
+        .unwrap();
+    let target = root.join("air-gapped").join("source");
+    git_ops
+        .clone_from_bundle(&full, &target, &repo.clone_url_https)
+        .await
+        .unwrap();
+    let target_path = target.to_string_lossy().to_string();
+    assert_eq!(
+        git_ops.get_remote_url(&target_path, "origin").unwrap(),
+        Some(repo.clone_url_https.clone())
+    );
+    let imported = git_ops.list_refs(&target_path).unwrap();
+    assert_eq!(
+        imported.get("refs/remotes/origin/feature"),
+        first_refs.get("refs/remotes/origin/feature")
+    );
+
+    // Upstream moves on and the workstation fetches without merging, so only
+    // refs/remotes/origin/main has the new commit
+    git(
+        &upstream,
+        &["commit", "-q", "--allow-empty", "-m", "new work"],
+    );
+    git(&source, &["fetch", "-q"]);
+    let second_refs = git_ops.list_refs(&source_path).unwrap();
+    assert_ne!(
+        second_refs.get("refs/remotes/origin/main"),
+        second_refs.get("refs/heads/main")
+    );
+
+    // Incremental export only needs the new commit
+    let prerequisites: Vec<String> = first_refs.values().cloned().collect();
+    let incremental = root.join("incremental.bundle");
+    git_ops
+        .create_bundle(&source_path, &incremental, &prerequisites)
+        .await
+        .unwrap();
+    git_ops
+        .fetch_from_bundle(&target_path, &incremental)
+        .await
+        .unwrap();
+    let imported = git_ops.list_refs(&target_path).unwrap();
+    let upstream_main = second_refs.get("refs/remotes/origin/main");
+    assert_eq!(imported.get("refs/remotes/origin/main"), upstream_main);
+    // The checked-out branch is fast-forwarded
+    assert_eq!(imported.get("refs/heads/main"), upstream_main);
+
+    // Manifest round trip
+    let manifest = BundleManifest {
+        created_at: chrono::Utc::now(),
+        entries: vec![BundleEntry {
+            repository: repo.clone(),
+            bundle: "incremental.bundle".to_string(),
+            incremental: true,
+            refs: second_refs,
+            prerequisites,
+        }],
+    };
+    manifest.write(&root).unwrap();
+    let read = BundleManifest::read(&root).unwrap();
+    assert_eq!(read.entries.len(), 1);
+    assert_eq!(read.entries[0].repository.full_name, "local/source");
+    assert!(read.entries[0].incremental);
+
+    std::fs::remove_dir_all(&upstream).unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_sparse_clone() {
+    use std::process::Command;
+    use super_clone::git::GitOperations;
+
+    let source = create_source_repo("sparse-source", 1);
+    for dir in ["services/payments", "services/search", "libs/core"] {
+        std::fs::create_dir_all(source.join(dir)).unwrap();
+        std::fs::write(source.join(dir).join("README.md"), dir).unwrap();
+    }
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(&source)
+        .args(["add", "."])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(&source)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(["commit", "-q", "-m", "dirs"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let base = std::env::temp_dir().join(format!("super-clone-sparse-{}", uuid::Uuid::new_v4()));
+    let url = format!("file://{}", source.display());
+    let mut repo = Repository::new(
+        "monorepo".to_string(),
+        "org/monorepo".to_string(),
+        "org".to_string(),
+        Provider::GitHub,
+        url.clone(),
+        url,
+        None,
+        false,
+    );
+    repo.set_sparse_paths(&["services/payments".to_string()]);
+
+    let git_ops = GitOperations::new(base.clone());
+    let path = std::path::PathBuf::from(git_ops.clone_repository(&repo, false).await.unwrap());
+    assert!(path.join("file.txt").exists());
+    assert!(path.join("services/payments/README.md").exists());
+    assert!(!path.join("services/search").exists());
+    assert!(!path.join("libs/core").exists());
+
+    git_ops
+        .set_sparse_checkout(&path.to_string_lossy(), &["libs/core".to_string()])
+        .await
+        .unwrap();
+    assert!(path.join("libs/core/README.md").exists());
+    assert!(!path.join("services/payments").exists());
+
+    git_ops
+        .disable_sparse_checkout(&path.to_string_lossy())
+        .await
+        .unwrap();
+    assert!(path.join("services/search/README.md").exists());
+
+    // A sparse checkout git refuses leaves no clone behind
+    let mut invalid = repo.clone();
+    invalid.full_name = "org/invalid".to_string();
+    invalid.name = "invalid".to_string();
+    invalid.set_sparse_paths(&["services/*".to_string()]);
+    assert!(git_ops.clone_repository(&invalid, false).await.is_err());
+    assert!(!git_ops.get_repo_path(&invalid).exists());
+
+    std::fs::remove_dir_all(&source).unwrap();
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[tokio::test]
+async fn test_clone_with_submodules() {
+    use std::process::Command;
+    use super_clone::git::GitOperations;
+    use super_clone::CheckoutOptions;
+
+    let library = create_source_repo("submodule-library", 1);
+    let app = create_source_repo("submodule-app", 1);
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(&app)
+        .args(["-c", "protocol.file.allow=always"])
+        .args(["submodule", "add", "-q"])
+        .arg(format!("file://{}", library.display()))
+        .arg("vendor/library")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(&app)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(["commit", "-q", "-m", "add submodule"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let base =
+        std::env::temp_dir().join(format!("super-clone-submodules-{}", uuid::Uuid::new_v4()));
+    let url = format!("file://{}", app.display());
+    let repo = Repository::new(
+        "app".to_string(),
+        "org/app".to_string(),
+        "org".to_string(),
+        Provider::GitHub,
+        url.clone(),
+        url,
+        None,
+        false,
+    );
+
+    // Without --submodules the submodule is only reported
+    let plain = GitOperations::new(base.join("plain"));
+    let path = plain.clone_repository(&repo, false).await.unwrap();
+    let report = plain.update_checkout(&path).await.unwrap();
+    assert!(report.has_submodules);
+    assert!(!report.submodules_updated);
+    assert!(!report.uses_lfs);
+    assert_eq!(report.notes().len(), 1);
+    assert!(!std::path::Path::new(&path)
+        .join("vendor/library/file.txt")
+        .exists());
+
+    // Local submodule URLs need the file protocol allowed in the nested clone
+    std::env::set_var("GIT_CONFIG_COUNT", "1");
+    std::env::set_var("GIT_CONFIG_KEY_0", "protocol.file.allow");
+    std::env::set_var("GIT_CONFIG_VALUE_0", "always");
+    let recursive = GitOperations::new(base.join("plain")).with_checkout_options(CheckoutOptions {
+        submodules: true,
+        ..Default::default()
+    });
+    let report = recursive.update_checkout(&path).await.unwrap();
+    assert!(report.submodules_updated);
+    assert!(std::path::Path::new(&path)
+        .join("vendor/library/file.txt")
+        .exists());
+
+    std::fs::remove_dir_all(&library).unwrap();
+    std::fs::remove_dir_all(&app).unwrap();
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+/// Clone, status, pull, fetch and ls-remote against a local bare repository
+async fn exercise_git_backend(backend: &dyn super_clone::git::GitBackend) {
+    use std::process::Command;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use super_clone::git::{GitError, ProgressCallback};
+    use super_clone::models::CloneOptions;
+
+    let source = create_source_repo(&format!("{}-source", backend.name()), 2);
+    let bare = source.with_extension("git");
+    let status = Command::new("git")
+        .args(["clone", "-q", "--bare"])
+        .arg(&source)
+        .arg(&bare)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let url = format!("file://{}", bare.display());
+
+    let refs = backend.ls_remote(&url).await.unwrap();
+    assert!(refs.iter().any(|r| r.name == "refs/heads/main"));
+
+    let target = std::env::temp_dir().join(format!(
+        "super-clone-{}-clone-{}",
+        backend.name(),
+        uuid::Uuid::new_v4()
+    ));
+    let updates = Arc::new(AtomicUsize::new(0));
+    let counter = updates.clone();
+    let progress: ProgressCallback = Arc::new(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    backend
+        .clone_repo(
+            &url,
+            &target,
+            &CloneOptions::default(),
+            false,
+            Some(progress.clone()),
+        )
+        .await
+        .unwrap();
+    assert!(updates.load(Ordering::SeqCst) > 0);
+    let status = backend.status(&target).await.unwrap();
+    assert_eq!(status.branch.as_deref(), Some("main"));
+    assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+    assert!(status.is_clean());
+
+    // Publish a new commit to the bare repository
+    std::fs::write(source.join("file.txt"), "version 2").unwrap();
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(&source)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["commit", "-q", "-a", "-m", "commit 2"]);
+    git(&["push", "-q", bare.to_str().unwrap(), "main"]);
+
+    backend.fetch(&target, "origin", None).await.unwrap();
+    let status = backend.status(&target).await.unwrap();
+    assert_eq!((status.ahead, status.behind), (0, 1));
+
+    backend
+        .pull(&target, &CloneOptions::default(), Some(progress))
+        .await
+        .unwrap();
+    let status = backend.status(&target).await.unwrap();
+    assert!(status.is_clean());
+    assert_eq!(
+        std::fs::read_to_string(target.join("file.txt")).unwrap(),
+        "version 2"
+    );
+
+    std::fs::write(target.join("notes.txt"), "local").unwrap();
+    assert_eq!(backend.status(&target).await.unwrap().untracked, 1);
+
+    // Failures come back as structured errors
+    let missing =
+        std::env::temp_dir().join(format!("super-clone-missing-{}", uuid::Uuid::new_v4()));
+    let error = backend
+        .clone_repo(
+            &format!("file://{}", missing.display()),
+            &missing.with_extension("clone"),
+            &CloneOptions::default(),
+            false,
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, GitError::RemoteNotFound { .. }),
+        "unexpected error: {:?}",
+        error
+    );
+    let error = backend.status(&missing).await.unwrap_err();
+    assert!(!matches!(error, GitError::Unsupported(_)));
+
+    std::fs::remove_dir_all(&source).unwrap();
+    std::fs::remove_dir_all(&bare).unwrap();
+    std::fs::remove_dir_all(&target).unwrap();
+}
+
+#[tokio::test]
+async fn test_cli_git_backend() {
+    exercise_git_backend(&super_clone::git::CliBackend::new()).await;
+}
+
+#[cfg(feature = "libgit2")]
+#[tokio::test]
+async fn test_libgit2_git_backend() {
+    exercise_git_backend(&super_clone::git::libgit2::Libgit2Backend::new()).await;
+}
+
+#[tokio::test]
+async fn test_timed_out_clone_is_cleaned_up() {
+    use std::time::Duration;
+    use super_clone::git::{GitError, GitOperations, RunLimits};
+
+    let source = create_source_repo("timeout-source", 3);
+    let base = std::env::temp_dir().join(format!("super-clone-timeout-{}", uuid::Uuid::new_v4()));
+    let url = format!("file://{}", source.display());
+    let repo = Repository::new(
+        "source".to_string(),
+        "local/source".to_string(),
+        "local".to_string(),
+        Provider::GitHub,
+        url.clone(),
+        url,
+        None,
+        false,
+    );
+
+    // A zero timeout kills git as soon as it has started
+    let git_ops = GitOperations::new(base.clone()).with_limits(RunLimits {
+        timeout: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    let error = git_ops.clone_repository(&repo, false).await.unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<GitError>(),
+        Some(&GitError::TimedOut(Duration::ZERO))
+    );
+    assert!(!git_ops.get_repo_path(&repo).exists());
+
+    // Once cancelled, nothing new is started
+    let limits = RunLimits::default();
+    limits.cancel.cancel();
+    let git_ops = GitOperations::new(base.clone()).with_limits(limits);
+    let error = git_ops.clone_repository(&repo, false).await.unwrap_err();
+    assert_eq!(error.downcast_ref::<GitError>(), Some(&GitError::Cancelled));
+    assert!(!git_ops.get_repo_path(&repo).exists());
+
+    std::fs::remove_dir_all(&source).unwrap();
+    let _ = std::fs::remove_dir_all(&base);
+}
+
+#[tokio::test]
+async fn test_reset_interrupted_statuses() {
+    use super_clone::database::RepositoryDatabase;
+    use super_clone::models::CloneStatus;
+
+    let db = RepositoryDatabase::new(":memory:").await.unwrap();
+    let mut cloning = Repository::new(
+        "a".to_string(),
+        "o/a".to_string(),
+        "o".to_string(),
+        Provider::GitHub,
+        "https://github.com/o/a.git".to_string(),
+        "git@github.com:o/a.git".to_string(),
+        None,
+        false,
+    );
+    cloning.update_status(CloneStatus::Cloning);
+    let mut updating = cloning.clone();
+    updating.id = uuid::Uuid::new_v4().to_string();
+    updating.full_name = "o/b".to_string();
+    updating.update_status(CloneStatus::Updating);
+    db.create_repository(&cloning).await.unwrap();
+    db.create_repository(&updating).await.unwrap();
+
+    assert_eq!(db.reset_interrupted_statuses().await.unwrap(), 2);
+    let cloning = db.get_repository(&cloning.id).await.unwrap().unwrap();
+    assert_eq!(cloning.status, CloneStatus::NotCloned.to_string());
+    let updating = db.get_repository(&updating.id).await.unwrap().unwrap();
+    assert_eq!(updating.status, CloneStatus::Cloned.to_string());
+}
+
+#[tokio::test]
+async fn test_job_journal_survives_interruption() {
+    use super_clone::database::RepositoryDatabase;
+    use super_clone::models::{Job, JobKind, JobStatus, JobTask, TaskStatus};
+
+    let db = RepositoryDatabase::new(":memory:").await.unwrap();
+    let mut tasks = Vec::new();
+    let job = Job::new(JobKind::Clone, "clone-org github acme".to_string());
+    for (position, name) in ["a", "b", "c"].iter().enumerate() {
+        let repo = Repository::new(
+            name.to_string(),
+            format!("acme/{}", name),
+            "acme".to_string(),
+            Provider::GitHub,
+            format!("https://github.com/acme/{}.git", name),
+            format!("git@github.com:acme/{}.git", name),
+            None,
+            false,
+        );
+        db.create_repository(&repo).await.unwrap();
+        tasks.push(JobTask::new(job.id.clone(), repo.id, position as u32));
+    }
+    db.create_job(&job, &tasks).await.unwrap();
+
+    // The first clone succeeds, the second fails and the process dies during the third
+    tasks[0].start();
+    tasks[0].finish(TaskStatus::Done, None);
+    tasks[1].start();
+    tasks[1].finish(TaskStatus::Failed, Some("network down".to_string()));
+    tasks[2].start();
+    for task in &tasks {
+        db.update_job_task(task).await.unwrap();
+    }
+
+    let interrupted = db.reset_interrupted_jobs().await.unwrap();
+    assert_eq!(interrupted.len(), 1);
+    assert_eq!(interrupted[0].id, job.id);
+
+    let found = db.find_job(job.short_id()).await.unwrap().unwrap();
+    assert_eq!(found.status, JobStatus::Interrupted.to_string());
+    assert!(found.finished_at.is_some());
+
+    let stored = db.get_job_tasks(&job.id).await.unwrap();
+    let statuses: Vec<&str> = stored.iter().map(|t| t.status.as_str()).collect();
+    assert_eq!(statuses, vec!["done", "failed", "pending"]);
+    assert_eq!(stored[1].last_error.as_deref(), Some("network down"));
+    assert_eq!(stored.iter().filter(|t| t.is_unfinished()).count(), 2);
+
+    let with_failures = db.get_latest_job_with_failures().await.unwrap().unwrap();
+    assert_eq!(with_failures.id, job.id);
+    assert!(db.find_job("does-not-exist").await.unwrap().is_none());
+    assert_eq!(db.get_jobs(10).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_operation_history() {
+    use super_clone::database::RepositoryDatabase;
+    use super_clone::models::{Operation, OperationKind, OperationOutcome};
+
+    let db = RepositoryDatabase::new(":memory:").await.unwrap();
+    let mut repos = Vec::new();
+    for name in ["broken", "recovered"] {
+        let repo = Repository::new(
+            name.to_string(),
+            format!("o/{}", name),
+            "o".to_string(),
+            Provider::GitHub,
+            format!("https://github.com/o/{}.git", name),
+            format!("git@github.com:o/{}.git", name),
+            None,
+            false,
+        );
+        db.create_repository(&repo).await.unwrap();
+        repos.push(repo);
+    }
+
+    let record = |repo: &Repository, kind, outcome, offset_secs: i64| {
+        let mut operation = Operation::start(repo.id.clone(), kind);
+        operation.started_at -= chrono::Duration::seconds(offset_secs);
+        let (exit_code, error) = match outcome {
+            OperationOutcome::Failed => {
+                (Some(128), Some("fatal: repository not found".to_string()))
+            }
+            _ => (Some(0), None),
+        };
+        operation.finish(outcome, exit_code, error);
+        operation
+    };
+    for operation in [
+        record(
+            &repos[0],
+            OperationKind::Clone,
+            OperationOutcome::Failed,
+            20,
+        ),
+        record(
+            &repos[0],
+            OperationKind::Clone,
+            OperationOutcome::Failed,
+            10,
+        ),
+        record(
+            &repos[1],
+            OperationKind::Clone,
+            OperationOutcome::Failed,
+            20,
+        ),
+        record(
+            &repos[1],
+            OperationKind::Clone,
+            OperationOutcome::Success,
+            10,
+        ),
+    ] {
+        db.create_operation(&operation).await.unwrap();
+    }
+
+    let history = db.get_operations(&repos[1].id, 10).await.unwrap();
+    let outcomes: Vec<&str> = history.iter().map(|o| o.outcome.as_str()).collect();
+    assert_eq!(outcomes, vec!["success", "failed"]);
+
+    let failures = db.get_current_failures().await.unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].repository_id, repos[0].id);
+    assert_eq!(failures[0].exit_code, Some(128));
+
+    // History goes away with its repository
+    db.delete_repository(&repos[0].id).await.unwrap();
+    assert!(db.get_current_failures().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_grep_across_repositories() {
+    use super_clone::exec::ExecTarget;
+    use super_clone::grep::{search, GrepOptions};
+
+    // file.txt holds "version <n>" for the last commit
+    let first = create_source_repo("grep-first", 1);
+    let second = create_source_repo("grep-second", 2);
+    let targets = vec![
+        ExecTarget {
+            name: "o/first".to_string(),
+            path: first.clone(),
+        },
+        ExecTarget {
+            name: "o/second".to_string(),
+            path: second.clone(),
+        },
+        ExecTarget {
+            name: "o/missing".to_string(),
+            path: first.join("missing"),
+        },
+    ];
+
+    let options = GrepOptions {
+        pattern: "version [1-9]".to_string(),
+        parallel: 2,
+        ..Default::default()
+    };
+    let results = search(&targets, &options).await.unwrap();
+    assert_eq!(results.len(), 3);
+    assert!(results[0].matches.is_empty());
+    assert!(results[0].error.is_none());
+    assert_eq!(results[1].matches.len(), 1);
+    assert_eq!(results[1].matches[0].path, "file.txt");
+    assert_eq!(results[1].matches[0].line, 1);
+    assert_eq!(results[1].matches[0].text, "version 1");
+    assert!(results[2].error.is_some());
+
+    let options = GrepOptions {
+        pattern: "VERSION".to_string(),
+        ignore_case: true,
+        paths: vec!["*.md".to_string()],
+        ..Default::default()
+    };
+    let results = search(&targets[..2], &options).await.unwrap();
+    assert!(results.iter().all(|r| r.matches.is_empty()));
+
+    let _ = std::fs::remove_dir_all(&first);
+    let _ = std::fs::remove_dir_all(&second);
+}
+
+#[tokio::test]
+async fn test_verify_findings_and_fix() {
+    use std::collections::HashMap;
+    use std::process::Command;
+    use super_clone::database::RepositoryDatabase;
+    use super_clone::git::GitOperations;
+    use super_clone::models::CloneStatus;
+    use super_clone::verify::{verify, Problem};
+
+    let root = std::env::temp_dir().join(format!("super-clone-verify-{}", uuid::Uuid::new_v4()));
+    let git_ops = GitOperations::new(root.clone());
+    let db = RepositoryDatabase::new(":memory:").await.unwrap();
+
+    let mut sources = Vec::new();
+    let mut repos = Vec::new();
+    for name in ["ok", "gone", "plain", "moved", "unrecorded"] {
+        let source = create_source_repo(&format!("verify-{}", name), 1);
+        let url = format!("file://{}", source.display());
+        let mut repo = Repository::new(
+            name.to_string(),
+            format!("acme/{}", name),
+            "acme".to_string(),
+            Provider::GitHub,
+            url.clone(),
+            url,
+            None,
+            false,
+        );
+        let path = git_ops.clone_repository(&repo, false).await.unwrap();
+        if name != "unrecorded" {
+            repo.set_local_path(path);
+            repo.update_status(CloneStatus::Cloned);
+        }
+        db.create_repository(&repo).await.unwrap();
+        sources.push(source);
+        repos.push(repo);
+    }
+    let path_of = |name: &str| root.join("acme").join(name);
+    let url_of = |name: &str| {
+        repos
+            .iter()
+            .find(|r| r.name == name)
+            .unwrap()
+            .clone_url_https
+            .clone()
+    };
+    let git = |dir: &std::path::Path, args: &[&str]| {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    std::fs::remove_dir_all(path_of("gone")).unwrap();
+    std::fs::remove_dir_all(path_of("plain").join(".git")).unwrap();
+    git(
+        &path_of("moved"),
+        &["remote", "set-url", "origin", &url_of("ok")],
+    );
+    std::fs::create_dir_all(root.join("stray")).unwrap();
+    git(&root.join("stray"), &["init", "-q"]);
+
+    // The clone path holds a clone of another repository
+    let moved = repos.iter().find(|r| r.name == "moved").unwrap();
+    let err = git_ops.clone_repository(moved, false).await.unwrap_err();
+    assert!(err.to_string().contains("not acme/moved"));
+
+    let findings = verify(&repos, &root, &git_ops).unwrap();
+    let problems: HashMap<String, Problem> = findings
+        .iter()
+        .map(|f| {
+            let label = f
+                .repository
+                .as_ref()
+                .map(|r| r.full_name.clone())
+                .unwrap_or_else(|| f.path.display().to_string());
+            (label, f.problem)
+        })
+        .collect();
+    assert_eq!(findings.len(), 5);
+    assert!(!problems.contains_key("acme/ok"));
+    assert_eq!(problems["acme/gone"], Problem::MissingPath);
+    assert_eq!(problems["acme/plain"], Problem::NotGitRepository);
+    assert_eq!(problems["acme/moved"], Problem::OriginMismatch);
+    assert_eq!(problems["acme/unrecorded"], Problem::UnrecordedClone);
+    let stray = root.join("stray").display().to_string();
+    assert_eq!(problems[&stray], Problem::UnknownClone);
+
+    for finding in &findings {
+        if let Some(repo) = finding.fixed_repository() {
+            db.update_repository(&repo).await.unwrap();
+        }
+    }
+    let unrecorded = db
+        .get_repository_by_full_name("acme/unrecorded")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unrecorded.status, "cloned");
+    assert_eq!(
+        unrecorded.local_path.as_deref(),
+        Some(path_of("unrecorded").to_string_lossy().as_ref())
+    );
+    let gone = db
+        .get_repository_by_full_name("acme/gone")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(gone.status, "not_cloned");
+    assert_eq!(gone.local_path, None);
+
+    // Only the clones no record points at are left
+    let repos = db.get_all_repositories().await.unwrap();
+    let findings = verify(&repos, &root, &git_ops).unwrap();
+    assert_eq!(findings.len(), 2);
+    assert!(findings.iter().all(|f| f.problem == Problem::UnknownClone));
+
+    std::fs::remove_dir_all(&root).unwrap();
+    for source in sources {
+        std::fs::remove_dir_all(source).unwrap();
+    }
+}
+
+#[test]
+fn test_gists_with_the_same_description_get_their_own_paths() {
+    use super_clone::git::GitOperations;
+    use super_clone::providers::github::gists_from_json;
+
+    let body = r#"[
+        {"id": "aa11", "description": "Dotfiles", "public": true,
+         "owner": {"login": "alice"}, "git_pull_url": "https://gist.github.com/aa11.git"},
+        {"id": "bb22", "description": "dotfiles!", "public": false,
+         "owner": {"login": "alice"}, "git_pull_url": "https://gist.github.com/bb22.git"},
+        {"id": "cc33", "description": null, "public": true,
+         "git_pull_url": "https://gist.github.com/cc33.git"}
+    ]"#;
+    let gists = gists_from_json(body, Some("alice")).unwrap();
+    let names: Vec<_> = gists.iter().map(|gist| gist.name.as_str()).collect();
+    assert_eq!(names, vec!["dotfiles-aa11", "dotfiles-bb22", "cc33"]);
+
+    let git_ops = GitOperations::new(std::path::PathBuf::from("/work"));
+    let paths: std::collections::HashSet<_> = gists
+        .iter()
+        .map(|gist| git_ops.get_repo_path(gist))
+        .collect();
+    assert_eq!(paths.len(), 3);
+    assert!(paths.contains(std::path::Path::new("/work/gists/alice/dotfiles-aa11")));
+}
+
+#[tokio::test]
+async fn test_wiki_exists_only_once_it_has_pages() {
+    use std::process::Command;
+    use super_clone::git::GitOperations;
+
+    let source = create_source_repo("wiki-project", 1);
+    let url = format!("file://{}.git", source.display());
+    let mut repo = Repository::new(
+        "project".to_string(),
+        "local/project".to_string(),
+        "local".to_string(),
+        Provider::GitHub,
+        url.clone(),
+        url,
+        None,
+        false,
+    );
+    repo.has_wiki = true;
+    let wiki = repo.wiki_repository();
+    let git_ops = GitOperations::new(std::env::temp_dir());
+    assert!(!git_ops.wiki_exists(&wiki, false).await.unwrap());
+
+    // The first page creates the wiki repository
+    let wiki_dir = std::path::PathBuf::from(format!("{}.wiki.git", source.display()));
+    let status = Command::new("git")
+        .args(["clone", "-q", "--bare"])
+        .arg(&source)
+        .arg(&wiki_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(git_ops.wiki_exists(&wiki, false).await.unwrap());
+
+    std::fs::remove_dir_all(&source).unwrap();
+    std::fs::remove_dir_all(&wiki_dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_private_repository_is_not_missing_without_a_token() {
+    use super_clone::providers::github::GitHubClient;
+    use super_clone::providers::gitlab::GitLabClient;
+    use super_clone::providers::RepositoryProvider;
+
+    let private = |provider: Provider| {
+        Repository::new(
+            "internal".to_string(),
+            "acme/internal".to_string(),
+            "acme".to_string(),
+            provider,
+            "https://example.com/acme/internal.git".to_string(),
+            "git@example.com:acme/internal.git".to_string(),
+            None,
+            true,
+        )
+    };
+    // Anonymous lookups of private repositories get a 404 whether or not they
+    // still exist, so no state is reported for them
+    let github = GitHubClient::new(None).unwrap();
+    let error = github
+        .repository_state(&private(Provider::GitHub))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("token"));
+    let gitlab = GitLabClient::new(None, None).unwrap();
+    assert!(gitlab
+        .repository_state(&private(Provider::GitLab))
+        .await
+        .is_err());
+}